@@ -1,4 +1,4 @@
-use ink_rpc::{RpcRequest, RpcResponse};
+use ink_rpc::{RpcError, RpcRequest, RpcResponse};
 use serde_json::json;
 
 fn main() {
@@ -15,7 +15,7 @@ fn main() {
     println!("{:?}\n", simple_request);
     
     // Create corresponding response
-    let mut simple_response = RpcResponse::new(simple_request.id());
+    let mut simple_response = RpcResponse::new(simple_request.id().unwrap());
     simple_response.set_result(json!({
         "server": "ink-rpc-server",
         "version": "1.0.0",
@@ -44,7 +44,7 @@ fn main() {
     println!("{:?}\n", transfer_request);
     
     // Create successful response
-    let mut transfer_response = RpcResponse::new(transfer_request.id());
+    let mut transfer_response = RpcResponse::new(transfer_request.id().unwrap());
     transfer_response.set_result(json!({
         "transaction_id": "txn_abc123def456",
         "status": "completed",
@@ -71,16 +71,14 @@ fn main() {
     println!("{:?}\n", invalid_request);
     
     // Create error response
-    let mut error_response = RpcResponse::new(invalid_request.id());
-    error_response.set_error(json!({
-        "code": -32001,
-        "message": "Insufficient funds",
-        "data": {
+    let mut error_response = RpcResponse::new(invalid_request.id().unwrap());
+    error_response.set_error(
+        RpcError::server_error(-32001, "Insufficient funds").with_data(json!({
             "requested_amount": 5000.0,
             "available_balance": 1749.25,
             "account": "acc_123456"
-        }
-    }));
+        })),
+    );
     
     println!("Error Response:");
     println!("{:?}\n", error_response);
@@ -89,7 +87,7 @@ fn main() {
     println!("Example 4: Batch request simulation");
     println!("-----------------------------------");
     
-    let requests = vec![
+    let requests = [
         {
             let mut req = RpcRequest::new();
             req.set_method("get_balance".to_string())
@@ -120,14 +118,14 @@ fn main() {
     }
     
     // Create corresponding responses
-    let responses = vec![
+    let responses = [
         {
-            let mut resp = RpcResponse::new(requests[0].id());
+            let mut resp = RpcResponse::new(requests[0].id().unwrap());
             resp.set_result(json!({"balance": 1749.25, "currency": "USD"}));
             resp
         },
         {
-            let mut resp = RpcResponse::new(requests[1].id());
+            let mut resp = RpcResponse::new(requests[1].id().unwrap());
             resp.set_result(json!({
                 "transactions": [
                     {
@@ -147,7 +145,7 @@ fn main() {
             resp
         },
         {
-            let mut resp = RpcResponse::new(requests[2].id());
+            let mut resp = RpcResponse::new(requests[2].id().unwrap());
             resp.set_result(json!({
                 "account_id": "acc_123456",
                 "account_type": "checking",
@@ -0,0 +1,45 @@
+//! Spawns the `stdio_plugin` example as a child process and calls it.
+//!
+//! Build the plugin first, then run this example:
+//!
+//! ```text
+//! cargo build --example stdio_plugin
+//! cargo run --example child_client
+//! ```
+
+use ink_rpc::ChildProcess;
+use serde_json::json;
+use std::process::Command;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Examples are built into the same directory
+    let plugin = std::env::current_exe()?.with_file_name("stdio_plugin");
+
+    let mut process = ChildProcess::new(Command::new(plugin));
+    process
+        .set_restart_policy(|status, restarts| {
+            eprintln!("plugin exited ({}), restart #{}", status, restarts + 1);
+            restarts < 3
+        })
+        .set_on_restart(|_| eprintln!("plugin restarted"));
+
+    let child = process.spawn()?;
+    println!("Spawned plugin with pid {}", child.transport().id());
+
+    let sum = child.client().call("add", json!([2, 3])).wait()?;
+    println!("add(2, 3) = {}", sum);
+
+    let greeting = child.client().call("greet", json!(["world"])).wait()?;
+    println!("greet(\"world\") = {}", greeting);
+
+    // A manual restart replaces the process; the restart hook runs again
+    child.restart()?;
+    println!("Restarted plugin with pid {}", child.transport().id());
+    let sum = child.client().call("add", json!([40, 2])).wait()?;
+    println!("add(40, 2) = {}", sum);
+
+    // Closing stdin lets the plugin shut down on its own
+    let status = child.close()?;
+    println!("Plugin exited with {}", status);
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! A plugin that serves JSON-RPC over stdin/stdout.
+//!
+//! Try it by piping a request into it:
+//!
+//! ```text
+//! echo '{"jsonrpc":"2.0","method":"add","params":[2,3],"id":1}' | cargo run --example stdio_plugin
+//! ```
+
+use ink_rpc::{RpcError, RpcRouter, serve_stdio};
+
+fn main() {
+    let mut router = RpcRouter::new();
+    router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+    router.register("greet", |(name,): (String,)| {
+        // Logs go to stderr; stdout carries only JSON-RPC messages
+        eprintln!("greeting {}", name);
+        Ok::<_, RpcError>(format!("Hello, {}!", name))
+    });
+
+    eprintln!("stdio_plugin: ready");
+    if let Err(error) = serve_stdio(&router) {
+        eprintln!("stdio_plugin: {}", error);
+        std::process::exit(1);
+    }
+    eprintln!("stdio_plugin: stdin closed, exiting");
+}
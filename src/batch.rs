@@ -0,0 +1,660 @@
+use crate::validation::{self, ValidationError};
+use crate::{RpcId, RpcRequest, RpcResponse};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::str::FromStr;
+
+// ===========================================================================
+// STRUCT: RpcBatchRequest
+// ===
+
+/// Represents a JSON-RPC 2.0 batch of requests and notifications.
+///
+/// A batch is serialized as a JSON array of request objects. It may freely mix
+/// requests and notifications; only the requests receive a response.
+///
+/// A batch parsed from JSON keeps the members that failed validation apart
+/// from its requests: the JSON-RPC 2.0 specification answers each of them
+/// with its own "Invalid Request" error while the valid members still run.
+#[derive(Clone, Default)]
+pub struct RpcBatchRequest {
+    requests: Vec<RpcRequest>,
+    invalid: Vec<ValidationError>,
+}
+
+impl RpcBatchRequest {
+    /// Creates a new, empty batch.
+    ///
+    /// An empty batch is not valid on the wire; add at least one request with
+    /// `push()` before sending it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcBatchRequest;
+    /// let batch = RpcBatchRequest::new();
+    /// assert!(batch.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            requests: Vec::new(),
+            invalid: Vec::new(),
+        }
+    }
+
+    /// Adds a request or notification to the batch.
+    ///
+    /// This function uses the builder pattern, returning a mutable reference
+    /// to self for method chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to add
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcBatchRequest, RpcRequest};
+    /// let mut batch = RpcBatchRequest::new();
+    /// batch.push(RpcRequest::new()).push(RpcRequest::notification());
+    /// assert_eq!(batch.len(), 2);
+    /// ```
+    pub fn push(&mut self, request: RpcRequest) -> &mut Self {
+        self.requests.push(request);
+        self
+    }
+
+    /// Returns the requests and notifications in this batch.
+    ///
+    /// # Returns
+    ///
+    /// A slice containing the batch members in their original order.
+    pub fn requests(&self) -> &[RpcRequest] {
+        &self.requests
+    }
+
+    /// Returns the members of this batch that failed validation.
+    ///
+    /// These are only found in batches parsed with `from_json()`; they are
+    /// left out of `requests()` and `to_json()`.
+    ///
+    /// # Returns
+    ///
+    /// A slice containing the validation error of every invalid member, in
+    /// their original order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcBatchRequest;
+    /// use serde_json::json;
+    ///
+    /// let batch = RpcBatchRequest::from_json(json!([
+    ///     {"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1},
+    ///     {"foo": "boo"}
+    /// ]))
+    /// .unwrap();
+    /// assert_eq!(batch.requests().len(), 1);
+    /// assert_eq!(batch.invalid()[0].code(), -32600);
+    /// ```
+    pub fn invalid(&self) -> &[ValidationError] {
+        &self.invalid
+    }
+
+    /// Returns the number of members in this batch, including the invalid
+    /// ones.
+    pub fn len(&self) -> usize {
+        self.requests.len() + self.invalid.len()
+    }
+
+    /// Checks if this batch has no members.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if any member of this batch expects a response.
+    ///
+    /// A batch made only of notifications produces no response at all.
+    /// Invalid members are always answered.
+    ///
+    /// # Returns
+    ///
+    /// `true` if at least one member is a request or is invalid, `false`
+    /// otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcBatchRequest, RpcRequest};
+    /// let mut batch = RpcBatchRequest::new();
+    /// batch.push(RpcRequest::notification());
+    /// assert!(!batch.expects_response());
+    /// ```
+    pub fn expects_response(&self) -> bool {
+        !self.invalid.is_empty()
+            || self
+                .requests
+                .iter()
+                .any(|request| !request.is_notification())
+    }
+
+    /// Converts the batch to a JSON array.
+    ///
+    /// # Returns
+    ///
+    /// A `JsonValue::Array` containing the JSON form of every member.
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.requests.iter().map(RpcRequest::to_json).collect())
+    }
+
+    /// Creates a batch from a JSON array.
+    ///
+    /// The JSON must be a non-empty array. An empty batch is rejected with the
+    /// "Invalid Request" (-32600) error required by the JSON-RPC 2.0
+    /// specification; use `ValidationError::to_response()` to build the
+    /// reply. Members that are not valid requests or notifications do not
+    /// reject the batch; they are kept in `invalid()` so each can be answered
+    /// on its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - A JSON array containing the batch members
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcBatchRequest)` - Successfully parsed batch
+    /// * `Err(ValidationError)` - The JSON value is not a non-empty array
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcBatchRequest;
+    /// use serde_json::json;
+    ///
+    /// let batch = RpcBatchRequest::from_json(json!([
+    ///     {"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1},
+    ///     {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]}
+    /// ]))
+    /// .unwrap();
+    /// assert_eq!(batch.len(), 2);
+    ///
+    /// let error = RpcBatchRequest::from_json(json!([])).unwrap_err();
+    /// assert_eq!(error.to_response().error().unwrap().code(), -32600);
+    /// ```
+    pub fn from_json(json: JsonValue) -> Result<Self, ValidationError> {
+        let mut batch = Self::new();
+        for member in validation::validate_batch(json)? {
+            match RpcRequest::from_json(member) {
+                Ok(request) => batch.requests.push(request),
+                Err(error) => batch.invalid.push(error),
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Pairs every request in this batch with its response.
+    ///
+    /// Responses in a batch may arrive in any order, so they are matched back
+    /// to their requests by ID. Notifications are skipped since they never
+    /// receive a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `responses` - The batch response received for this batch
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(request, response)` pairs in request order. The response
+    /// is `None` if the peer did not answer that request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcBatchRequest, RpcBatchResponse, RpcRequest, RpcResponse};
+    ///
+    /// let mut batch = RpcBatchRequest::new();
+    /// batch.push(RpcRequest::new()).push(RpcRequest::new());
+    ///
+    /// let mut responses = RpcBatchResponse::new();
+    /// responses.push(RpcResponse::new(batch.requests()[1].id().unwrap()));
+    ///
+    /// let pairs = batch.pair_responses(&responses);
+    /// assert!(pairs[0].1.is_none());
+    /// assert!(pairs[1].1.is_some());
+    /// ```
+    pub fn pair_responses<'a>(
+        &'a self,
+        responses: &'a RpcBatchResponse,
+    ) -> Vec<(&'a RpcRequest, Option<&'a RpcResponse>)> {
+        self.requests
+            .iter()
+            .filter_map(|request| request.id().map(|id| (request, responses.get(id))))
+            .collect()
+    }
+}
+
+impl From<Vec<RpcRequest>> for RpcBatchRequest {
+    fn from(requests: Vec<RpcRequest>) -> Self {
+        Self {
+            requests,
+            invalid: Vec::new(),
+        }
+    }
+}
+
+impl FromIterator<RpcRequest> for RpcBatchRequest {
+    fn from_iter<I: IntoIterator<Item = RpcRequest>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl IntoIterator for RpcBatchRequest {
+    type Item = RpcRequest;
+    type IntoIter = std::vec::IntoIter<RpcRequest>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.requests.into_iter()
+    }
+}
+
+impl FromStr for RpcBatchRequest {
+    type Err = ValidationError;
+
+    /// Parses and validates a batch from JSON text.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_json(validation::parse_json(text)?)
+    }
+}
+
+impl fmt::Debug for RpcBatchRequest {
+    /// Formats the batch as a pretty-printed JSON array.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json_string = serde_json::to_string_pretty(&self.to_json()).unwrap_or("[]".to_string());
+        write!(f, "{}", &json_string)
+    }
+}
+
+impl fmt::Display for RpcBatchRequest {
+    /// Formats the batch as a JSON array.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json_string = serde_json::to_string(&self.to_json()).unwrap_or("[]".to_string());
+        write!(f, "{}", &json_string)
+    }
+}
+
+// ===========================================================================
+// STRUCT: RpcBatchResponse
+// ===
+
+/// Represents a JSON-RPC 2.0 batch of responses.
+///
+/// A batch response is serialized as a JSON array of response objects. The
+/// responses may be in any order relative to the requests of the batch, so
+/// they are matched back to requests by ID.
+#[derive(Clone, Default)]
+pub struct RpcBatchResponse {
+    responses: Vec<RpcResponse>,
+}
+
+impl RpcBatchResponse {
+    /// Creates a new, empty batch response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcBatchResponse;
+    /// let responses = RpcBatchResponse::new();
+    /// assert!(responses.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            responses: Vec::new(),
+        }
+    }
+
+    /// Adds a response to the batch.
+    ///
+    /// This function uses the builder pattern, returning a mutable reference
+    /// to self for method chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response to add
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn push(&mut self, response: RpcResponse) -> &mut Self {
+        self.responses.push(response);
+        self
+    }
+
+    /// Returns the responses in this batch.
+    ///
+    /// # Returns
+    ///
+    /// A slice containing the responses in the order they were received.
+    pub fn responses(&self) -> &[RpcResponse] {
+        &self.responses
+    }
+
+    /// Returns the number of responses in this batch.
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Checks if this batch has no responses.
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Finds the response for the request with the given ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the request whose response is wanted
+    ///
+    /// # Returns
+    ///
+    /// `Some(&RpcResponse)` if a response with that ID exists, `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcBatchResponse, RpcId, RpcResponse};
+    ///
+    /// let mut responses = RpcBatchResponse::new();
+    /// responses.push(RpcResponse::new("a")).push(RpcResponse::new(2));
+    /// assert!(responses.get(&RpcId::from("a")).is_some());
+    /// assert!(responses.get(&RpcId::from(3)).is_none());
+    /// ```
+    pub fn get(&self, id: &RpcId) -> Option<&RpcResponse> {
+        self.responses.iter().find(|response| response.id() == id)
+    }
+
+    /// Converts the batch response to a JSON array.
+    ///
+    /// # Returns
+    ///
+    /// A `JsonValue::Array` containing the JSON form of every response.
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.responses.iter().map(RpcResponse::to_json).collect())
+    }
+
+    /// Creates a batch response from a JSON array.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - A JSON array containing the responses
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcBatchResponse)` - Successfully parsed batch response
+    /// * `Err(ValidationError)` - The JSON value is not a non-empty array, or a member is not a valid response
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcBatchResponse, RpcId};
+    /// use serde_json::json;
+    ///
+    /// let responses = RpcBatchResponse::from_json(json!([
+    ///     {"jsonrpc": "2.0", "result": 3, "id": 1}
+    /// ]))
+    /// .unwrap();
+    /// assert_eq!(responses.get(&RpcId::from(1)).unwrap().result(), Some(&json!(3)));
+    /// ```
+    pub fn from_json(json: JsonValue) -> Result<Self, ValidationError> {
+        validation::validate_batch(json)?
+            .into_iter()
+            .map(RpcResponse::from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|responses| Self { responses })
+    }
+}
+
+impl From<Vec<RpcResponse>> for RpcBatchResponse {
+    fn from(responses: Vec<RpcResponse>) -> Self {
+        Self { responses }
+    }
+}
+
+impl FromIterator<RpcResponse> for RpcBatchResponse {
+    fn from_iter<I: IntoIterator<Item = RpcResponse>>(iter: I) -> Self {
+        Self {
+            responses: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for RpcBatchResponse {
+    type Item = RpcResponse;
+    type IntoIter = std::vec::IntoIter<RpcResponse>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.responses.into_iter()
+    }
+}
+
+impl FromStr for RpcBatchResponse {
+    type Err = ValidationError;
+
+    /// Parses and validates a batch response from JSON text.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_json(validation::parse_json(text)?)
+    }
+}
+
+impl fmt::Debug for RpcBatchResponse {
+    /// Formats the batch response as a pretty-printed JSON array.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json_string = serde_json::to_string_pretty(&self.to_json()).unwrap_or("[]".to_string());
+        write!(f, "{}", &json_string)
+    }
+}
+
+impl fmt::Display for RpcBatchResponse {
+    /// Formats the batch response as a JSON array.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json_string = serde_json::to_string(&self.to_json()).unwrap_or("[]".to_string());
+        write!(f, "{}", &json_string)
+    }
+}
+
+// ===========================================================================
+// TESTS: RpcBatchRequest, RpcBatchResponse
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RpcError, ValidationErrorKind};
+    use serde_json::json;
+
+    fn request(method: &str) -> RpcRequest {
+        let mut request = RpcRequest::new();
+        request.set_method(method.to_string());
+        request
+    }
+
+    fn notification(method: &str) -> RpcRequest {
+        let mut notification = RpcRequest::notification();
+        notification.set_method(method.to_string());
+        notification
+    }
+
+    // ---------------------------------------------------------------------------
+    // RpcBatchRequest Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_batch_request_building() {
+        let mut batch = RpcBatchRequest::new();
+        assert!(batch.is_empty());
+        assert!(!batch.expects_response());
+
+        batch.push(notification("log")).push(request("sum"));
+        assert_eq!(batch.len(), 2);
+        assert!(batch.expects_response());
+        assert_eq!(batch.requests()[0].method(), "log");
+        assert_eq!(batch.requests()[1].method(), "sum");
+    }
+
+    #[test]
+    fn test_batch_request_serialization() {
+        let batch: RpcBatchRequest = vec![request("sum"), notification("log")].into();
+
+        let json = batch.to_json();
+        assert!(json.is_array());
+        assert_eq!(json[0]["method"], "sum");
+        assert!(json[0].get("id").is_some());
+        assert_eq!(json[1]["method"], "log");
+        assert!(json[1].get("id").is_none());
+
+        // Test roundtrip
+        let parsed = RpcBatchRequest::from_json(json).expect("Failed to parse batch");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.requests()[0].id(), batch.requests()[0].id());
+        assert!(parsed.requests()[1].is_notification());
+    }
+
+    #[test]
+    fn test_batch_request_rejects_empty() {
+        let error = RpcBatchRequest::from_json(json!([])).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::EmptyBatch);
+
+        let response = error.to_response();
+        assert_eq!(response.id(), &RpcId::Null);
+        assert_eq!(response.error().unwrap().code(), RpcError::INVALID_REQUEST);
+        assert_eq!(response.error().unwrap().message(), "Invalid Request");
+    }
+
+    #[test]
+    fn test_batch_request_rejects_invalid() {
+        // Not an array
+        let error = RpcBatchRequest::from_json(json!({"jsonrpc": "2.0"})).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+        assert_eq!(error.code(), -32600);
+
+        // Invalid JSON text
+        let error = "[{".parse::<RpcBatchRequest>().unwrap_err();
+        assert_eq!(error.code(), -32700);
+    }
+
+    #[test]
+    fn test_batch_request_keeps_invalid_members() {
+        let batch = RpcBatchRequest::from_json(json!([
+            {"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1},
+            {"foo": 1},
+            {"jsonrpc": "1.0", "method": "sum", "id": 2}
+        ]))
+        .expect("Failed to parse batch");
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.requests().len(), 1);
+        assert_eq!(batch.requests()[0].method(), "sum");
+
+        // Each invalid member is answered on its own, echoing its ID if it had one
+        assert_eq!(batch.invalid().len(), 2);
+        assert_eq!(batch.invalid()[0].id(), &RpcId::Null);
+        assert_eq!(batch.invalid()[1].id(), &RpcId::Number(2));
+        assert_eq!(
+            batch.invalid()[1].kind(),
+            ValidationErrorKind::InvalidVersion
+        );
+
+        // A batch of only invalid members still parses
+        let batch = RpcBatchRequest::from_json(json!([1, 2, 3])).unwrap();
+        assert!(batch.requests().is_empty());
+        assert_eq!(batch.invalid().len(), 3);
+        assert!(batch.expects_response());
+        assert!(
+            batch
+                .invalid()
+                .iter()
+                .all(|error| error.code() == RpcError::INVALID_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_batch_request_display() {
+        let batch: RpcBatchRequest = vec![request("sum")].into_iter().collect();
+
+        let display_string = format!("{}", batch);
+        assert!(display_string.starts_with('['));
+        assert!(display_string.ends_with(']'));
+
+        let parsed: JsonValue = serde_json::from_str(&display_string).unwrap();
+        assert_eq!(parsed[0]["method"], "sum");
+    }
+
+    // ---------------------------------------------------------------------------
+    // RpcBatchResponse Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_batch_response_lookup() {
+        let mut responses = RpcBatchResponse::new();
+        responses
+            .push(RpcResponse::new(2))
+            .push(RpcResponse::new("two"));
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses.get(&RpcId::from(2)).unwrap().id(),
+            &RpcId::from(2)
+        );
+        assert_eq!(
+            responses.get(&RpcId::from("two")).unwrap().id(),
+            &RpcId::from("two")
+        );
+        assert!(responses.get(&RpcId::from(3)).is_none());
+    }
+
+    #[test]
+    fn test_batch_response_serialization() {
+        let json = json!([
+            {"jsonrpc": "2.0", "result": 7, "id": "1"},
+            {"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "2"}
+        ]);
+
+        let responses = RpcBatchResponse::from_json(json.clone()).expect("Failed to parse");
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses.to_json(), json);
+
+        assert!(RpcBatchResponse::from_json(json!({"result": 1})).is_err());
+        assert!(RpcBatchResponse::from_json(json!([])).is_err());
+        assert!(RpcBatchResponse::from_json(json!([{"jsonrpc": "2.0", "id": 1}])).is_err());
+    }
+
+    #[test]
+    fn test_pair_responses_out_of_order() {
+        let batch: RpcBatchRequest = vec![
+            request("a"),
+            notification("log"),
+            request("b"),
+            request("c"),
+        ]
+        .into();
+
+        // Responses arrive out of order and one is missing
+        let mut responses = RpcBatchResponse::new();
+        let mut response_c = RpcResponse::new(batch.requests()[3].id().unwrap());
+        response_c.set_result(json!("c"));
+        let mut response_a = RpcResponse::new(batch.requests()[0].id().unwrap());
+        response_a.set_result(json!("a"));
+        responses.push(response_c).push(response_a);
+
+        let pairs = batch.pair_responses(&responses);
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].0.method(), "a");
+        assert_eq!(pairs[0].1.unwrap().result(), Some(&json!("a")));
+        assert_eq!(pairs[1].0.method(), "b");
+        assert!(pairs[1].1.is_none());
+        assert_eq!(pairs[2].0.method(), "c");
+        assert_eq!(pairs[2].1.unwrap().result(), Some(&json!("c")));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{RpcError, RpcId};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// The method of the notification cancelling a request, as in the Language
+/// Server Protocol. Its params are `{"id": id}`.
+pub const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+// ===========================================================================
+// STRUCT: CancellationToken
+// ===
+
+/// Tells a handler that the client has cancelled its request.
+///
+/// The router creates a token for every request it dispatches with an ID and
+/// cancels it when a `$/cancelRequest` notification for that ID arrives on
+/// the same connection. Handlers get it from `RpcContext::cancellation()`:
+/// a synchronous handler checks it between steps of long work, and an async
+/// handler is dropped at its next `.await` and answered with "Request
+/// cancelled" (-32800) without doing anything itself.
+///
+/// Cancellation can only arrive while the connection keeps reading, which is
+/// the case on an `RpcPeer` and a `WebSocketServer`. Servers that answer one
+/// request at a time, such as `serve()`, read the notification only once the
+/// request has completed.
+///
+/// `CancellationToken` is cheap to clone; clones share the same state.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcContext, RpcError, RpcRouter};
+///
+/// let mut router = RpcRouter::new();
+/// router.register_with_context("index", |(files,): (Vec<String>,), context: &RpcContext| {
+///     for file in &files {
+///         context.cancellation().check()?;
+///         println!("indexing {}", file);
+///     }
+///     Ok::<_, RpcError>(files.len())
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+}
+
+/// The state shared by all clones of a token.
+#[derive(Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and wakes every task waiting on `cancelled()`.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        for waker in std::mem::take(&mut *self.state.wakers.lock().unwrap()) {
+            waker.wake();
+        }
+    }
+
+    /// Checks if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Checks the token, for handlers that stop with `?`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the request goes on, or the "Request cancelled" error to
+    /// answer it with.
+    pub fn check(&self) -> Result<(), RpcError> {
+        if self.is_cancelled() {
+            return Err(RpcError::request_cancelled());
+        }
+        Ok(())
+    }
+
+    /// Returns a future that completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            token: self.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// The future returned by `CancellationToken::cancelled()`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Cancelled {
+    token: CancellationToken,
+}
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut wakers = self.token.state.wakers.lock().unwrap();
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+// ===========================================================================
+// STRUCT: InFlight
+// ===
+
+/// The tokens of the requests a connection is handling, by ID.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    tokens: Mutex<HashMap<RpcId, CancellationToken>>,
+}
+
+impl InFlight {
+    /// Creates the token of a request that is starting.
+    pub(crate) fn start(&self, id: &RpcId) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(id.clone(), token.clone());
+        token
+    }
+
+    /// Forgets the token of a request that has completed.
+    pub(crate) fn finish(&self, id: &RpcId, token: &CancellationToken) {
+        let mut tokens = self.tokens.lock().unwrap();
+        // A newer request may have reused the ID
+        if tokens
+            .get(id)
+            .is_some_and(|current| Arc::ptr_eq(&current.state, &token.state))
+        {
+            tokens.remove(id);
+        }
+    }
+
+    /// Cancels the request with the given ID.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the request was still being handled.
+    pub(crate) fn cancel(&self, id: &RpcId) -> bool {
+        match self.tokens.lock().unwrap().get(id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// ===========================================================================
+// TESTS: CancellationToken
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        assert!(!token.is_cancelled());
+        assert!(token.check().is_ok());
+
+        let waiter = {
+            let token = token.clone();
+            thread::spawn(move || executor::block_on(token.cancelled()))
+        };
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+        waiter.join().unwrap();
+
+        assert!(token.is_cancelled());
+        assert_eq!(
+            token.check().unwrap_err().code(),
+            RpcError::REQUEST_CANCELLED
+        );
+        executor::block_on(token.cancelled());
+    }
+
+    #[test]
+    fn test_in_flight_tokens() {
+        let in_flight = InFlight::default();
+        let id = RpcId::Number(1);
+
+        let first = in_flight.start(&id);
+        let second = in_flight.start(&id);
+        in_flight.finish(&id, &first);
+
+        // Only the request currently using the ID is cancelled
+        assert!(in_flight.cancel(&id));
+        assert!(!first.is_cancelled());
+        assert!(second.is_cancelled());
+
+        in_flight.finish(&id, &second);
+        assert!(!in_flight.cancel(&id));
+    }
+}
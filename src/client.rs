@@ -0,0 +1,1103 @@
+use crate::subscription::{Subscribing, SubscriptionStreams};
+use crate::{
+    CANCEL_REQUEST_METHOD, IdGenerator, RpcBatchRequest, RpcError, RpcId, RpcMessage, RpcRequest,
+    RpcResponse, RpcRouter, SequentialIdGenerator, Subscription, Transport, TransportError,
+};
+use futures::FutureExt;
+use futures::channel::oneshot;
+use futures::executor;
+use futures::ready;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use std::thread;
+
+/// The error type returned by the outbound side of a client.
+pub type SendError = Box<dyn Error + Send + Sync>;
+
+/// The outbound side of a client: writes one message to the connection.
+type SendFn = dyn Fn(RpcMessage) -> Result<(), SendError> + Send + Sync;
+
+/// The result a pending call is completed with.
+type CallResult = Result<JsonValue, ClientError>;
+
+// ===========================================================================
+// ENUM: ClientError
+// ===
+
+/// Describes why a call made through an `RpcClient` did not produce a result.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The server answered with an error response.
+    Rpc(RpcError),
+
+    /// The params could not be serialized, or the result did not match the
+    /// expected type. The error names the offending field.
+    Conversion(RpcError),
+
+    /// Another call with the same ID is still waiting for its response.
+    DuplicateId(RpcId),
+
+    /// The message could not be written to the connection.
+    Send(SendError),
+
+    /// The connection was closed before the response arrived.
+    Disconnected,
+
+    /// The server process exited before the response arrived.
+    ProcessExited(ExitStatus),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Rpc(error) => write!(f, "server returned an error: {}", error),
+            ClientError::Conversion(error) => write!(f, "{}", error.message()),
+            ClientError::DuplicateId(id) => {
+                write!(f, "a call with id {} is already pending", id)
+            }
+            ClientError::Send(error) => write!(f, "failed to send message: {}", error),
+            ClientError::Disconnected => write!(f, "connection closed before a response arrived"),
+            ClientError::ProcessExited(status) => {
+                write!(
+                    f,
+                    "server process exited before a response arrived ({})",
+                    status
+                )
+            }
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Rpc(error) | ClientError::Conversion(error) => Some(error),
+            ClientError::Send(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+// ===========================================================================
+// STRUCT: PendingCall
+// ===
+
+/// A call that has been sent and is waiting for its response.
+///
+/// `PendingCall` is a future resolving to the call's result. Code outside of an
+/// async context can block on it with `wait()`.
+///
+/// Dropping a call before its response has arrived cancels it: the client
+/// stops waiting and sends the server a `$/cancelRequest` notification for
+/// its ID, so the handler can stop working on it.
+#[must_use = "a pending call does nothing unless awaited or waited on"]
+pub struct PendingCall {
+    id: Option<RpcId>,
+    receiver: oneshot::Receiver<CallResult>,
+    client: Option<Weak<ClientState>>,
+    done: bool,
+}
+
+impl PendingCall {
+    /// Creates a call that is already complete.
+    fn ready(id: Option<RpcId>, result: CallResult) -> Self {
+        let (sender, receiver) = oneshot::channel();
+        let _ = sender.send(result);
+        Self {
+            id,
+            receiver,
+            client: None,
+            done: false,
+        }
+    }
+
+    /// Returns the ID of the request, or `None` for a notification.
+    pub fn id(&self) -> Option<&RpcId> {
+        self.id.as_ref()
+    }
+
+    /// Blocks the current thread until the response arrives.
+    ///
+    /// # Returns
+    ///
+    /// The result of the call, or the `ClientError` describing why it failed.
+    pub fn wait(self) -> CallResult {
+        executor::block_on(self)
+    }
+
+    /// Cancels the call, which is the same as dropping it.
+    pub fn cancel(self) {}
+}
+
+impl Future for PendingCall {
+    type Output = CallResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = ready!(self.receiver.poll_unpin(cx));
+        self.done = true;
+        Poll::Ready(result.unwrap_or(Err(ClientError::Disconnected)))
+    }
+}
+
+impl Drop for PendingCall {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let state = self.client.as_ref().and_then(Weak::upgrade);
+        if let (Some(id), Some(state)) = (&self.id, state) {
+            RpcClient { state }.cancel(id);
+        }
+    }
+}
+
+impl fmt::Debug for PendingCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingCall")
+            .field("id", &self.id)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: RpcClient
+// ===
+
+/// Sends requests and correlates the responses that come back.
+///
+/// The client keeps track of every request still waiting for a response,
+/// keyed by its ID. Inbound responses complete the matching call no matter in
+/// which order they arrive. When the connection drops, every call that is
+/// still pending fails with `ClientError::Disconnected`.
+///
+/// `RpcClient::connect()` runs a client over a `Transport`, reading responses
+/// on a background thread. `RpcClient::new()` builds one on a plain send
+/// function instead; whoever reads from the connection then hands inbound
+/// messages to `handle_message()` and calls `close()` when it drops.
+///
+/// Each client draws IDs from its own generator, so several clients in one
+/// process never share a counter. `RpcClient` is cheap to clone; clones share
+/// the same connection and pending calls.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcClient, RpcMessage, RpcResponse};
+/// use serde_json::json;
+/// use std::sync::mpsc;
+///
+/// // Outbound messages go wherever the send function puts them
+/// let (outbox, sent) = mpsc::channel();
+/// let client = RpcClient::new(move |message: RpcMessage| outbox.send(message));
+///
+/// let call = client.call("add", json!([2, 3]));
+///
+/// // The server's answer arrives through the reading side of the connection
+/// let RpcMessage::Request(request) = sent.recv().unwrap() else { unreachable!() };
+/// client.handle_response(RpcResponse::success(request.id().unwrap(), json!(5)));
+///
+/// assert_eq!(call.wait().unwrap(), json!(5));
+/// ```
+#[derive(Clone)]
+pub struct RpcClient {
+    state: Arc<ClientState>,
+}
+
+/// The state shared by all clones of a client.
+struct ClientState {
+    send: Box<SendFn>,
+    transport: Option<Arc<dyn Transport>>,
+    generator: Box<dyn IdGenerator>,
+    pending: Mutex<PendingCalls>,
+    subscriptions: SubscriptionStreams,
+}
+
+impl Drop for ClientState {
+    fn drop(&mut self) {
+        if let Some(transport) = &self.transport {
+            let _ = transport.close();
+        }
+    }
+}
+
+/// The calls waiting for a response, and whether the connection is closed.
+#[derive(Default)]
+struct PendingCalls {
+    calls: HashMap<RpcId, oneshot::Sender<CallResult>>,
+    closed: bool,
+    exit_status: Option<ExitStatus>,
+}
+
+impl PendingCalls {
+    /// Returns the error calls fail with once the connection is closed.
+    fn closed_error(&self) -> ClientError {
+        match self.exit_status {
+            Some(status) => ClientError::ProcessExited(status),
+            None => ClientError::Disconnected,
+        }
+    }
+}
+
+impl RpcClient {
+    /// Creates a client that writes outbound messages with the given function.
+    ///
+    /// Request IDs are numbered 1, 2, 3, ... by a counter owned by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `send` - Writes one message to the connection
+    pub fn new<F, E>(send: F) -> Self
+    where
+        F: Fn(RpcMessage) -> Result<(), E> + Send + Sync + 'static,
+        E: Into<SendError>,
+    {
+        Self::with_generator(send, SequentialIdGenerator::new())
+    }
+
+    /// Creates a client that draws request IDs from the given generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `send` - Writes one message to the connection
+    /// * `generator` - The generator providing request IDs
+    pub fn with_generator<F, E>(send: F, generator: impl IdGenerator + 'static) -> Self
+    where
+        F: Fn(RpcMessage) -> Result<(), E> + Send + Sync + 'static,
+        E: Into<SendError>,
+    {
+        Self::build(
+            Box::new(move |message| send(message).map_err(Into::into)),
+            Box::new(generator),
+            None,
+        )
+    }
+
+    /// Creates a client running over a transport.
+    ///
+    /// A background thread reads from the transport and completes pending
+    /// calls as their responses arrive. When the peer closes the connection
+    /// or it fails, every pending call fails with `ClientError::Disconnected`,
+    /// or with `ClientError::ProcessExited` if the transport reports that the
+    /// server process exited. Requests the server sends are answered with
+    /// "Method not found". Dropping the last clone of the client closes the
+    /// transport, as `close()` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The connection to the server
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{MemoryTransport, RpcClient, ClientError};
+    /// use serde_json::json;
+    ///
+    /// let (client_end, server_end) = MemoryTransport::pair();
+    /// let client = RpcClient::connect(client_end);
+    ///
+    /// let call = client.call("ping", json!(null));
+    /// drop(server_end);
+    /// assert!(matches!(call.wait(), Err(ClientError::Disconnected)));
+    /// ```
+    pub fn connect(transport: impl Transport + 'static) -> Self {
+        Self::connect_with_generator(transport, SequentialIdGenerator::new())
+    }
+
+    /// Creates a client running over a transport, drawing request IDs from
+    /// the given generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The connection to the server
+    /// * `generator` - The generator providing request IDs
+    pub fn connect_with_generator(
+        transport: impl Transport + 'static,
+        generator: impl IdGenerator + 'static,
+    ) -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(transport);
+        let sender = transport.clone();
+        let client = Self::build(
+            Box::new(move |message| sender.send(message).map_err(Into::into)),
+            Box::new(generator),
+            Some(transport.clone()),
+        );
+
+        // The reader does not keep the client alive, so dropping the last
+        // clone closes the transport
+        let reader = Arc::downgrade(&client.state);
+        // A client serves no methods, so an empty router answers the server's
+        // requests with "Method not found"
+        let router = RpcRouter::new();
+        thread::spawn(move || {
+            let exit_status = loop {
+                match transport.receive() {
+                    Ok(Some(message)) => {
+                        let Some(state) = reader.upgrade() else {
+                            return;
+                        };
+                        let inbound = RpcClient { state }.handle_message(message);
+                        if let Some(reply) =
+                            inbound.and_then(|message| router.handle_message(message))
+                        {
+                            let _ = transport.send(reply);
+                        }
+                    }
+                    Err(error) if error.is_recoverable() => continue,
+                    Err(TransportError::ProcessExited(status)) => break Some(status),
+                    Ok(None) | Err(_) => break None,
+                }
+            };
+            if let Some(state) = reader.upgrade() {
+                RpcClient { state }.fail_pending(exit_status);
+            }
+        });
+
+        client
+    }
+
+    /// Creates a client from its parts.
+    fn build(
+        send: Box<SendFn>,
+        generator: Box<dyn IdGenerator>,
+        transport: Option<Arc<dyn Transport>>,
+    ) -> Self {
+        Self {
+            state: Arc::new(ClientState {
+                send,
+                transport,
+                generator,
+                pending: Mutex::new(PendingCalls::default()),
+                subscriptions: SubscriptionStreams::default(),
+            }),
+        }
+    }
+
+    /// Creates a request for a method, with an ID from this client's generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    ///
+    /// # Returns
+    ///
+    /// A request without params, ready to be completed and sent.
+    pub fn new_request(&self, method: &str) -> RpcRequest {
+        let mut request = RpcRequest::with_generator(self.state.generator.as_ref());
+        request.set_method(method.to_string());
+        request
+    }
+
+    /// Calls a method with JSON params.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    /// * `params` - The params, or `JsonValue::Null` for none
+    ///
+    /// # Returns
+    ///
+    /// A `PendingCall` resolving to the result, or to `ClientError::Rpc` if the
+    /// server answered with an error.
+    pub fn call(&self, method: &str, params: JsonValue) -> PendingCall {
+        let mut request = self.new_request(method);
+        request.set_params(params);
+        self.send_request(request)
+    }
+
+    /// Calls a method with typed params and converts the result.
+    ///
+    /// The request is sent right away; the returned future only waits for the
+    /// response.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    /// * `params` - The params, serialized to an array or an object
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the typed result. Params that cannot be
+    /// serialized and results of the wrong type fail with
+    /// `ClientError::Conversion`.
+    pub fn call_typed<P, R>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> impl Future<Output = Result<R, ClientError>> + Send + use<P, R>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut request = self.new_request(method);
+        let pending = match request.set_params_typed(params) {
+            Ok(_) => Ok(self.send_request(request)),
+            Err(error) => Err(ClientError::Conversion(error)),
+        };
+
+        async move {
+            let pending = pending?;
+            let id = pending.id().cloned().unwrap_or(RpcId::Null);
+            let result = pending.await?;
+
+            RpcResponse::success(id, result)
+                .result_as()
+                .map_err(ClientError::Conversion)
+        }
+    }
+
+    /// Sends a notification, which gets no response.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to notify
+    /// * `params` - The params, or `JsonValue::Null` for none
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the notification has been written to the connection.
+    pub fn notify(&self, method: &str, params: JsonValue) -> Result<(), ClientError> {
+        {
+            let pending = self.state.pending.lock().unwrap();
+            if pending.closed {
+                return Err(pending.closed_error());
+            }
+        }
+
+        let mut notification = RpcRequest::notification();
+        notification
+            .set_method(method.to_string())
+            .set_params(params);
+        (self.state.send)(RpcMessage::Notification(notification)).map_err(ClientError::Send)
+    }
+
+    /// Opens a subscription and returns the stream of items the server pushes
+    /// for it.
+    ///
+    /// The subscribe method must return the subscription ID. The server then
+    /// sends each item as a notification whose params are
+    /// `{"subscription": id, "result": item}`, which the client routes to the
+    /// stream instead of handing it to its reader. Items pushed before the
+    /// subscribe call's response arrives are not lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The subscribe method, such as `eth_subscribe`
+    /// * `params` - The params, serialized to an array or an object
+    /// * `notification_method` - The method of the notifications carrying
+    ///   items, such as `eth_subscription`
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the `Subscription`, or to the `ClientError` the
+    /// subscribe call failed with.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures::executor::{self, block_on_stream};
+    /// use ink_rpc::{RpcClient, WebSocketTransport};
+    /// use serde_json::Value;
+    ///
+    /// let client = RpcClient::connect(WebSocketTransport::connect("ws://127.0.0.1:8546")?);
+    /// let heads = client.subscribe::<_, Value>("eth_subscribe", &("newHeads",), "eth_subscription");
+    ///
+    /// for head in block_on_stream(executor::block_on(heads)?).take(3) {
+    ///     println!("new head: {}", head?);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn subscribe<P, T>(
+        &self,
+        method: &str,
+        params: &P,
+        notification_method: &str,
+    ) -> impl Future<Output = Result<Subscription<T>, ClientError>> + Send + use<P, T>
+    where
+        P: Serialize,
+        T: DeserializeOwned,
+    {
+        let subscribing = Subscribing::new(self, notification_method);
+        let call = self.call_typed::<P, RpcId>(method, params);
+        let client = self.clone();
+        let notification_method = notification_method.to_string();
+
+        async move {
+            let id = call.await?;
+            let subscription = Subscription::open(client, notification_method, id);
+            drop(subscribing);
+            Ok(subscription)
+        }
+    }
+
+    /// Sends a prepared request and tracks it until its response arrives.
+    ///
+    /// A notification is sent as-is and its `PendingCall` resolves to
+    /// `JsonValue::Null` as soon as it has been written.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send
+    ///
+    /// # Returns
+    ///
+    /// A `PendingCall` resolving to the result.
+    pub fn send_request(&self, request: RpcRequest) -> PendingCall {
+        let Some(id) = request.id().cloned() else {
+            let result = (self.state.send)(RpcMessage::Notification(request))
+                .map(|_| JsonValue::Null)
+                .map_err(ClientError::Send);
+            return PendingCall::ready(None, result);
+        };
+
+        let pending = match self.track(&id) {
+            Ok(pending) => pending,
+            Err(error) => return PendingCall::ready(Some(id), Err(error)),
+        };
+
+        if let Err(error) = (self.state.send)(RpcMessage::Request(request)) {
+            self.complete(&id, Err(ClientError::Send(error)));
+        }
+
+        pending
+    }
+
+    /// Sends a batch and tracks each of its requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch of requests and notifications to send
+    ///
+    /// # Returns
+    ///
+    /// One `PendingCall` per request in the batch, in order. Notifications get
+    /// none.
+    pub fn send_batch(&self, batch: RpcBatchRequest) -> Vec<PendingCall> {
+        let mut pending = Vec::new();
+        let mut tracked = Vec::new();
+
+        for request in batch.requests() {
+            if let Some(id) = request.id() {
+                match self.track(id) {
+                    Ok(call) => {
+                        tracked.push(id.clone());
+                        pending.push(call);
+                    }
+                    Err(error) => pending.push(PendingCall::ready(Some(id.clone()), Err(error))),
+                }
+            }
+        }
+
+        if let Err(error) = (self.state.send)(RpcMessage::RequestBatch(batch)) {
+            // Every request in the batch failed for the same reason
+            let reason = error.to_string();
+            for id in &tracked {
+                self.complete(id, Err(ClientError::Send(reason.clone().into())));
+            }
+        }
+
+        pending
+    }
+
+    /// Completes the pending call matching a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - A response read from the connection
+    ///
+    /// # Returns
+    ///
+    /// `true` if a pending call was waiting for this response, `false` if the
+    /// response was unsolicited or arrived after its call was given up.
+    pub fn handle_response(&self, response: RpcResponse) -> bool {
+        let id = response.id().clone();
+        let result = response.into_result().map_err(ClientError::Rpc);
+        self.complete(&id, result)
+    }
+
+    /// Routes an inbound message to the pending calls it answers.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - A message read from the connection
+    ///
+    /// # Returns
+    ///
+    /// `None` if the message was a response, a response batch or an item of
+    /// a subscription, or the message itself if it is a request or
+    /// notification meant for someone else.
+    pub fn handle_message(&self, message: RpcMessage) -> Option<RpcMessage> {
+        match message {
+            RpcMessage::Notification(notification) => self
+                .state
+                .subscriptions
+                .dispatch(notification)
+                .map(RpcMessage::Notification),
+            RpcMessage::Response(response) => {
+                self.handle_response(response);
+                None
+            }
+            RpcMessage::ResponseBatch(batch) => {
+                for response in batch {
+                    self.handle_response(response);
+                }
+                None
+            }
+            other => Some(other),
+        }
+    }
+
+    /// Closes the connection.
+    ///
+    /// Every pending call fails with `ClientError::Disconnected`, and so does
+    /// every call made afterwards. A client created with `connect()` also
+    /// closes its transport, so the server sees the connection end.
+    pub fn close(&self) {
+        self.fail_pending(None);
+        if let Some(transport) = &self.state.transport {
+            let _ = transport.close();
+        }
+    }
+
+    /// Marks the connection as closed and fails every pending call.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_status` - How the server process exited, if the connection
+    ///   ended because it did
+    fn fail_pending(&self, exit_status: Option<ExitStatus>) {
+        let mut pending = self.state.pending.lock().unwrap();
+        if !pending.closed {
+            pending.closed = true;
+            pending.exit_status = exit_status;
+        }
+
+        for (_, sender) in std::mem::take(&mut pending.calls) {
+            let _ = sender.send(Err(pending.closed_error()));
+        }
+        self.state.subscriptions.close_all();
+    }
+
+    /// Checks if the connection has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.state.pending.lock().unwrap().closed
+    }
+
+    /// Returns the number of calls waiting for a response.
+    pub fn pending_count(&self) -> usize {
+        self.state.pending.lock().unwrap().calls.len()
+    }
+
+    /// Returns the streams of the subscriptions opened by this client.
+    pub(crate) fn subscriptions(&self) -> &SubscriptionStreams {
+        &self.state.subscriptions
+    }
+
+    /// Checks if two clients are clones sharing the same connection.
+    pub(crate) fn same_connection(&self, other: &RpcClient) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+
+    /// Registers a call waiting for the response with the given ID.
+    ///
+    /// The call is registered before the request is sent, so a response that
+    /// arrives immediately still finds it.
+    fn track(&self, id: &RpcId) -> Result<PendingCall, ClientError> {
+        let mut pending = self.state.pending.lock().unwrap();
+        if pending.closed {
+            return Err(pending.closed_error());
+        }
+        if pending.calls.contains_key(id) {
+            return Err(ClientError::DuplicateId(id.clone()));
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        pending.calls.insert(id.clone(), sender);
+
+        Ok(PendingCall {
+            id: Some(id.clone()),
+            receiver,
+            client: Some(Arc::downgrade(&self.state)),
+            done: false,
+        })
+    }
+
+    /// Stops waiting for a call and asks the server to cancel it, unless its
+    /// response has already arrived.
+    fn cancel(&self, id: &RpcId) {
+        let removed = self.state.pending.lock().unwrap().calls.remove(id);
+        if removed.is_some() {
+            let _ = self.notify(CANCEL_REQUEST_METHOD, json!({ "id": id }));
+        }
+    }
+
+    /// Completes the call with the given ID, if it is still pending.
+    fn complete(&self, id: &RpcId, result: CallResult) -> bool {
+        let sender = self.state.pending.lock().unwrap().calls.remove(id);
+        match sender {
+            Some(sender) => {
+                let _ = sender.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for RpcClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pending = self.state.pending.lock().unwrap();
+        f.debug_struct("RpcClient")
+            .field("pending", &pending.calls.len())
+            .field("closed", &pending.closed)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// TESTS: RpcClient
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::io;
+    use std::sync::mpsc;
+
+    /// Creates a client whose outbound messages can be inspected.
+    fn client() -> (RpcClient, mpsc::Receiver<RpcMessage>) {
+        let (outbox, sent) = mpsc::channel();
+        let client = RpcClient::new(move |message: RpcMessage| {
+            outbox.send(message).map_err(|_| "the receiver was dropped")
+        });
+        (client, sent)
+    }
+
+    fn sent_request(sent: &mpsc::Receiver<RpcMessage>) -> RpcRequest {
+        match sent.try_recv().unwrap() {
+            RpcMessage::Request(request) => request,
+            other => panic!("expected a request, got {:?}", other),
+        }
+    }
+
+    // ---------------------------------------------------------------------------
+    // Correlation Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_call_resolves_with_result() {
+        let (client, sent) = client();
+
+        let call = client.call("add", json!([2, 3]));
+        let request = sent_request(&sent);
+        assert_eq!(request.method(), "add");
+        assert_eq!(call.id(), request.id());
+        assert_eq!(client.pending_count(), 1);
+
+        assert!(client.handle_response(RpcResponse::success(request.id().unwrap(), json!(5))));
+        assert_eq!(call.wait().unwrap(), json!(5));
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_out_of_order_responses() {
+        let (client, sent) = client();
+
+        let first = client.call("first", JsonValue::Null);
+        let second = client.call("second", JsonValue::Null);
+        let first_id = sent_request(&sent).id().cloned().unwrap();
+        let second_id = sent_request(&sent).id().cloned().unwrap();
+
+        client.handle_response(RpcResponse::success(&second_id, json!("two")));
+        client.handle_response(RpcResponse::success(&first_id, json!("one")));
+
+        assert_eq!(second.wait().unwrap(), json!("two"));
+        assert_eq!(first.wait().unwrap(), json!("one"));
+    }
+
+    #[test]
+    fn test_error_response() {
+        let (client, sent) = client();
+
+        let call = client.call("missing", JsonValue::Null);
+        let request = sent_request(&sent);
+        client.handle_response(RpcResponse::failure(
+            request.id().unwrap(),
+            RpcError::method_not_found(),
+        ));
+
+        match call.wait() {
+            Err(ClientError::Rpc(error)) => assert_eq!(error.code(), RpcError::METHOD_NOT_FOUND),
+            other => panic!("expected an rpc error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unsolicited_response_is_ignored() {
+        let (client, _sent) = client();
+        assert!(!client.handle_response(RpcResponse::success(99, json!(1))));
+
+        // Requests are handed back to the caller
+        let message = RpcMessage::from(RpcRequest::new());
+        assert!(client.handle_message(message).is_some());
+    }
+
+    #[test]
+    fn test_duplicate_id() {
+        let (client, _sent) = client();
+        let mut request = RpcRequest::new();
+        request.set_id("same");
+
+        let first = client.send_request(request.clone());
+        let second = client.send_request(request);
+        assert!(matches!(second.wait(), Err(ClientError::DuplicateId(_))));
+
+        client.handle_response(RpcResponse::success("same", json!(true)));
+        assert_eq!(first.wait().unwrap(), json!(true));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Typed Call Tests
+    // ---------------------------------------------------------------------------
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Balance {
+        amount: u64,
+    }
+
+    #[test]
+    fn test_call_typed() {
+        let (client, sent) = client();
+
+        let call = client.call_typed::<_, Balance>("balance", &("acc_1",));
+        let request = sent_request(&sent);
+        assert_eq!(request.params(), &json!(["acc_1"]));
+
+        client.handle_response(RpcResponse::success(
+            request.id().unwrap(),
+            json!({"amount": 7}),
+        ));
+        assert_eq!(executor::block_on(call).unwrap(), Balance { amount: 7 });
+
+        // A result of the wrong type is reported as a conversion error
+        let call = client.call_typed::<_, Balance>("balance", &("acc_1",));
+        let request = sent_request(&sent);
+        client.handle_response(RpcResponse::success(
+            request.id().unwrap(),
+            json!({"amount": "x"}),
+        ));
+        assert!(matches!(
+            executor::block_on(call),
+            Err(ClientError::Conversion(_))
+        ));
+
+        // Params must serialize to an array or object
+        let call = client.call_typed::<_, Balance>("balance", &5);
+        assert!(matches!(
+            executor::block_on(call),
+            Err(ClientError::Conversion(_))
+        ));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Batch and Notification Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_send_batch() {
+        let (client, sent) = client();
+
+        let mut notification = RpcRequest::notification();
+        notification.set_method("log".to_string());
+        let batch: RpcBatchRequest = vec![
+            client.new_request("a"),
+            notification,
+            client.new_request("b"),
+        ]
+        .into();
+
+        let calls = client.send_batch(batch);
+        assert_eq!(calls.len(), 2);
+
+        let RpcMessage::RequestBatch(batch) = sent.try_recv().unwrap() else {
+            panic!("expected a batch");
+        };
+        let responses: crate::RpcBatchResponse = batch
+            .requests()
+            .iter()
+            .filter_map(RpcResponse::for_request)
+            .collect();
+        assert!(
+            client
+                .handle_message(RpcMessage::ResponseBatch(responses))
+                .is_none()
+        );
+
+        for call in calls {
+            assert_eq!(call.wait().unwrap(), JsonValue::Null);
+        }
+    }
+
+    #[test]
+    fn test_notify() {
+        let (client, sent) = client();
+        client.notify("log", json!({"level": "info"})).unwrap();
+
+        assert!(matches!(
+            sent.try_recv().unwrap(),
+            RpcMessage::Notification(_)
+        ));
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_dropping_a_call_cancels_it() {
+        let (client, sent) = client();
+
+        let call = client.call("slow", JsonValue::Null);
+        let id = sent_request(&sent).id().cloned().unwrap();
+        drop(call);
+
+        match sent.try_recv().unwrap() {
+            RpcMessage::Notification(notification) => {
+                assert_eq!(notification.method(), CANCEL_REQUEST_METHOD);
+                assert_eq!(notification.params(), &json!({ "id": id }));
+            }
+            other => panic!("expected a notification, got {:?}", other),
+        }
+        assert_eq!(client.pending_count(), 0);
+        assert!(!client.handle_response(RpcResponse::success(&id, json!("late"))));
+
+        // Calls that completed, or whose response already arrived, are not cancelled
+        let done = client.call("done", JsonValue::Null);
+        let answered = client.call("answered", JsonValue::Null);
+        let done_id = sent_request(&sent).id().cloned().unwrap();
+        let answered_id = sent_request(&sent).id().cloned().unwrap();
+        client.handle_response(RpcResponse::success(&done_id, json!(1)));
+        client.handle_response(RpcResponse::success(&answered_id, json!(2)));
+        assert_eq!(done.wait().unwrap(), json!(1));
+        drop(answered);
+        assert!(sent.try_recv().is_err());
+    }
+
+    // ---------------------------------------------------------------------------
+    // Connection Failure Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_close_fails_pending_calls() {
+        let (client, _sent) = client();
+
+        let first = client.call("a", JsonValue::Null);
+        let second = client.call("b", JsonValue::Null);
+        client.close();
+
+        assert!(client.is_closed());
+        assert!(matches!(first.wait(), Err(ClientError::Disconnected)));
+        assert!(matches!(second.wait(), Err(ClientError::Disconnected)));
+
+        // Calls made after the connection closed fail immediately
+        let call = client.call("c", JsonValue::Null);
+        assert!(matches!(call.wait(), Err(ClientError::Disconnected)));
+        assert!(matches!(
+            client.notify("d", JsonValue::Null),
+            Err(ClientError::Disconnected)
+        ));
+    }
+
+    #[test]
+    fn test_send_failure() {
+        let (client, sent) = client();
+        drop(sent);
+
+        let call = client.call("a", JsonValue::Null);
+        assert!(matches!(call.wait(), Err(ClientError::Send(_))));
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_connect_over_transport() {
+        let (client_end, server_end) = crate::MemoryTransport::pair();
+        let client = RpcClient::connect(client_end);
+
+        let first = client.call("first", JsonValue::Null);
+        let second = client.call("second", JsonValue::Null);
+
+        // Answer out of order from the other end of the connection
+        let mut requests = Vec::new();
+        for _ in 0..2 {
+            match server_end.receive().unwrap() {
+                Some(RpcMessage::Request(request)) => requests.push(request),
+                other => panic!("expected a request, got {:?}", other),
+            }
+        }
+        for request in requests.iter().rev() {
+            let response = RpcResponse::success(request.id().unwrap(), json!(request.method()));
+            server_end.send(RpcMessage::from(response)).unwrap();
+        }
+
+        assert_eq!(first.wait().unwrap(), json!("first"));
+        assert_eq!(second.wait().unwrap(), json!("second"));
+
+        // Requests from the server are answered, not left hanging
+        let mut request = RpcRequest::new();
+        request.set_method("ping".to_string());
+        server_end.send(RpcMessage::from(request.clone())).unwrap();
+        match server_end.receive().unwrap() {
+            Some(RpcMessage::Response(response)) => {
+                assert_eq!(response.id(), request.id().unwrap());
+                assert_eq!(response.error().unwrap().code(), RpcError::METHOD_NOT_FOUND);
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+
+        // Closing the client closes the transport
+        client.close();
+        assert!(server_end.receive().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dropping_the_client_closes_the_transport() {
+        let (client_end, server_end) = crate::MemoryTransport::pair();
+        let client = RpcClient::connect(client_end);
+        let clone = client.clone();
+
+        drop(client);
+        clone.notify("still_open", JsonValue::Null).unwrap();
+        assert!(matches!(
+            server_end.receive().unwrap(),
+            Some(RpcMessage::Notification(_))
+        ));
+
+        drop(clone);
+        assert!(server_end.receive().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_per_client_ids() {
+        let (first, _) = client();
+        let (second, _) = client();
+
+        assert_eq!(first.new_request("a").id(), Some(&RpcId::Number(1)));
+        assert_eq!(second.new_request("a").id(), Some(&RpcId::Number(1)));
+
+        let seeded = RpcClient::with_generator(
+            |_: RpcMessage| Ok::<_, io::Error>(()),
+            crate::SeededIdGenerator::new(3),
+        );
+        let expected = crate::SeededIdGenerator::new(3).next_id();
+        assert_eq!(seeded.new_request("a").id(), Some(&expected));
+    }
+}
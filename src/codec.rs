@@ -0,0 +1,304 @@
+use crate::{RpcMessage, TransportError, ValidationError};
+use futures::io::{AsyncBufRead, AsyncBufReadExt};
+use serde_json::Value as JsonValue;
+use std::io::{self, BufRead, Write};
+
+// ===========================================================================
+// TRAIT: FrameCodec
+// ===
+
+/// Splits a byte stream into JSON-RPC messages and writes messages back.
+///
+/// Pipes and sockets carry bytes, not messages, so each message is wrapped in
+/// a frame that tells the reader where it ends. A codec implements one framing
+/// format; `StreamTransport` runs any codec over a `Read`/`Write` pair.
+///
+/// Implementors only deal with frames, the raw JSON bytes of one message.
+/// `read_message()` and `write_message()` take care of converting between
+/// frames and `RpcMessage`s.
+pub trait FrameCodec: Send + Sync {
+    /// Reads the next frame from a stream.
+    ///
+    /// The frame may arrive in any number of partial reads.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Vec<u8>))` - The body of the next frame
+    /// * `Ok(None)` - The stream ended between frames
+    /// * `Err(TransportError)` - The frame could not be read; recoverable
+    ///   errors leave the stream positioned at the start of the next frame
+    fn read_frame(&self, reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError>;
+
+    /// Writes one frame to a stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    /// * `frame` - The body of the frame
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the whole frame has been written.
+    fn write_frame(&self, writer: &mut dyn Write, frame: &[u8]) -> io::Result<()>;
+
+    /// Reads and validates the next message from a stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(RpcMessage))` - The next message
+    /// * `Ok(None)` - The stream ended between messages
+    /// * `Err(TransportError)` - The message could not be read; a frame that is
+    ///   not valid JSON-RPC 2.0 yields `TransportError::Invalid`
+    fn read_message(&self, reader: &mut dyn BufRead) -> Result<Option<RpcMessage>, TransportError> {
+        match self.read_frame(reader)? {
+            Some(frame) => Ok(Some(parse_frame(&frame)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes a message to a stream and flushes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    /// * `message` - The message to write
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message has been written and flushed.
+    fn write_message(&self, writer: &mut dyn Write, message: &RpcMessage) -> io::Result<()> {
+        self.write_frame(writer, &encode_message(message))?;
+        writer.flush()
+    }
+}
+
+/// Creates a fresh codec for each new connection or process.
+pub(crate) type CodecFactory = dyn Fn() -> Box<dyn FrameCodec> + Send + Sync;
+
+impl<C: FrameCodec + ?Sized> FrameCodec for Box<C> {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError> {
+        (**self).read_frame(reader)
+    }
+
+    fn write_frame(&self, writer: &mut dyn Write, frame: &[u8]) -> io::Result<()> {
+        (**self).write_frame(writer, frame)
+    }
+
+    fn read_message(&self, reader: &mut dyn BufRead) -> Result<Option<RpcMessage>, TransportError> {
+        (**self).read_message(reader)
+    }
+
+    fn write_message(&self, writer: &mut dyn Write, message: &RpcMessage) -> io::Result<()> {
+        (**self).write_message(writer, message)
+    }
+}
+
+/// Parses the body of a frame into a message.
+///
+/// # Arguments
+///
+/// * `frame` - The raw JSON bytes of one message
+///
+/// # Returns
+///
+/// The classified message, or the `ValidationError` to reply with.
+pub(crate) fn parse_frame(frame: &[u8]) -> Result<RpcMessage, ValidationError> {
+    let json: JsonValue = serde_json::from_slice(frame).map_err(ValidationError::parse)?;
+    RpcMessage::from_json(json)
+}
+
+/// Serializes a message into the body of a frame.
+///
+/// The JSON is written in compact form, which never contains a raw newline:
+/// newlines inside strings are escaped as `\n`.
+///
+/// # Arguments
+///
+/// * `message` - The message to serialize
+///
+/// # Returns
+///
+/// The raw JSON bytes of the message.
+pub(crate) fn encode_message(message: &RpcMessage) -> Vec<u8> {
+    message.to_string().into_bytes()
+}
+
+/// Reads one line from a stream, without its line ending.
+///
+/// Lines may end with `\n` or `\r\n`, and the last line of the stream may
+/// have no line ending at all. A line longer than `limit` bytes is skipped
+/// without ever being buffered whole.
+///
+/// # Arguments
+///
+/// * `reader` - The stream to read from
+/// * `limit` - The maximum length of a line in bytes
+///
+/// # Returns
+///
+/// * `Ok(Some(Vec<u8>))` - The next line
+/// * `Ok(None)` - The stream ended
+/// * `Err(TransportError::FrameTooLarge)` - The line was too long and has been skipped
+pub(crate) fn read_line(
+    reader: &mut dyn BufRead,
+    limit: usize,
+) -> Result<Option<Vec<u8>>, TransportError> {
+    let mut scanner = LineScanner::new(limit);
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+        if available.is_empty() {
+            return scanner.finish();
+        }
+
+        let (consumed, ended) = scanner.scan(available);
+        reader.consume(consumed);
+        if ended {
+            return scanner.finish();
+        }
+    }
+}
+
+/// Reads one line from an async stream, without its line ending.
+///
+/// This is the async equivalent of `read_line()`.
+pub(crate) async fn read_line_async<R>(
+    reader: &mut R,
+    limit: usize,
+) -> Result<Option<Vec<u8>>, TransportError>
+where
+    R: AsyncBufRead + Unpin + ?Sized,
+{
+    let mut scanner = LineScanner::new(limit);
+
+    loop {
+        let available = match reader.fill_buf().await {
+            Ok(available) => available,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+        if available.is_empty() {
+            return scanner.finish();
+        }
+
+        let (consumed, ended) = scanner.scan(available);
+        reader.consume_unpin(consumed);
+        if ended {
+            return scanner.finish();
+        }
+    }
+}
+
+/// Fails if the stream ended before a skipped frame body was complete.
+///
+/// # Arguments
+///
+/// * `skipped` - The number of bytes actually skipped
+/// * `length` - The length of the body that was being skipped
+pub(crate) fn check_skipped(skipped: u64, length: usize) -> io::Result<()> {
+    if skipped < length as u64 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Checks if a header name is a valid HTTP token.
+pub(crate) fn is_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+// ===========================================================================
+// STRUCT: LineScanner
+// ===
+
+/// Collects one line from a stream that arrives in chunks of any size.
+///
+/// The scanner is shared by the blocking and async readers, which only differ
+/// in how they obtain the next chunk.
+struct LineScanner {
+    line: Vec<u8>,
+    limit: usize,
+    overflowed: bool,
+    started: bool,
+}
+
+impl LineScanner {
+    /// Creates a scanner that rejects lines longer than `limit` bytes.
+    fn new(limit: usize) -> Self {
+        Self {
+            line: Vec::new(),
+            limit,
+            overflowed: false,
+            started: false,
+        }
+    }
+
+    /// Scans a chunk of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The bytes currently available from the stream
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes to consume from the stream, and whether the line
+    /// ended within the chunk.
+    fn scan(&mut self, chunk: &[u8]) -> (usize, bool) {
+        let (content, consumed, ended) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(end) => (&chunk[..end], end + 1, true),
+            None => (chunk, chunk.len(), false),
+        };
+        self.started = true;
+
+        // Once a line is too long, the rest of it is skipped without buffering.
+        // One extra byte is allowed for the `\r` of a `\r\n` line ending.
+        if !self.overflowed {
+            if self.line.len() + content.len() > self.limit + 1 {
+                self.overflowed = true;
+                self.line = Vec::new();
+            } else {
+                self.line.extend_from_slice(content);
+            }
+        }
+
+        (consumed, ended)
+    }
+
+    /// Completes the line at a line ending or at the end of the stream.
+    ///
+    /// # Returns
+    ///
+    /// The line without its line ending, `None` if the stream ended before
+    /// the line started, or `TransportError::FrameTooLarge`.
+    fn finish(self) -> Result<Option<Vec<u8>>, TransportError> {
+        if !self.started {
+            return Ok(None);
+        }
+
+        let mut line = self.line;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        if self.overflowed || line.len() > self.limit {
+            return Err(TransportError::FrameTooLarge { limit: self.limit });
+        }
+
+        Ok(Some(line))
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// ===========================================================================
+// TRAIT: Socket
+// ===
+
+/// A connected socket that a server can close from another thread.
+pub(crate) trait Socket: Send + Sync {
+    /// Shuts down both directions of the socket, which wakes any thread
+    /// blocked reading from it.
+    fn shutdown(&self) -> io::Result<()>;
+}
+
+impl Socket for TcpStream {
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+#[cfg(unix)]
+impl Socket for std::os::unix::net::UnixStream {
+    fn shutdown(&self) -> io::Result<()> {
+        std::os::unix::net::UnixStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+// ===========================================================================
+// STRUCT: SharedSocket
+// ===
+
+/// A handle to a socket shared between the reading and writing halves of a
+/// transport and the server tracking it, so a connection only holds one file
+/// descriptor.
+pub(crate) struct SharedSocket<S>(pub(crate) Arc<S>);
+
+impl<S> Read for SharedSocket<S>
+where
+    for<'a> &'a S: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.0).read(buf)
+    }
+}
+
+impl<S> Write for SharedSocket<S>
+where
+    for<'a> &'a S: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.0).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.0).flush()
+    }
+}
+
+// ===========================================================================
+// STRUCT: Connections
+// ===
+
+/// The sockets of a server's open connections, so they can be closed on
+/// shutdown.
+#[derive(Debug)]
+pub(crate) struct Connections<S> {
+    state: Mutex<ConnectionState<S>>,
+}
+
+#[derive(Debug)]
+struct ConnectionState<S> {
+    sockets: HashMap<u64, Arc<S>>,
+    next_id: u64,
+    stopped: bool,
+}
+
+impl<S: Socket> Connections<S> {
+    /// Creates an empty set of connections.
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(ConnectionState {
+                sockets: HashMap::new(),
+                next_id: 0,
+                stopped: false,
+            }),
+        }
+    }
+
+    /// Records an open connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(u64)` - The ID to untrack the connection with
+    /// * `None` - The server has been shut down and the connection must not
+    ///   be served
+    pub(crate) fn track(&self, socket: &Arc<S>) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.stopped {
+            return None;
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.sockets.insert(id, socket.clone());
+        Some(id)
+    }
+
+    /// Forgets a connection whose session has ended.
+    pub(crate) fn untrack(&self, id: u64) {
+        self.state.lock().unwrap().sockets.remove(&id);
+    }
+
+    /// Returns the number of open connections.
+    pub(crate) fn count(&self) -> usize {
+        self.state.lock().unwrap().sockets.len()
+    }
+
+    /// Stops tracking new connections and closes the open ones, which ends
+    /// their sessions.
+    pub(crate) fn close_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.stopped = true;
+        for socket in state.sockets.values() {
+            let _ = socket.shutdown();
+        }
+    }
+}
+
+// ===========================================================================
+// FUNCTIONS: Accepting
+// ===
+
+/// The pause before accepting again once the process runs out of resources.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(5);
+
+/// The longest pause between attempts while the process is out of resources.
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Accepts the next connection, retrying errors that do not end the server.
+///
+/// Errors that only affect the connection being accepted are retried right
+/// away. Running out of file descriptors or memory is retried after a pause
+/// that doubles up to a second, since sessions ending free them again.
+///
+/// # Arguments
+///
+/// * `accept` - Accepts one connection from the listener
+///
+/// # Returns
+///
+/// The accepted connection, or the error that ends the server.
+pub(crate) fn accept_next<T>(mut accept: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut backoff = MIN_ACCEPT_BACKOFF;
+    loop {
+        match accept() {
+            Ok(accepted) => return Ok(accepted),
+            Err(error) if is_transient(&error) => continue,
+            Err(error) if is_exhausted(&error) => {
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Checks if an accept error only affects the connection being accepted.
+fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::Interrupted
+    )
+}
+
+/// Checks if an accept error means the process or system is out of file
+/// descriptors or memory.
+fn is_exhausted(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    if matches!(
+        error.raw_os_error(),
+        Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM)
+    ) {
+        return true;
+    }
+    error.kind() == io::ErrorKind::OutOfMemory
+}
+
+// ===========================================================================
+// TESTS: Connections
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A socket counting its shutdowns.
+    #[derive(Default)]
+    struct FakeSocket {
+        shutdowns: AtomicUsize,
+    }
+
+    impl Socket for FakeSocket {
+        fn shutdown(&self) -> io::Result<()> {
+            self.shutdowns.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_track_and_close() {
+        let socket = Arc::new(FakeSocket::default());
+        let connections = Connections::new();
+
+        let first = connections.track(&socket).unwrap();
+        let second = connections.track(&socket).unwrap();
+        assert_ne!(first, second);
+        connections.untrack(first);
+        assert_eq!(connections.count(), 1);
+
+        connections.close_all();
+        assert_eq!(socket.shutdowns.load(Ordering::SeqCst), 1);
+        assert!(connections.track(&socket).is_none());
+    }
+
+    #[test]
+    fn test_accept_retries() {
+        #[cfg(unix)]
+        let exhausted = || io::Error::from_raw_os_error(libc::EMFILE);
+        #[cfg(not(unix))]
+        let exhausted = || io::Error::from(io::ErrorKind::OutOfMemory);
+
+        // Running out of resources or losing one peer does not end the server
+        let mut errors = vec![
+            io::Error::from(io::ErrorKind::ConnectionAborted),
+            exhausted(),
+            exhausted(),
+        ];
+        let accepted = accept_next(|| match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(7),
+        });
+        assert_eq!(accepted.unwrap(), 7);
+        assert!(errors.is_empty());
+
+        let error = accept_next(|| Err::<(), _>(io::Error::from(io::ErrorKind::InvalidInput)));
+        assert_eq!(error.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
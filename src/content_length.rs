@@ -0,0 +1,690 @@
+use crate::codec::{self, FrameCodec, is_token};
+use crate::{RpcMessage, TransportError};
+use futures::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::{self, BufRead, Read, Write};
+
+/// The maximum length of a single header line, in bytes.
+const MAX_HEADER_LINE: usize = 8 * 1024;
+
+// ===========================================================================
+// STRUCT: ContentLengthCodec
+// ===
+
+/// Frames messages with `Content-Length` headers, as in the Language Server
+/// Protocol base protocol.
+///
+/// Each message is preceded by a header block and an empty line:
+///
+/// ```text
+/// Content-Length: 52\r\n
+/// Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n
+/// \r\n
+/// {"jsonrpc":"2.0","method":"initialized","params":{}}
+/// ```
+///
+/// `Content-Length` is required; `Content-Type` is optional, but a charset
+/// other than UTF-8 is rejected. Unknown headers are ignored.
+///
+/// Reading recovers from malformed frames where it can: a frame with a bad
+/// header or a body over the size limit is skipped using its
+/// `Content-Length`, and a frame without a usable `Content-Length` is skipped
+/// by scanning past its JSON body. Either way, the error is reported as a
+/// recoverable `TransportError::MalformedFrame` or
+/// `TransportError::FrameTooLarge` and the next read starts at the next frame.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{ContentLengthCodec, FrameCodec, RpcMessage, RpcRequest};
+/// use std::io::Cursor;
+///
+/// let codec = ContentLengthCodec::new();
+/// let mut notification = RpcRequest::notification();
+/// notification.set_method("initialized".to_string());
+///
+/// let mut buffer = Vec::new();
+/// codec.write_message(&mut buffer, &RpcMessage::from(notification)).unwrap();
+/// assert!(buffer.starts_with(b"Content-Length: 40\r\n\r\n"));
+///
+/// let message = codec.read_message(&mut Cursor::new(buffer)).unwrap();
+/// assert!(matches!(message, Some(RpcMessage::Notification(_))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContentLengthCodec {
+    max_body_size: usize,
+    content_type: Option<String>,
+}
+
+impl ContentLengthCodec {
+    /// The default maximum body size: 16 MiB.
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Creates a codec with the default maximum body size.
+    pub fn new() -> Self {
+        Self::with_max_body_size(Self::DEFAULT_MAX_BODY_SIZE)
+    }
+
+    /// Creates a codec that rejects bodies larger than `max_body_size` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_body_size` - The maximum size of a message body in bytes
+    pub fn with_max_body_size(max_body_size: usize) -> Self {
+        Self {
+            max_body_size,
+            content_type: None,
+        }
+    }
+
+    /// Returns the maximum body size in bytes.
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Returns the `Content-Type` written with each message, if any.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Sets the `Content-Type` header written with each message.
+    ///
+    /// By default no `Content-Type` is written, which LSP peers read as
+    /// `application/vscode-jsonrpc; charset=utf-8`.
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The header value, such as `application/vscode-jsonrpc; charset=utf-8`
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_content_type(&mut self, content_type: impl Into<String>) -> &mut Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Reads and validates the next message from an async stream.
+    ///
+    /// This is the async equivalent of `FrameCodec::read_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// The next message, `None` at the end of the stream, or the error that
+    /// prevented reading it.
+    pub async fn read_message_async<R>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Option<RpcMessage>, TransportError>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        let mut headers = Headers::default();
+        loop {
+            let line = codec::read_line_async(reader, MAX_HEADER_LINE).await;
+            if headers.add(line)? {
+                break;
+            }
+        }
+
+        let body = match self.plan(headers) {
+            Body::Empty => return Ok(None),
+            Body::Read(length) => {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await?;
+                body
+            }
+            Body::Skip(length, error) => {
+                let skipped =
+                    futures::io::copy((&mut *reader).take(length as u64), &mut futures::io::sink())
+                        .await?;
+                codec::check_skipped(skipped, length)?;
+                return Err(error);
+            }
+            Body::Resync(error) => {
+                let mut skipper = JsonSkipper::new(self.max_body_size);
+                loop {
+                    let (consumed, done) = skipper.scan(reader.fill_buf().await?);
+                    reader.consume_unpin(consumed);
+                    if done {
+                        return Err(error);
+                    }
+                }
+            }
+        };
+
+        Ok(Some(codec::parse_frame(&body)?))
+    }
+
+    /// Writes a message to an async stream and flushes it.
+    ///
+    /// This is the async equivalent of `FrameCodec::write_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    /// * `message` - The message to write
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message has been written and flushed.
+    pub async fn write_message_async<W>(
+        &self,
+        writer: &mut W,
+        message: &RpcMessage,
+    ) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        writer
+            .write_all(&self.encode_frame(&codec::encode_message(message)))
+            .await?;
+        writer.flush().await
+    }
+
+    /// Decides how to handle the body following a header block.
+    fn plan(&self, headers: Headers) -> Body {
+        if headers.lines == 0 {
+            return Body::Empty;
+        }
+
+        match (headers.content_length, headers.problem) {
+            (Some(length), _) if length > self.max_body_size => Body::Skip(
+                length,
+                TransportError::FrameTooLarge {
+                    limit: self.max_body_size,
+                },
+            ),
+            (Some(length), Some(problem)) => {
+                Body::Skip(length, TransportError::MalformedFrame(problem))
+            }
+            (Some(length), None) => Body::Read(length),
+            (None, problem) => Body::Resync(TransportError::MalformedFrame(
+                problem.unwrap_or_else(|| "missing Content-Length header".to_string()),
+            )),
+        }
+    }
+
+    /// Prepends the header block to the body of a frame.
+    fn encode_frame(&self, frame: &[u8]) -> Vec<u8> {
+        let mut header = format!("Content-Length: {}\r\n", frame.len());
+        if let Some(content_type) = &self.content_type {
+            header.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        header.push_str("\r\n");
+
+        let mut bytes = header.into_bytes();
+        bytes.extend_from_slice(frame);
+        bytes
+    }
+}
+
+impl Default for ContentLengthCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameCodec for ContentLengthCodec {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError> {
+        let mut headers = Headers::default();
+        while !headers.add(codec::read_line(reader, MAX_HEADER_LINE))? {}
+
+        match self.plan(headers) {
+            Body::Empty => Ok(None),
+            Body::Read(length) => {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body)?;
+                Ok(Some(body))
+            }
+            Body::Skip(length, error) => {
+                let skipped = io::copy(&mut Read::take(reader, length as u64), &mut io::sink())?;
+                codec::check_skipped(skipped, length)?;
+                Err(error)
+            }
+            Body::Resync(error) => {
+                let mut skipper = JsonSkipper::new(self.max_body_size);
+                loop {
+                    let (consumed, done) = skipper.scan(reader.fill_buf()?);
+                    reader.consume(consumed);
+                    if done {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_frame(&self, writer: &mut dyn Write, frame: &[u8]) -> io::Result<()> {
+        writer.write_all(&self.encode_frame(frame))
+    }
+}
+
+// ===========================================================================
+// STRUCT: Headers
+// ===
+
+/// What to do with the body following a header block.
+enum Body {
+    /// The stream ended before a header block started.
+    Empty,
+
+    /// The body is valid and has this length.
+    Read(usize),
+
+    /// The frame is invalid, but its length is known and it can be skipped.
+    Skip(usize, TransportError),
+
+    /// The frame is invalid and its length is unknown.
+    Resync(TransportError),
+}
+
+/// The parsed header block of a frame.
+#[derive(Default)]
+struct Headers {
+    lines: usize,
+    content_length: Option<usize>,
+    problem: Option<String>,
+}
+
+impl Headers {
+    /// Adds the result of reading one header line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line, as returned by `codec::read_line()`
+    ///
+    /// # Returns
+    ///
+    /// `true` once the header block is complete, or an error if the stream
+    /// failed or ended in the middle of a header block.
+    fn add(
+        &mut self,
+        line: Result<Option<Vec<u8>>, TransportError>,
+    ) -> Result<bool, TransportError> {
+        match line {
+            // Empty lines before a header block are skipped
+            Ok(Some(line)) if line.is_empty() => Ok(self.lines > 0),
+            Ok(Some(line)) => {
+                self.lines += 1;
+                self.parse(&line);
+                Ok(false)
+            }
+            Ok(None) if self.lines == 0 => Ok(true),
+            Ok(None) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended inside a header block",
+            )
+            .into()),
+            Err(TransportError::FrameTooLarge { limit }) => {
+                self.lines += 1;
+                self.report(format!("header line exceeds {} bytes", limit));
+                Ok(false)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Parses one `Name: value` header line.
+    fn parse(&mut self, line: &[u8]) {
+        let Some((name, value)) = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| line.split_once(':'))
+            .filter(|(name, _)| is_token(name))
+        else {
+            let line = String::from_utf8_lossy(line);
+            return self.report(format!("malformed header line `{}`", line));
+        };
+        let value = value.trim();
+
+        if name.eq_ignore_ascii_case("Content-Length") {
+            match value.parse::<usize>() {
+                Ok(length) if self.content_length.is_some_and(|known| known != length) => {
+                    self.report("conflicting Content-Length headers".to_string())
+                }
+                Ok(length) => self.content_length = Some(length),
+                Err(_) => self.report(format!("invalid Content-Length `{}`", value)),
+            }
+        } else if name.eq_ignore_ascii_case("Content-Type") {
+            let charset = value
+                .split(';')
+                .skip(1)
+                .filter_map(|parameter| parameter.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+                .map(|(_, charset)| charset.trim().trim_matches('"'));
+
+            if let Some(charset) = charset
+                && !charset.eq_ignore_ascii_case("utf-8")
+                && !charset.eq_ignore_ascii_case("utf8")
+            {
+                self.report(format!("unsupported charset `{}`", charset));
+            }
+        }
+    }
+
+    /// Records a problem with the header block, keeping the first one.
+    fn report(&mut self, problem: String) {
+        self.problem.get_or_insert(problem);
+    }
+}
+
+// ===========================================================================
+// STRUCT: JsonSkipper
+// ===
+
+/// Skips one JSON object or array in a stream, to resynchronize after a frame
+/// without a usable `Content-Length`.
+///
+/// Only nesting and strings are tracked, which is enough to find the end of
+/// the value without parsing it. Anything that does not start like an object
+/// or array is left in the stream.
+struct JsonSkipper {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    remaining: usize,
+}
+
+impl JsonSkipper {
+    /// Creates a skipper that gives up after `limit` bytes.
+    fn new(limit: usize) -> Self {
+        Self {
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            remaining: limit,
+        }
+    }
+
+    /// Scans a chunk of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The bytes currently available from the stream
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes to consume from the stream, and whether the value
+    /// has been skipped.
+    fn scan(&mut self, chunk: &[u8]) -> (usize, bool) {
+        if chunk.is_empty() {
+            return (0, true);
+        }
+
+        for (index, &byte) in chunk.iter().enumerate() {
+            if self.remaining == 0 {
+                return (index, true);
+            }
+            self.remaining -= 1;
+
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 0 => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        return (index + 1, true);
+                    }
+                }
+                b'"' if self.depth > 0 => self.in_string = true,
+                _ if self.depth > 0 || byte.is_ascii_whitespace() => {}
+                _ => return (index, true),
+            }
+        }
+
+        (chunk.len(), false)
+    }
+}
+
+// ===========================================================================
+// TESTS: ContentLengthCodec
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RpcRequest;
+    use futures::executor;
+    use std::io::{BufReader, Cursor};
+
+    /// Reads through a tiny buffer so every frame arrives in partial reads.
+    fn chunked(input: &[u8]) -> BufReader<Cursor<Vec<u8>>> {
+        BufReader::with_capacity(4, Cursor::new(input.to_vec()))
+    }
+
+    fn frame(headers: &str, body: &str) -> String {
+        format!("{}\r\n{}", headers, body)
+    }
+
+    const BODY: &str = r#"{"jsonrpc":"2.0","method":"ping","id":1}"#;
+
+    fn valid() -> String {
+        frame(&format!("Content-Length: {}\r\n", BODY.len()), BODY)
+    }
+
+    fn assert_request(codec: &ContentLengthCodec, reader: &mut dyn BufRead) {
+        match codec.read_message(reader) {
+            Ok(Some(RpcMessage::Request(request))) => assert_eq!(request.method(), "ping"),
+            other => panic!("expected a request, got {:?}", other),
+        }
+    }
+
+    fn assert_malformed(codec: &ContentLengthCodec, reader: &mut dyn BufRead, reason: &str) {
+        match codec.read_message(reader) {
+            Err(TransportError::MalformedFrame(problem)) => {
+                assert!(problem.contains(reason), "unexpected problem: {}", problem)
+            }
+            other => panic!("expected a malformed frame, got {:?}", other),
+        }
+    }
+
+    // ---------------------------------------------------------------------------
+    // Reading Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_read_frames_across_partial_reads() {
+        let input = format!(
+            "{}{}",
+            valid(),
+            frame(
+                &format!(
+                    "content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\nX-Trace: 1\r\n",
+                    BODY.len()
+                ),
+                BODY
+            )
+        );
+        let codec = ContentLengthCodec::new();
+        let mut reader = chunked(input.as_bytes());
+
+        assert_request(&codec, &mut reader);
+        assert_request(&codec, &mut reader);
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recover_from_malformed_headers() {
+        let codec = ContentLengthCodec::new();
+        let input = [
+            // A garbage header line, with a usable length
+            frame(
+                &format!("Content-Length: {}\r\nnot a header\r\n", BODY.len()),
+                BODY,
+            ),
+            valid(),
+            // An unsupported charset
+            frame(
+                &format!(
+                    "Content-Length: {}\r\nContent-Type: text/json; charset=latin1\r\n",
+                    BODY.len()
+                ),
+                BODY,
+            ),
+            valid(),
+            // No usable length: the JSON body is skipped by scanning
+            frame("Content-Length: lots\r\n", r#"{"a":"}\"]","b":[1,{}]}"#),
+            valid(),
+            frame("X-Missing: length\r\n", BODY),
+            valid(),
+        ]
+        .concat();
+        let mut reader = chunked(input.as_bytes());
+
+        assert_malformed(&codec, &mut reader, "malformed header line");
+        assert_request(&codec, &mut reader);
+        assert_malformed(&codec, &mut reader, "unsupported charset");
+        assert_request(&codec, &mut reader);
+        assert_malformed(&codec, &mut reader, "invalid Content-Length");
+        assert_request(&codec, &mut reader);
+        assert_malformed(&codec, &mut reader, "missing Content-Length");
+        assert_request(&codec, &mut reader);
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_body_size_limit() {
+        let codec = ContentLengthCodec::with_max_body_size(BODY.len());
+        let large = format!(r#"{{"jsonrpc":"2.0","method":"{}"}}"#, "x".repeat(100));
+        let input = format!(
+            "{}{}",
+            frame(&format!("Content-Length: {}\r\n", large.len()), &large),
+            valid()
+        );
+        let mut reader = chunked(input.as_bytes());
+
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::FrameTooLarge { limit }) if limit == BODY.len()
+        ));
+        assert_request(&codec, &mut reader);
+    }
+
+    #[test]
+    fn test_long_header_line() {
+        let codec = ContentLengthCodec::new();
+        let input = format!(
+            "{}{}",
+            frame(
+                &format!(
+                    "Content-Length: {}\r\nX-Long: {}\r\n",
+                    BODY.len(),
+                    "x".repeat(MAX_HEADER_LINE)
+                ),
+                BODY
+            ),
+            valid()
+        );
+        let mut reader = chunked(input.as_bytes());
+
+        assert_malformed(&codec, &mut reader, "header line exceeds");
+        assert_request(&codec, &mut reader);
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let codec = ContentLengthCodec::new();
+
+        // The stream ends inside the header block
+        let mut reader = chunked(b"Content-Length: 10\r\n");
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::Io(_))
+        ));
+
+        // The stream ends inside the body
+        let mut reader = chunked(b"Content-Length: 10\r\n\r\n{}");
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::Io(_))
+        ));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Writing Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_write_with_content_type() {
+        let mut codec = ContentLengthCodec::new();
+        codec.set_content_type("application/vscode-jsonrpc; charset=utf-8");
+
+        let mut request = RpcRequest::new();
+        request.set_id(1).set_method("ping".to_string());
+
+        let mut buffer = Vec::new();
+        codec
+            .write_message(&mut buffer, &RpcMessage::from(request))
+            .unwrap();
+
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        let (headers, body) = text.split_once("\r\n\r\n").unwrap();
+        assert_eq!(
+            headers,
+            format!(
+                "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8",
+                body.len()
+            )
+        );
+        assert_request(&codec, &mut Cursor::new(buffer));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Async Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_async_roundtrip_and_recovery() {
+        let codec = ContentLengthCodec::with_max_body_size(64);
+
+        executor::block_on(async {
+            let mut buffer = futures::io::Cursor::new(Vec::new());
+            let mut request = RpcRequest::new();
+            request.set_id(1).set_method("ping".to_string());
+            codec
+                .write_message_async(&mut buffer, &RpcMessage::from(request))
+                .await
+                .unwrap();
+
+            let mut bytes = buffer.into_inner();
+            bytes.extend_from_slice(frame("Content-Length: 100\r\n", &"x".repeat(100)).as_bytes());
+            bytes.extend_from_slice(frame("Bad header\r\n", "[1, [2]]").as_bytes());
+            bytes.extend_from_slice(valid().as_bytes());
+
+            let mut reader =
+                futures::io::BufReader::with_capacity(4, futures::io::Cursor::new(bytes));
+
+            let first = codec.read_message_async(&mut reader).await.unwrap();
+            assert!(matches!(first, Some(RpcMessage::Request(_))));
+            assert!(matches!(
+                codec.read_message_async(&mut reader).await,
+                Err(TransportError::FrameTooLarge { limit: 64 })
+            ));
+            assert!(matches!(
+                codec.read_message_async(&mut reader).await,
+                Err(TransportError::MalformedFrame(_))
+            ));
+            let last = codec.read_message_async(&mut reader).await.unwrap();
+            assert!(matches!(last, Some(RpcMessage::Request(_))));
+            assert!(
+                codec
+                    .read_message_async(&mut reader)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        });
+    }
+}
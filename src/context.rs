@@ -0,0 +1,204 @@
+use crate::cancellation::InFlight;
+use crate::{CancellationToken, RpcClient};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+// ===========================================================================
+// STRUCT: RpcContext
+// ===
+
+/// Describes the connection a request arrived on.
+///
+/// Servers build one context per connection and dispatch every request
+/// through the router with it. Handlers registered with
+/// `RpcRouter::register_with_context()` receive it alongside their params.
+/// Requests dispatched without a connection, such as with `RpcRouter::handle()`,
+/// get an empty context.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcContext, RpcError, RpcMessage, RpcRequest, RpcRouter};
+///
+/// let mut router = RpcRouter::new();
+/// router.register_with_context("whoami", |_: (), context: &RpcContext| {
+///     Ok::<_, RpcError>(context.peer_addr().map(|addr| addr.to_string()))
+/// });
+///
+/// let mut context = RpcContext::new();
+/// context.set_peer_addr("127.0.0.1:4000".parse().unwrap());
+///
+/// let mut request = RpcRequest::new();
+/// request.set_method("whoami".to_string());
+/// let reply = router.handle_message_with_context(RpcMessage::from(request), &context);
+///
+/// match reply {
+///     Some(RpcMessage::Response(response)) => {
+///         assert_eq!(response.result().unwrap(), "127.0.0.1:4000")
+///     }
+///     other => panic!("unexpected reply: {:?}", other),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RpcContext {
+    peer_addr: Option<SocketAddr>,
+    peer_credentials: Option<PeerCredentials>,
+    client: Option<RpcClient>,
+    cancellation: CancellationToken,
+    in_flight: Arc<InFlight>,
+}
+
+impl RpcContext {
+    /// Creates an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the network address of the peer, if the connection has one.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Sets the network address of the peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer_addr` - The address the connection comes from
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_peer_addr(&mut self, peer_addr: SocketAddr) -> &mut Self {
+        self.peer_addr = Some(peer_addr);
+        self
+    }
+
+    /// Returns the credentials of the peer process, if the connection is
+    /// local and the operating system reports them.
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.peer_credentials
+    }
+
+    /// Sets the credentials of the peer process.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer_credentials` - The identity of the process on the other end
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_peer_credentials(&mut self, peer_credentials: PeerCredentials) -> &mut Self {
+        self.peer_credentials = Some(peer_credentials);
+        self
+    }
+
+    /// Returns a client that calls the peer back over the same connection,
+    /// if the connection carries calls in both directions.
+    ///
+    /// Handlers can use it to call methods on the peer or to push
+    /// notifications to it, such as from a `WebSocketServer`.
+    pub fn client(&self) -> Option<&RpcClient> {
+        self.client.as_ref()
+    }
+
+    /// Sets the client that calls the peer back over the same connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client sending on the connection the request arrived on
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_client(&mut self, client: RpcClient) -> &mut Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Returns the cancellation token of the request being handled.
+    ///
+    /// The token is cancelled when the client sends a `$/cancelRequest`
+    /// notification for the request. Outside of a request, such as in a
+    /// connection hook, it is never cancelled.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Returns a copy of the context for one request, carrying its token.
+    pub(crate) fn with_cancellation(&self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the tokens of the requests being handled on the connection.
+    pub(crate) fn in_flight(&self) -> &InFlight {
+        &self.in_flight
+    }
+}
+
+// ===========================================================================
+// STRUCT: PeerCredentials
+// ===
+
+/// The identity of the process on the other end of a local connection.
+///
+/// The operating system records these when the connection is made, so unlike
+/// anything the peer sends they can be trusted for authorization. `UnixServer`
+/// puts them in the `RpcContext` of every connection it serves.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{PeerCredentials, RpcContext, RpcError};
+///
+/// fn require_root(context: &RpcContext) -> Result<(), RpcError> {
+///     match context.peer_credentials() {
+///         Some(credentials) if credentials.uid() == 0 => Ok(()),
+///         _ => Err(RpcError::new(-32001, "permission denied")),
+///     }
+/// }
+///
+/// let mut context = RpcContext::new();
+/// assert!(require_root(&context).is_err());
+///
+/// context.set_peer_credentials(PeerCredentials::new(0, 0, Some(42)));
+/// assert!(require_root(&context).is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PeerCredentials {
+    uid: u32,
+    gid: u32,
+    pid: Option<u32>,
+}
+
+impl PeerCredentials {
+    /// Creates a set of credentials.
+    ///
+    /// # Arguments
+    ///
+    /// * `uid` - The user ID of the peer process
+    /// * `gid` - The group ID of the peer process
+    /// * `pid` - The process ID, if the operating system reports it
+    pub fn new(uid: u32, gid: u32, pid: Option<u32>) -> Self {
+        Self { uid, gid, pid }
+    }
+
+    /// Returns the effective user ID of the peer process.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the effective group ID of the peer process.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the process ID of the peer, which only some operating systems
+    /// report.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+}
@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Error as JsonError;
+use serde_json::Value as JsonValue;
+use std::fmt;
+
+// ===========================================================================
+// STRUCT: RpcError
+// ===
+
+/// Represents the error object of a JSON-RPC 2.0 response.
+///
+/// An error consists of a numeric `code`, a short `message` and optional
+/// `data` with additional information. Constructors are provided for the
+/// standard error codes defined by the JSON-RPC 2.0 specification.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::RpcError;
+/// use serde_json::json;
+///
+/// let error = RpcError::invalid_params().with_data(json!({"parameter": "account"}));
+/// assert_eq!(error.code(), RpcError::INVALID_PARAMS);
+/// assert_eq!(error.message(), "Invalid params");
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<JsonValue>,
+}
+
+impl RpcError {
+    /// Invalid JSON was received by the server.
+    pub const PARSE_ERROR: i64 = -32700;
+
+    /// The JSON sent is not a valid request object.
+    pub const INVALID_REQUEST: i64 = -32600;
+
+    /// The method does not exist or is not available.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+
+    /// Invalid method parameters.
+    pub const INVALID_PARAMS: i64 = -32602;
+
+    /// Internal JSON-RPC error.
+    pub const INTERNAL_ERROR: i64 = -32603;
+
+    /// The lowest code of the range reserved for implementation-defined server errors.
+    pub const SERVER_ERROR_MIN: i64 = -32099;
+
+    /// The highest code of the range reserved for implementation-defined server errors.
+    pub const SERVER_ERROR_MAX: i64 = -32000;
+
+    /// The request was cancelled by the client, as defined by the Language Server Protocol.
+    pub const REQUEST_CANCELLED: i64 = -32800;
+
+    /// The server has too many requests in progress on the connection to take another.
+    pub const SERVER_BUSY: i64 = -32001;
+
+    /// Creates a new error with the given code and message.
+    ///
+    /// Use this for application-defined errors. The standard errors have
+    /// dedicated constructors.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The error code
+    /// * `message` - A short description of the error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcError;
+    /// let error = RpcError::new(1001, "Insufficient funds");
+    /// assert_eq!(error.code(), 1001);
+    /// ```
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Creates a "Parse error" (-32700).
+    ///
+    /// Invalid JSON was received and could not be parsed.
+    pub fn parse_error() -> Self {
+        Self::new(Self::PARSE_ERROR, "Parse error")
+    }
+
+    /// Creates an "Invalid Request" error (-32600).
+    ///
+    /// The JSON sent is not a valid request object.
+    pub fn invalid_request() -> Self {
+        Self::new(Self::INVALID_REQUEST, "Invalid Request")
+    }
+
+    /// Creates a "Method not found" error (-32601).
+    ///
+    /// The method does not exist or is not available.
+    pub fn method_not_found() -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, "Method not found")
+    }
+
+    /// Creates an "Invalid params" error (-32602).
+    ///
+    /// The method parameters are invalid.
+    pub fn invalid_params() -> Self {
+        Self::new(Self::INVALID_PARAMS, "Invalid params")
+    }
+
+    /// Creates an "Internal error" (-32603).
+    ///
+    /// An internal JSON-RPC error occurred while handling the request.
+    pub fn internal_error() -> Self {
+        Self::new(Self::INTERNAL_ERROR, "Internal error")
+    }
+
+    /// Creates a "Request cancelled" error (-32800).
+    ///
+    /// The client cancelled the request before the handler completed it.
+    pub fn request_cancelled() -> Self {
+        Self::new(Self::REQUEST_CANCELLED, "Request cancelled")
+    }
+
+    /// Creates a "Server busy" error (-32001).
+    ///
+    /// The connection already has as many requests in progress as the server
+    /// handles at once; the request was not run and can be retried.
+    pub fn server_busy() -> Self {
+        Self::server_error(Self::SERVER_BUSY, "Server busy")
+    }
+
+    /// Creates an implementation-defined server error.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The error code, between -32099 and -32000. A code outside
+    ///   of that range is clamped to its nearest end, so the error is always
+    ///   a server error
+    /// * `message` - A short description of the error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcError;
+    /// let error = RpcError::server_error(-32001, "Server busy");
+    /// assert!(error.is_server_error());
+    ///
+    /// assert_eq!(RpcError::server_error(-1, "Out of range").code(), -32000);
+    /// ```
+    pub fn server_error(code: i64, message: impl Into<String>) -> Self {
+        Self::new(
+            code.clamp(Self::SERVER_ERROR_MIN, Self::SERVER_ERROR_MAX),
+            message,
+        )
+    }
+
+    /// Attaches additional information to the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Any JSON value describing the error in more detail
+    ///
+    /// # Returns
+    ///
+    /// The error with its `data` member set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcError;
+    /// use serde_json::json;
+    ///
+    /// let error = RpcError::method_not_found().with_data(json!({"method": "foo"}));
+    /// assert_eq!(error.data(), Some(&json!({"method": "foo"})));
+    /// ```
+    pub fn with_data(mut self, data: JsonValue) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Returns the error code.
+    pub fn code(&self) -> i64 {
+        self.code
+    }
+
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the additional error information, if any.
+    pub fn data(&self) -> Option<&JsonValue> {
+        self.data.as_ref()
+    }
+
+    /// Checks if the error code is in the implementation-defined server error range.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the code is between -32099 and -32000, `false` otherwise.
+    pub fn is_server_error(&self) -> bool {
+        (Self::SERVER_ERROR_MIN..=Self::SERVER_ERROR_MAX).contains(&self.code)
+    }
+
+    /// Converts the error to a JSON value.
+    ///
+    /// # Returns
+    ///
+    /// A `JsonValue` representation of the error, or `JsonValue::Null` on error.
+    pub fn to_json(&self) -> JsonValue {
+        serde_json::to_value(self).unwrap_or(JsonValue::Null)
+    }
+
+    /// Creates an error from a JSON value.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - A JSON object with `code`, `message` and optional `data` members
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcError)` - Successfully deserialized error
+    /// * `Err(JsonError)` - Deserialization failed
+    pub fn from_json(json: JsonValue) -> Result<Self, JsonError> {
+        serde_json::from_value(json)
+    }
+}
+
+impl fmt::Display for RpcError {
+    /// Formats the error as its message followed by its code.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+// ===========================================================================
+// TESTS: RpcError
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_standard_errors() {
+        let cases = [
+            (RpcError::parse_error(), -32700, "Parse error"),
+            (RpcError::invalid_request(), -32600, "Invalid Request"),
+            (RpcError::method_not_found(), -32601, "Method not found"),
+            (RpcError::invalid_params(), -32602, "Invalid params"),
+            (RpcError::internal_error(), -32603, "Internal error"),
+            (RpcError::request_cancelled(), -32800, "Request cancelled"),
+        ];
+
+        for (error, code, message) in cases {
+            assert_eq!(error.code(), code);
+            assert_eq!(error.message(), message);
+            assert_eq!(error.data(), None);
+            assert!(!error.is_server_error());
+        }
+
+        let busy = RpcError::server_busy();
+        assert_eq!((busy.code(), busy.message()), (-32001, "Server busy"));
+        assert!(busy.is_server_error());
+    }
+
+    #[test]
+    fn test_server_error_range() {
+        assert_eq!(RpcError::server_error(-32000, "a").code(), -32000);
+        assert_eq!(RpcError::server_error(-32099, "b").code(), -32099);
+        assert!(RpcError::server_error(-32050, "c").is_server_error());
+        assert!(!RpcError::new(-31999, "d").is_server_error());
+    }
+
+    #[test]
+    fn test_server_error_out_of_range() {
+        let error = RpcError::server_error(-32700, "below the range");
+        assert_eq!(error.code(), RpcError::SERVER_ERROR_MIN);
+        assert!(error.is_server_error());
+
+        let error = RpcError::server_error(42, "above the range");
+        assert_eq!(error.code(), RpcError::SERVER_ERROR_MAX);
+        assert_eq!(error.message(), "above the range");
+    }
+
+    #[test]
+    fn test_with_data() {
+        let error = RpcError::new(1, "Custom").with_data(json!({"detail": [1, 2]}));
+        assert_eq!(error.data(), Some(&json!({"detail": [1, 2]})));
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let error = RpcError::invalid_params().with_data(json!("missing field"));
+
+        let json = error.to_json();
+        assert_eq!(
+            json,
+            json!({"code": -32602, "message": "Invalid params", "data": "missing field"})
+        );
+        assert_eq!(RpcError::from_json(json).unwrap(), error);
+
+        // The data member is omitted when not set
+        let json = RpcError::parse_error().to_json();
+        assert!(json.get("data").is_none());
+
+        // Code and message are required
+        assert!(RpcError::from_json(json!({"message": "no code"})).is_err());
+    }
+
+    #[test]
+    fn test_display_and_error_trait() {
+        let error = RpcError::method_not_found();
+        assert_eq!(error.to_string(), "Method not found (code -32601)");
+
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert_eq!(boxed.to_string(), "Method not found (code -32601)");
+    }
+}
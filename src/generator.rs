@@ -0,0 +1,245 @@
+use crate::RpcId;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::{Builder, Uuid};
+
+// ===========================================================================
+// TRAIT: IdGenerator
+// ===
+
+/// A source of request IDs.
+///
+/// `RpcRequest::new()` draws from a process-wide counter. Clients that need IDs
+/// of their own, such as several clients sharing a process or tests that assert
+/// on exact IDs, pass a generator to `RpcRequest::with_generator()` instead.
+///
+/// Generators are shared between threads, so `next_id()` takes `&self`.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{IdGenerator, RpcId, RpcRequest, SequentialIdGenerator};
+///
+/// let generator = SequentialIdGenerator::new();
+/// let first = RpcRequest::with_generator(&generator);
+/// let second = RpcRequest::with_generator(&generator);
+///
+/// assert_eq!(first.id(), Some(&RpcId::Number(1)));
+/// assert_eq!(second.id(), Some(&RpcId::Number(2)));
+/// ```
+pub trait IdGenerator: Send + Sync {
+    /// Returns the next request ID.
+    ///
+    /// # Returns
+    ///
+    /// An ID that has not been returned by this generator before.
+    fn next_id(&self) -> RpcId;
+}
+
+// ===========================================================================
+// STRUCT: SequentialIdGenerator
+// ===
+
+/// Generates numeric IDs from an atomic counter: 1, 2, 3, ...
+///
+/// Each instance keeps its own counter, so two clients with their own
+/// generators never influence each other's IDs.
+#[derive(Debug)]
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Creates a generator whose first ID is 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{IdGenerator, RpcId, SequentialIdGenerator};
+    /// let generator = SequentialIdGenerator::new();
+    /// assert_eq!(generator.next_id(), RpcId::Number(1));
+    /// ```
+    pub const fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    /// Creates a generator whose first ID is `first`.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The first ID to hand out
+    pub const fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> RpcId {
+        RpcId::from(self.next.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+// ===========================================================================
+// STRUCT: UuidIdGenerator
+// ===
+
+/// Generates random UUID v4 string IDs, such as `"67e55044-10b1-426f-9247-bb680e5fe0c8"`.
+///
+/// UUIDs stay unique across processes and restarts, which helps when IDs end up
+/// in logs or are correlated across services.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{IdGenerator, UuidIdGenerator};
+/// let id = UuidIdGenerator.next_id();
+/// assert_eq!(id.as_str().unwrap().len(), 36);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UuidIdGenerator;
+
+impl IdGenerator for UuidIdGenerator {
+    fn next_id(&self) -> RpcId {
+        RpcId::String(Uuid::new_v4().to_string())
+    }
+}
+
+// ===========================================================================
+// STRUCT: SeededIdGenerator
+// ===
+
+/// Generates UUID v4 string IDs from a seed, for reproducible tests.
+///
+/// Two generators with the same seed return the same sequence of IDs, so tests
+/// can assert on exact UUID-shaped IDs. The IDs come from a simple
+/// (SplitMix64) pseudo-random generator and must not be used where IDs need
+/// to be unpredictable.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{IdGenerator, SeededIdGenerator};
+///
+/// let a = SeededIdGenerator::new(42);
+/// let b = SeededIdGenerator::new(42);
+/// assert_eq!(a.next_id(), b.next_id());
+/// ```
+#[derive(Debug)]
+pub struct SeededIdGenerator {
+    state: AtomicU64,
+}
+
+impl SeededIdGenerator {
+    /// The increment of the SplitMix64 sequence.
+    const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    /// Creates a generator producing the sequence for `seed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed selecting the sequence of IDs
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Returns the next 64 pseudo-random bits.
+    fn next_u64(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(Self::GAMMA, Ordering::SeqCst)
+            .wrapping_add(Self::GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl IdGenerator for SeededIdGenerator {
+    fn next_id(&self) -> RpcId {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.next_u64().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next_u64().to_le_bytes());
+
+        RpcId::String(Builder::from_random_bytes(bytes).into_uuid().to_string())
+    }
+}
+
+// ===========================================================================
+// TESTS: IdGenerator
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_sequential_generator() {
+        let generator = SequentialIdGenerator::new();
+        assert_eq!(generator.next_id(), RpcId::Number(1));
+        assert_eq!(generator.next_id(), RpcId::Number(2));
+
+        let generator = SequentialIdGenerator::starting_at(100);
+        assert_eq!(generator.next_id(), RpcId::Number(100));
+
+        // Separate instances keep separate counters
+        let other = SequentialIdGenerator::new();
+        assert_eq!(other.next_id(), RpcId::Number(1));
+    }
+
+    #[test]
+    fn test_sequential_generator_across_threads() {
+        let generator = Arc::new(SequentialIdGenerator::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = generator.clone();
+                thread::spawn(move || (0..100).map(|_| generator.next_id()).collect::<Vec<_>>())
+            })
+            .collect();
+
+        let ids: HashSet<RpcId> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(ids.len(), 400);
+    }
+
+    #[test]
+    fn test_uuid_generator() {
+        let first = UuidIdGenerator.next_id();
+        let second = UuidIdGenerator.next_id();
+        assert_ne!(first, second);
+
+        let uuid = Uuid::parse_str(first.as_str().unwrap()).unwrap();
+        assert_eq!(uuid.get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_seeded_generator_is_deterministic() {
+        let a = SeededIdGenerator::new(7);
+        let b = SeededIdGenerator::new(7);
+        let c = SeededIdGenerator::new(8);
+
+        let sequence: Vec<RpcId> = (0..5).map(|_| a.next_id()).collect();
+        assert_eq!(sequence, (0..5).map(|_| b.next_id()).collect::<Vec<_>>());
+        assert_ne!(sequence[0], c.next_id());
+
+        // All IDs in a sequence are distinct valid UUID v4 strings
+        assert_eq!(sequence.iter().collect::<HashSet<_>>().len(), 5);
+        for id in &sequence {
+            let uuid = Uuid::parse_str(id.as_str().unwrap()).unwrap();
+            assert_eq!(uuid.get_version_num(), 4);
+        }
+    }
+}
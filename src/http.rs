@@ -0,0 +1,934 @@
+use crate::codec;
+use crate::connections::{Connections, accept_next};
+use crate::{RpcContext, RpcMessage, RpcRouter, TcpServerHandle, TransportError};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+
+/// The maximum length of the request or status line and of each header line.
+const MAX_HEADER_LINE: usize = 8 * 1024;
+
+/// The maximum number of header lines in one message.
+const MAX_HEADERS: usize = 100;
+
+// ===========================================================================
+// STRUCT: HttpResponse
+// ===
+
+/// The HTTP response to a JSON-RPC POST, as produced by `handle_http_post()`.
+///
+/// The status is one of:
+///
+/// * `200 OK` - The body holds the JSON-RPC reply, which may be an error
+///   response such as a parse error
+/// * `204 No Content` - The message needed no reply, such as a notification or
+///   a batch made only of notifications
+/// * `415 Unsupported Media Type` - The request was not `application/json`
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcError, RpcRouter, handle_http_post};
+///
+/// let mut router = RpcRouter::new();
+/// router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+///
+/// let body = br#"{"jsonrpc":"2.0","method":"add","params":[2,3],"id":1}"#;
+/// let response = handle_http_post(&router, Some("application/json"), body);
+/// assert_eq!(response.status(), 200);
+/// assert_eq!(response.content_type(), Some("application/json"));
+///
+/// let body = br#"{"jsonrpc":"2.0","method":"add","params":[2,3]}"#;
+/// let response = handle_http_post(&router, Some("application/json"), body);
+/// assert_eq!(response.status(), 204);
+/// assert!(response.body().is_empty());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Creates a response with an empty body.
+    pub(crate) fn empty(status: u16) -> Self {
+        Self {
+            status,
+            body: Vec::new(),
+        }
+    }
+
+    /// Returns the HTTP status code.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Returns the standard reason phrase of the status code.
+    pub fn reason(&self) -> &'static str {
+        reason_phrase(self.status)
+    }
+
+    /// Returns the value of the `Content-Type` header, or `None` if the
+    /// response has no body.
+    pub fn content_type(&self) -> Option<&'static str> {
+        (!self.body.is_empty()).then_some("application/json")
+    }
+
+    /// Returns the body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Consumes the response, returning its body.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+// ===========================================================================
+// FUNCTIONS: HTTP Handler
+// ===
+
+/// Handles the body of an HTTP POST carrying a JSON-RPC message.
+///
+/// This is the core of `HttpServer`, without any networking, so a JSON-RPC
+/// endpoint can be mounted in an existing HTTP server: pass it the request's
+/// `Content-Type` header and body, and send back the returned status, content
+/// type and body.
+///
+/// Invalid JSON and invalid messages are answered like on any other
+/// transport, with a JSON-RPC error response and status 200.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching the request
+/// * `content_type` - The value of the request's `Content-Type` header, if any
+/// * `body` - The body of the request
+///
+/// # Returns
+///
+/// The HTTP response to send back.
+pub fn handle_http_post(
+    router: &RpcRouter,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> HttpResponse {
+    handle_http_post_with_context(router, content_type, body, &RpcContext::default())
+}
+
+/// Handles the body of an HTTP POST that arrived on a connection described by
+/// `context`.
+///
+/// This is the same as `handle_http_post()`, but handlers registered with
+/// `RpcRouter::register_with_context()` receive `context`.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching the request
+/// * `content_type` - The value of the request's `Content-Type` header, if any
+/// * `body` - The body of the request
+/// * `context` - The connection the request arrived on
+///
+/// # Returns
+///
+/// The HTTP response to send back.
+pub fn handle_http_post_with_context(
+    router: &RpcRouter,
+    content_type: Option<&str>,
+    body: &[u8],
+    context: &RpcContext,
+) -> HttpResponse {
+    if !content_type.is_some_and(is_json) {
+        return HttpResponse::empty(415);
+    }
+
+    let reply = match codec::parse_frame(body) {
+        Ok(message) => router.handle_message_with_context(message, context),
+        Err(error) => Some(RpcMessage::Response(error.to_response())),
+    };
+
+    match reply {
+        Some(reply) => HttpResponse {
+            status: 200,
+            body: codec::encode_message(&reply),
+        },
+        None => HttpResponse::empty(204),
+    }
+}
+
+/// Checks if a `Content-Type` header value is JSON in UTF-8.
+pub(crate) fn is_json(content_type: &str) -> bool {
+    let mut parts = content_type.split(';');
+    let media_type = parts.next().unwrap_or_default().trim();
+
+    media_type.eq_ignore_ascii_case("application/json")
+        && parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+            .all(|(_, charset)| {
+                let charset = charset.trim().trim_matches('"');
+                charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8")
+            })
+}
+
+/// Returns the standard reason phrase of an HTTP status code.
+pub(crate) fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        426 => "Upgrade Required",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
+
+// ===========================================================================
+// STRUCT: MessageHead
+// ===
+
+/// The start line and headers of an HTTP/1.x request or response, parsed
+/// with `httparse`.
+#[derive(Debug)]
+pub(crate) struct MessageHead {
+    method: Option<String>,
+    status: Option<u16>,
+    minor_version: u8,
+    headers: Vec<(String, String)>,
+}
+
+impl MessageHead {
+    /// Reads the raw bytes of a message head from a stream, up to and
+    /// including the empty line that ends it.
+    ///
+    /// Empty lines before the start line are skipped, and every line ending
+    /// is normalized to CRLF.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Vec<u8>))` - The head, with the stream positioned at the body
+    /// * `Ok(None)` - The stream ended before a message started
+    /// * `Err(TransportError::FrameTooLarge)` - A line was too long or there were too many headers
+    pub(crate) fn read_raw(reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError> {
+        let mut head = loop {
+            match codec::read_line(reader, MAX_HEADER_LINE)? {
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Ok(None),
+            }
+        };
+        head.extend_from_slice(b"\r\n");
+
+        for _ in 0..=MAX_HEADERS {
+            let line = codec::read_line(reader, MAX_HEADER_LINE)?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended inside headers")
+            })?;
+            head.extend_from_slice(&line);
+            head.extend_from_slice(b"\r\n");
+            if line.is_empty() {
+                return Ok(Some(head));
+            }
+        }
+        Err(TransportError::FrameTooLarge { limit: MAX_HEADERS })
+    }
+
+    /// Reads a request head from a stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(MessageHead))` - The head, with the stream positioned at the body
+    /// * `Ok(None)` - The stream ended before a message started
+    /// * `Err(TransportError::FrameTooLarge)` - A line was too long or there were too many headers
+    /// * `Err(TransportError::MalformedFrame)` - The head is not a valid HTTP/1.x request
+    pub(crate) fn read_request(reader: &mut dyn BufRead) -> Result<Option<Self>, TransportError> {
+        let Some(raw) = Self::read_raw(reader)? else {
+            return Ok(None);
+        };
+        Self::parse_request(&raw).map(Some).map_err(malformed)
+    }
+
+    /// Reads a response head from a stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(MessageHead))` - The head, with the stream positioned at the body
+    /// * `Ok(None)` - The stream ended before a message started
+    /// * `Err(TransportError::FrameTooLarge)` - A line was too long or there were too many headers
+    /// * `Err(TransportError::MalformedFrame)` - The head is not a valid HTTP/1.x response
+    pub(crate) fn read_response(reader: &mut dyn BufRead) -> Result<Option<Self>, TransportError> {
+        let Some(raw) = Self::read_raw(reader)? else {
+            return Ok(None);
+        };
+        Self::parse_response(&raw).map(Some).map_err(malformed)
+    }
+
+    /// Parses a request head read by `read_raw()`.
+    ///
+    /// # Returns
+    ///
+    /// The head, or the reason it is not a valid HTTP/1.x request, such as
+    /// `httparse::Error::Version` for another version of HTTP.
+    pub(crate) fn parse_request(raw: &[u8]) -> Result<Self, httparse::Error> {
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut request = httparse::Request::new(&mut headers);
+        if request.parse(raw)?.is_partial() {
+            return Err(httparse::Error::NewLine);
+        }
+
+        Ok(Self {
+            method: request.method.map(String::from),
+            status: None,
+            minor_version: request.version.unwrap_or_default(),
+            headers: collect_headers(request.headers)?,
+        })
+    }
+
+    /// Parses a response head read by `read_raw()`.
+    ///
+    /// # Returns
+    ///
+    /// The head, or the reason it is not a valid HTTP/1.x response.
+    pub(crate) fn parse_response(raw: &[u8]) -> Result<Self, httparse::Error> {
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut response = httparse::Response::new(&mut headers);
+        if response.parse(raw)?.is_partial() {
+            return Err(httparse::Error::NewLine);
+        }
+
+        Ok(Self {
+            method: None,
+            status: response.code,
+            minor_version: response.version.unwrap_or_default(),
+            headers: collect_headers(response.headers)?,
+        })
+    }
+
+    /// Returns the method of a request.
+    pub(crate) fn method(&self) -> Option<&str> {
+        self.method.as_deref()
+    }
+
+    /// Returns the status code of a response.
+    pub(crate) fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// Checks if the message was sent as HTTP/1.1 rather than HTTP/1.0.
+    pub(crate) fn is_http_11(&self) -> bool {
+        self.minor_version == 1
+    }
+
+    /// Returns the value of the first header named `name`, ignoring case.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Checks if a comma-separated header such as `Connection` lists `token`.
+    pub(crate) fn has_token(&self, name: &str, token: &str) -> bool {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .flat_map(|(_, value)| value.split(','))
+            .any(|item| item.trim().eq_ignore_ascii_case(token))
+    }
+
+    /// Returns the length of the body from the `Content-Length` header.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(usize))` - The length of the body
+    /// * `Ok(None)` - The header is missing
+    /// * `Err(TransportError::MalformedFrame)` - The header is invalid or repeated with different values
+    pub(crate) fn content_length(&self) -> Result<Option<usize>, TransportError> {
+        let mut length = None;
+        for (_, value) in self
+            .headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+        {
+            let value = value.parse::<usize>().map_err(|_| {
+                TransportError::MalformedFrame(format!("invalid Content-Length `{}`", value))
+            })?;
+            if length.is_some_and(|known| known != value) {
+                return Err(TransportError::MalformedFrame(
+                    "conflicting Content-Length headers".to_string(),
+                ));
+            }
+            length = Some(value);
+        }
+        Ok(length)
+    }
+}
+
+/// Copies the headers out of a parsed head; HTTP heads are ASCII, so values
+/// that are not UTF-8 are refused.
+fn collect_headers(
+    headers: &[httparse::Header<'_>],
+) -> Result<Vec<(String, String)>, httparse::Error> {
+    headers
+        .iter()
+        .map(|header| match std::str::from_utf8(header.value) {
+            Ok(value) => Ok((header.name.to_string(), value.to_string())),
+            Err(_) => Err(httparse::Error::HeaderValue),
+        })
+        .collect()
+}
+
+/// Reports a head `httparse` refused as a malformed frame.
+fn malformed(error: httparse::Error) -> TransportError {
+    TransportError::MalformedFrame(format!("invalid HTTP head: {}", error))
+}
+
+// ===========================================================================
+// STRUCT: HttpServer
+// ===
+
+/// Serves JSON-RPC over HTTP/1.1: every POST carries one message or batch,
+/// and the reply comes back in the response body.
+///
+/// Every connection runs on its own thread and stays open between requests
+/// unless the client asks to close it. Requests are answered as by
+/// `handle_http_post()`, with an `RpcContext` holding the peer's address.
+/// Methods other than POST are refused with `405 Method Not Allowed`, and
+/// bodies larger than the maximum body size with `413 Content Too Large`.
+/// Bodies must be sent with a `Content-Length`: any `Transfer-Encoding`,
+/// such as chunked, is refused with `501 Not Implemented`. The request path
+/// is not checked.
+///
+/// Each request on a connection is answered before the next is read, so
+/// `$/cancelRequest` notifications cannot reach a request still running and
+/// cancellation is not supported over HTTP.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{HttpServer, RpcError, RpcRouter};
+/// use std::io::{Read, Write};
+/// use std::net::TcpStream;
+/// use std::thread;
+///
+/// let mut router = RpcRouter::new();
+/// router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+///
+/// let server = HttpServer::bind("127.0.0.1:0").unwrap();
+/// let addr = server.local_addr().unwrap();
+/// let handle = server.handle().unwrap();
+/// let running = thread::spawn(move || server.serve(&router));
+///
+/// let body = r#"{"jsonrpc":"2.0","method":"add","params":[2,3],"id":1}"#;
+/// let mut socket = TcpStream::connect(addr).unwrap();
+/// write!(
+///     socket,
+///     "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+///      Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+///     body.len(),
+///     body
+/// )
+/// .unwrap();
+///
+/// let mut response = String::new();
+/// socket.read_to_string(&mut response).unwrap();
+/// assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+/// assert!(response.contains(r#""result":5"#));
+///
+/// handle.shutdown();
+/// running.join().unwrap().unwrap();
+/// ```
+pub struct HttpServer {
+    listener: TcpListener,
+    max_body_size: usize,
+    connections: Arc<Connections<TcpStream>>,
+}
+
+impl HttpServer {
+    /// The default maximum body size: 16 MiB.
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Binds a server.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on; port 0 picks a free port
+    ///
+    /// # Returns
+    ///
+    /// The server, or the error that prevented binding the address.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::from_listener(TcpListener::bind(addr)?))
+    }
+
+    /// Creates a server accepting connections from an existing listener.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The bound listener
+    pub fn from_listener(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            connections: Arc::new(Connections::new()),
+        }
+    }
+
+    /// Returns the maximum body size in bytes.
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Sets the maximum size of a request body.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_body_size` - The maximum size in bytes
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_max_body_size(&mut self, max_body_size: usize) -> &mut Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a handle that can shut the server down from another thread.
+    pub fn handle(&self) -> io::Result<TcpServerHandle> {
+        Ok(TcpServerHandle::new(
+            self.local_addr()?,
+            self.connections.clone(),
+        ))
+    }
+
+    /// Accepts connections and serves them until the server is shut down.
+    ///
+    /// Each connection is served on its own thread. Errors on a single
+    /// connection only end that connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router dispatching requests on every connection
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The server was shut down and every connection has ended
+    /// * `Err(io::Error)` - Accepting connections failed
+    pub fn serve(&self, router: &RpcRouter) -> io::Result<()> {
+        thread::scope(|scope| {
+            let result = loop {
+                let (socket, peer_addr) = match accept_next(|| self.listener.accept()) {
+                    Ok((socket, peer_addr)) => (Arc::new(socket), peer_addr),
+                    Err(error) => break Err(error),
+                };
+                let Some(id) = self.connections.track(&socket) else {
+                    break Ok(());
+                };
+
+                let connections = self.connections.clone();
+                scope.spawn(move || {
+                    let mut context = RpcContext::new();
+                    context.set_peer_addr(peer_addr);
+
+                    let _ = self.serve_connection(&socket, router, &context);
+                    let _ = socket.shutdown(Shutdown::Both);
+                    connections.untrack(id);
+                });
+            };
+
+            self.connections.close_all();
+            result
+        })
+    }
+
+    /// Answers requests on one connection until either side closes it.
+    fn serve_connection(
+        &self,
+        socket: &TcpStream,
+        router: &RpcRouter,
+        context: &RpcContext,
+    ) -> io::Result<()> {
+        socket.set_nodelay(true)?;
+        let mut reader = BufReader::new(socket);
+        let mut writer = socket;
+
+        loop {
+            let raw = match MessageHead::read_raw(&mut reader) {
+                Ok(Some(raw)) => raw,
+                Ok(None) => return Ok(()),
+                Err(TransportError::Io(error)) => return Err(error),
+                Err(TransportError::FrameTooLarge { .. }) => {
+                    return write_response(&mut writer, &HttpResponse::empty(431), &[], true);
+                }
+                Err(_) => return write_response(&mut writer, &HttpResponse::empty(400), &[], true),
+            };
+            let head = match MessageHead::parse_request(&raw) {
+                Ok(head) => head,
+                Err(error) => {
+                    let status = match error {
+                        httparse::Error::Version => 505,
+                        httparse::Error::TooManyHeaders => 431,
+                        _ => 400,
+                    };
+                    return write_response(&mut writer, &HttpResponse::empty(status), &[], true);
+                }
+            };
+
+            let (length, keep_alive) = match self.check_request(&head) {
+                Ok(accepted) => accepted,
+                Err(status) => {
+                    let headers: &[_] = match status {
+                        405 => &[("Allow", "POST")],
+                        _ => &[],
+                    };
+                    return write_response(
+                        &mut writer,
+                        &HttpResponse::empty(status),
+                        headers,
+                        true,
+                    );
+                }
+            };
+
+            // Clients waiting for permission to send the body get it only
+            // once the head has been accepted
+            if head.has_token("Expect", "100-continue") {
+                writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            }
+
+            let mut body = vec![0; length];
+            io::Read::read_exact(&mut reader, &mut body)?;
+
+            let response =
+                handle_http_post_with_context(router, head.header("Content-Type"), &body, context);
+            write_response(&mut writer, &response, &[], !keep_alive)?;
+            if !keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Checks that a request can be served.
+    ///
+    /// # Returns
+    ///
+    /// The length of the body and whether the connection stays open
+    /// afterwards, or the status of the error response.
+    fn check_request(&self, head: &MessageHead) -> Result<(usize, bool), u16> {
+        let keep_alive = head.is_http_11() && !head.has_token("Connection", "close");
+        if head.method() != Some("POST") {
+            return Err(405);
+        }
+        // Chunked bodies are not supported. Refusing every transfer coding,
+        // even next to a Content-Length, also keeps a proxy and this server
+        // from disagreeing on where the body ends.
+        if head.header("Transfer-Encoding").is_some() {
+            return Err(501);
+        }
+
+        match head.content_length() {
+            Ok(Some(length)) if length > self.max_body_size => Err(413),
+            Ok(Some(length)) => Ok((length, keep_alive)),
+            Ok(None) => Err(411),
+            Err(_) => Err(400),
+        }
+    }
+}
+
+impl fmt::Debug for HttpServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpServer")
+            .field("local_addr", &self.listener.local_addr().ok())
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+/// Writes an HTTP/1.1 response.
+pub(crate) fn write_response(
+    writer: &mut dyn Write,
+    response: &HttpResponse,
+    headers: &[(&str, &str)],
+    close: bool,
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status(), response.reason());
+    if let Some(content_type) = response.content_type() {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    head.push_str(&format!("Content-Length: {}\r\n", response.body().len()));
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if close {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str("\r\n");
+
+    let mut bytes = head.into_bytes();
+    bytes.extend_from_slice(response.body());
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+// ===========================================================================
+// TESTS: HttpServer
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RpcError;
+    use serde_json::{Value as JsonValue, json};
+    use std::io::Read;
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        router.register_with_context("whoami", |_: (), context: &RpcContext| {
+            Ok::<_, RpcError>(context.peer_addr().map(|addr| addr.to_string()))
+        });
+        router
+    }
+
+    fn post(body: &str) -> HttpResponse {
+        handle_http_post(&router(), Some("application/json"), body.as_bytes())
+    }
+
+    fn json_body(response: &HttpResponse) -> JsonValue {
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    // -----------------------------------------------------------------------
+    // Handler Tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_single_and_batch_replies() {
+        let response = post(r#"{"jsonrpc":"2.0","method":"add","params":[1,2],"id":1}"#);
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            json_body(&response),
+            json!({"jsonrpc": "2.0", "result": 3, "id": 1})
+        );
+
+        // Only the requests of a batch are answered
+        let response = post(
+            r#"[{"jsonrpc":"2.0","method":"add","params":[1,2]},
+                {"jsonrpc":"2.0","method":"add","params":[3,4],"id":2}]"#,
+        );
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            json_body(&response),
+            json!([{"jsonrpc": "2.0", "result": 7, "id": 2}])
+        );
+    }
+
+    #[test]
+    fn test_notifications_get_no_content() {
+        let response = post(r#"{"jsonrpc":"2.0","method":"add","params":[1,2]}"#);
+        assert_eq!(response, HttpResponse::empty(204));
+
+        let response = post(
+            r#"[{"jsonrpc":"2.0","method":"add","params":[1,2]},
+                {"jsonrpc":"2.0","method":"missing"}]"#,
+        );
+        assert_eq!(response.status(), 204);
+        assert_eq!(response.content_type(), None);
+    }
+
+    #[test]
+    fn test_invalid_messages_get_error_responses() {
+        let response = post(r#"{"jsonrpc":"2.0","method":"add""#);
+        assert_eq!(response.status(), 200);
+        assert_eq!(json_body(&response)["error"]["code"], json!(-32700));
+        assert_eq!(json_body(&response)["id"], JsonValue::Null);
+
+        let response = post("[]");
+        assert_eq!(response.status(), 200);
+        assert_eq!(json_body(&response)["error"]["code"], json!(-32600));
+
+        // Invalid batch members are answered one by one
+        let response =
+            post(r#"[{"jsonrpc":"2.0","method":"add","params":[2,3],"id":1},{"foo":1}]"#);
+        let body = json_body(&response);
+        assert_eq!(body[0], json!({"jsonrpc": "2.0", "result": 5, "id": 1}));
+        assert_eq!(body[1]["error"]["code"], json!(-32600));
+
+        let response = post("[1,2]");
+        assert_eq!(json_body(&response).as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_content_type_is_enforced() {
+        let body = br#"{"jsonrpc":"2.0","method":"add","params":[1,2],"id":1}"#;
+        let status = |content_type| handle_http_post(&router(), content_type, body).status();
+
+        assert_eq!(status(Some("application/json")), 200);
+        assert_eq!(status(Some("Application/JSON; charset=UTF-8")), 200);
+        assert_eq!(status(Some("application/json; charset=latin1")), 415);
+        assert_eq!(status(Some("text/plain")), 415);
+        assert_eq!(status(None), 415);
+    }
+
+    // -----------------------------------------------------------------------
+    // Server Tests
+    // -----------------------------------------------------------------------
+
+    /// Sends raw request bytes and reads one response.
+    fn exchange(
+        socket: &TcpStream,
+        reader: &mut BufReader<&TcpStream>,
+        request: &str,
+    ) -> (MessageHead, String) {
+        (&*socket).write_all(request.as_bytes()).unwrap();
+        read_response(reader)
+    }
+
+    fn read_response(reader: &mut BufReader<&TcpStream>) -> (MessageHead, String) {
+        let head = MessageHead::read_response(reader).unwrap().unwrap();
+        let mut body = vec![0; head.content_length().unwrap().unwrap()];
+        reader.read_exact(&mut body).unwrap();
+        (head, String::from_utf8(body).unwrap())
+    }
+
+    fn post_request(body: &str, extra_headers: &str) -> String {
+        format!(
+            "POST /rpc HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\n{}\r\n{}",
+            body.len(),
+            extra_headers,
+            body
+        )
+    }
+
+    #[test]
+    fn test_keep_alive_connection() {
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+            let socket = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(&socket);
+
+            // Several requests share the connection, and handlers see the peer
+            let body = r#"{"jsonrpc":"2.0","method":"whoami","id":1}"#;
+            let (head, reply) = exchange(&socket, &mut reader, &post_request(body, ""));
+            assert_eq!(head.status(), Some(200));
+            assert_eq!(head.header("content-type"), Some("application/json"));
+            let reply: JsonValue = serde_json::from_str(&reply).unwrap();
+            assert_eq!(
+                reply["result"],
+                json!(socket.local_addr().unwrap().to_string())
+            );
+
+            let body = r#"{"jsonrpc":"2.0","method":"add","params":[1,2]}"#;
+            let (head, reply) = exchange(&socket, &mut reader, &post_request(body, ""));
+            assert_eq!(head.status(), Some(204));
+            assert!(reply.is_empty());
+
+            // Clients expecting a 100 Continue get one before the response
+            let body = r#"{"jsonrpc":"2.0","method":"add","params":[2,3],"id":2}"#;
+            let request = post_request(body, "Expect: 100-continue\r\n");
+            (&socket).write_all(request.as_bytes()).unwrap();
+            let head = MessageHead::read_response(&mut reader).unwrap().unwrap();
+            assert_eq!(head.status(), Some(100));
+            let (head, reply) = read_response(&mut reader);
+            assert_eq!(head.status(), Some(200));
+            assert!(reply.contains(r#""result":5"#));
+            assert_eq!(handle.connection_count(), 1);
+
+            // Asking to close ends the connection after the response
+            let body = r#"{"jsonrpc":"2.0","method":"add","params":[1,1],"id":3}"#;
+            let request = post_request(body, "Connection: close\r\n");
+            let (head, _) = exchange(&socket, &mut reader, &request);
+            assert!(head.has_token("Connection", "close"));
+            let mut rest = Vec::new();
+            assert_eq!(reader.read_to_end(&mut rest).unwrap(), 0);
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_refused_requests() {
+        let mut server = HttpServer::bind("127.0.0.1:0").unwrap();
+        server.set_max_body_size(64);
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        let refuse = |request: &str| {
+            let socket = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(&socket);
+            let (head, _) = exchange(&socket, &mut reader, request);
+            assert!(head.has_token("Connection", "close"));
+            (
+                head.status().unwrap(),
+                head.header("Allow").map(String::from),
+            )
+        };
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            assert_eq!(
+                refuse("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+                (405, Some("POST".to_string()))
+            );
+            let (status, _) = refuse(&post_request(&"x".repeat(65), ""));
+            assert_eq!(status, 413);
+            let (status, _) = refuse("POST / HTTP/1.1\r\nContent-Type: application/json\r\n\r\n");
+            assert_eq!(status, 411);
+            let (status, _) = refuse("POST / HTTP/1.1\r\nbad header\r\n\r\n");
+            assert_eq!(status, 400);
+            let (status, _) = refuse("POST / HTTP/2.0\r\nContent-Length: 2\r\n\r\n{}");
+            assert_eq!(status, 505);
+
+            // Chunked bodies are refused, with or without a Content-Length
+            let chunked = "Transfer-Encoding: chunked\r\n";
+            let request = "POST / HTTP/1.1\r\nContent-Type: application/json\r\n";
+            let (status, _) = refuse(&format!("{}{}\r\n2\r\n{{}}\r\n0\r\n\r\n", request, chunked));
+            assert_eq!(status, 501);
+            let (status, _) = refuse(&post_request("{}", chunked));
+            assert_eq!(status, 501);
+
+            // A wrong content type is answered without closing the connection
+            let socket = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(&socket);
+            let request =
+                "POST / HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\n{}";
+            let (head, _) = exchange(&socket, &mut reader, request);
+            assert_eq!(head.status(), Some(415));
+            assert!(!head.has_token("Connection", "close"));
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+}
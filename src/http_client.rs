@@ -0,0 +1,961 @@
+use crate::codec::{self, is_token};
+use crate::http::MessageHead;
+use crate::{
+    IdGenerator, RpcBatchRequest, RpcBatchResponse, RpcError, RpcId, RpcMessage, RpcRequest,
+    RpcResponse, SequentialIdGenerator, TransportError, ValidationError, ValidationErrorKind,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The maximum length of a chunk-size line in a chunked body.
+const MAX_CHUNK_LINE: usize = 1024;
+
+/// The maximum number of idle connections kept open for reuse.
+const MAX_IDLE_CONNECTIONS: usize = 8;
+
+/// Headers that delimit messages on the connection, which the client manages.
+const FRAMING_HEADERS: [&str; 3] = ["Content-Length", "Transfer-Encoding", "Connection"];
+
+// ===========================================================================
+// ENUM: HttpClientError
+// ===
+
+/// Describes why a request made through an `HttpClient` did not produce a
+/// response.
+#[derive(Debug)]
+pub enum HttpClientError {
+    /// The endpoint URL is not a valid `http://` URL.
+    InvalidUrl(String),
+
+    /// A header passed to `HttpClient::set_header()` cannot be sent.
+    InvalidHeader(String),
+
+    /// Connecting to the server, or sending or receiving, failed.
+    Io(io::Error),
+
+    /// The server's answer is not valid HTTP.
+    MalformedResponse(String),
+
+    /// The response body exceeds the maximum body size.
+    ResponseTooLarge {
+        /// The maximum body size in bytes.
+        limit: usize,
+    },
+
+    /// The server answered with a status other than 2xx.
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The body of the response, which may explain the error.
+        body: Vec<u8>,
+    },
+
+    /// The response body is not JSON.
+    NotJson {
+        /// The value of the response's `Content-Type` header, if any.
+        content_type: Option<String>,
+        /// The body of the response.
+        body: Vec<u8>,
+    },
+
+    /// The response body is JSON but not the JSON-RPC response that was
+    /// expected.
+    Invalid(ValidationError),
+
+    /// The server answered with an error response.
+    Rpc(RpcError),
+
+    /// The params could not be serialized, or the result did not match the
+    /// expected type. The error names the offending field.
+    Conversion(RpcError),
+}
+
+impl HttpClientError {
+    /// Returns the HTTP status code, if the server answered with an error
+    /// status.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            HttpClientError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpClientError::InvalidUrl(message) => write!(f, "invalid URL: {}", message),
+            HttpClientError::InvalidHeader(message) => write!(f, "invalid header: {}", message),
+            HttpClientError::Io(error) => write!(f, "HTTP connection failed: {}", error),
+            HttpClientError::MalformedResponse(message) => {
+                write!(f, "malformed HTTP response: {}", message)
+            }
+            HttpClientError::ResponseTooLarge { limit } => {
+                write!(f, "response body exceeds {} bytes", limit)
+            }
+            HttpClientError::Status { status, .. } => write!(
+                f,
+                "server answered with HTTP status {} {}",
+                status,
+                crate::http::reason_phrase(*status)
+            ),
+            HttpClientError::NotJson { content_type, .. } => write!(
+                f,
+                "response body is not JSON (Content-Type: {})",
+                content_type.as_deref().unwrap_or("none")
+            ),
+            HttpClientError::Invalid(error) => write!(f, "invalid response: {}", error),
+            HttpClientError::Rpc(error) => write!(f, "server returned an error: {}", error),
+            HttpClientError::Conversion(error) => write!(f, "{}", error.message()),
+        }
+    }
+}
+
+impl Error for HttpClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpClientError::Io(error) => Some(error),
+            HttpClientError::Invalid(error) => Some(error),
+            HttpClientError::Rpc(error) | HttpClientError::Conversion(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HttpClientError {
+    fn from(error: io::Error) -> Self {
+        HttpClientError::Io(error)
+    }
+}
+
+impl From<TransportError> for HttpClientError {
+    fn from(error: TransportError) -> Self {
+        match error {
+            TransportError::Io(error) => HttpClientError::Io(error),
+            other => HttpClientError::MalformedResponse(other.to_string()),
+        }
+    }
+}
+
+// ===========================================================================
+// STRUCT: HttpClient
+// ===
+
+/// Calls a JSON-RPC endpoint over HTTP/1.1.
+///
+/// Every request or batch is POSTed to the endpoint URL and its responses are
+/// read from the body of the HTTP response. Connections are kept open and
+/// reused by later requests, from any thread; a kept-alive connection that
+/// the server has meanwhile closed is replaced transparently.
+///
+/// Failures are reported by kind: an HTTP error status is
+/// `HttpClientError::Status`, a body that is not JSON is
+/// `HttpClientError::NotJson`, and a JSON-RPC error response is
+/// `HttpClientError::Rpc`.
+///
+/// Only plain `http://` URLs are supported; put a TLS-terminating proxy in
+/// front of `https://` endpoints.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::HttpClient;
+/// use serde_json::json;
+///
+/// let mut client = HttpClient::new("http://localhost:8545/rpc")?;
+/// client.set_header("Authorization", "Bearer secret")?;
+///
+/// let sum = client.call("add", json!([2, 3]))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct HttpClient {
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    max_body_size: usize,
+    generator: Box<dyn IdGenerator>,
+    idle: Mutex<Vec<BufReader<TcpStream>>>,
+}
+
+impl HttpClient {
+    /// The default maximum response body size: 16 MiB.
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Creates a client for an endpoint.
+    ///
+    /// Request IDs are numbered 1, 2, 3, ... by a counter owned by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The endpoint, such as `http://localhost:8545/rpc`
+    ///
+    /// # Returns
+    ///
+    /// The client, or `HttpClientError::InvalidUrl`. No connection is made
+    /// until the first request.
+    pub fn new(url: &str) -> Result<Self, HttpClientError> {
+        Self::with_generator(url, SequentialIdGenerator::new())
+    }
+
+    /// Creates a client for an endpoint that draws request IDs from the given
+    /// generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The endpoint, such as `http://localhost:8545/rpc`
+    /// * `generator` - The generator providing request IDs
+    pub fn with_generator(
+        url: &str,
+        generator: impl IdGenerator + 'static,
+    ) -> Result<Self, HttpClientError> {
+        let (host, port, path) = parse_url(url, "http")?;
+        let authority = host_header(&host, port);
+
+        Ok(Self {
+            host,
+            port,
+            path,
+            headers: vec![
+                ("Host".to_string(), authority),
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Accept".to_string(), "application/json".to_string()),
+            ],
+            timeout: None,
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            generator: Box::new(generator),
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Sets a header sent with every request, replacing any header of the
+    /// same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header name, such as `Authorization`
+    /// * `value` - The header value
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut Self)` - A mutable reference to self, allowing for method chaining
+    /// * `Err(HttpClientError::InvalidHeader)` - The name is not a valid
+    ///   header name, the value contains a line break, or the header is
+    ///   `Content-Length`, `Transfer-Encoding` or `Connection`, which the
+    ///   client manages itself
+    pub fn set_header(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<&mut Self, HttpClientError> {
+        let (name, value) = (name.into(), value.into());
+        if !is_token(&name) {
+            return Err(HttpClientError::InvalidHeader(format!(
+                "`{}` is not a valid header name",
+                name
+            )));
+        }
+        if value.contains(['\r', '\n']) {
+            return Err(HttpClientError::InvalidHeader(format!(
+                "the value of {} contains a line break",
+                name
+            )));
+        }
+        if FRAMING_HEADERS
+            .iter()
+            .any(|framing| framing.eq_ignore_ascii_case(&name))
+        {
+            return Err(HttpClientError::InvalidHeader(format!(
+                "the {} header is managed by the client",
+                name
+            )));
+        }
+
+        match self
+            .headers
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+        {
+            Some(header) => header.1 = value,
+            None => self.headers.push((name, value)),
+        }
+        Ok(self)
+    }
+
+    /// Returns the headers sent with every request.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Sets how long connecting, sending and waiting for a response may each
+    /// take before the request fails with an `io::ErrorKind::TimedOut` or
+    /// `io::ErrorKind::WouldBlock` error.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout; `None` waits forever
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the maximum response body size in bytes.
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Sets the maximum size of a response body.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_body_size` - The maximum size in bytes
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_max_body_size(&mut self, max_body_size: usize) -> &mut Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Creates a request for a method, with an ID from this client's generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    pub fn new_request(&self, method: &str) -> RpcRequest {
+        let mut request = RpcRequest::with_generator(self.generator.as_ref());
+        request.set_method(method.to_string());
+        request
+    }
+
+    /// Calls a method and returns its result.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    /// * `params` - The params of the call
+    ///
+    /// # Returns
+    ///
+    /// The result of the call, or the `HttpClientError` describing why it
+    /// failed.
+    pub fn call(&self, method: &str, params: JsonValue) -> Result<JsonValue, HttpClientError> {
+        let mut request = self.new_request(method);
+        request.set_params(params);
+        self.call_request(request)
+    }
+
+    /// Calls a method with typed params and converts its result.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to call
+    /// * `params` - The params, serialized with serde
+    ///
+    /// # Returns
+    ///
+    /// The converted result, or `HttpClientError::Conversion` naming the field
+    /// that failed to convert.
+    pub fn call_typed<P, R>(&self, method: &str, params: &P) -> Result<R, HttpClientError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut request = self.new_request(method);
+        request
+            .set_params_typed(params)
+            .map_err(HttpClientError::Conversion)?;
+        let id = request.id().cloned().unwrap_or(RpcId::Null);
+        let result = self.call_request(request)?;
+
+        RpcResponse::success(id, result)
+            .result_as()
+            .map_err(HttpClientError::Conversion)
+    }
+
+    /// Sends a notification.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method to notify
+    /// * `params` - The params of the notification
+    pub fn notify(&self, method: &str, params: JsonValue) -> Result<(), HttpClientError> {
+        let mut notification = RpcRequest::notification();
+        notification
+            .set_method(method.to_string())
+            .set_params(params);
+        self.send_request(notification).map(|_| ())
+    }
+
+    /// Sends a request or notification.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send
+    ///
+    /// # Returns
+    ///
+    /// The response, or `None` for a notification.
+    pub fn send_request(
+        &self,
+        request: RpcRequest,
+    ) -> Result<Option<RpcResponse>, HttpClientError> {
+        match self.post(&RpcMessage::from(request))? {
+            None => Ok(None),
+            Some(RpcMessage::Response(response)) => Ok(Some(response)),
+            Some(_) => Err(unexpected("a batch")),
+        }
+    }
+
+    /// Sends a batch of requests and notifications.
+    ///
+    /// Pair the responses with their requests using
+    /// `RpcBatchRequest::pair_responses()`. If the server rejects the batch as
+    /// a whole, the result holds its single error response.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch to send
+    ///
+    /// # Returns
+    ///
+    /// The responses, which are empty if the batch held only notifications.
+    pub fn send_batch(&self, batch: RpcBatchRequest) -> Result<RpcBatchResponse, HttpClientError> {
+        match self.post(&RpcMessage::from(batch))? {
+            None => Ok(RpcBatchResponse::new()),
+            Some(RpcMessage::ResponseBatch(batch)) => Ok(batch),
+            Some(RpcMessage::Response(response)) => Ok(RpcBatchResponse::from(vec![response])),
+            Some(_) => Err(unexpected("a request")),
+        }
+    }
+
+    /// Sends a request and returns the result of its response.
+    fn call_request(&self, request: RpcRequest) -> Result<JsonValue, HttpClientError> {
+        self.send_request(request)?
+            .ok_or_else(|| unexpected("no response"))?
+            .into_result()
+            .map_err(HttpClientError::Rpc)
+    }
+
+    /// POSTs a message and parses the reply, if the response has a body.
+    fn post(&self, message: &RpcMessage) -> Result<Option<RpcMessage>, HttpClientError> {
+        let request = self.encode_request(&codec::encode_message(message));
+        let response = self.exchange(&request)?;
+
+        if !(200..300).contains(&response.status) {
+            return Err(HttpClientError::Status {
+                status: response.status,
+                body: response.body,
+            });
+        }
+        if response.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+
+        let json: JsonValue = match serde_json::from_slice(&response.body) {
+            Ok(json) => json,
+            Err(_) => {
+                return Err(HttpClientError::NotJson {
+                    content_type: response.content_type,
+                    body: response.body,
+                });
+            }
+        };
+        RpcMessage::from_json(json)
+            .map(Some)
+            .map_err(HttpClientError::Invalid)
+    }
+
+    /// Serializes the head and body of a POST request.
+    fn encode_request(&self, body: &[u8]) -> Vec<u8> {
+        let mut head = format!("POST {} HTTP/1.1\r\n", self.path);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// Sends a request on a kept-alive connection, or a new one, and reads
+    /// the response.
+    fn exchange(&self, request: &[u8]) -> Result<RawResponse, HttpClientError> {
+        loop {
+            let idle = self.idle.lock().unwrap().pop();
+            let reused = idle.is_some();
+            let mut connection = match idle {
+                Some(connection) => connection,
+                None => self.connect()?,
+            };
+
+            let Some(response) = self.try_exchange(&mut connection, request)? else {
+                // The server closed the idle connection before it was reused
+                if reused {
+                    continue;
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "server closed the connection without responding",
+                )
+                .into());
+            };
+
+            if response.keep_alive {
+                let mut idle = self.idle.lock().unwrap();
+                if idle.len() < MAX_IDLE_CONNECTIONS {
+                    idle.push(connection);
+                }
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Opens a new connection to the server.
+    fn connect(&self) -> Result<BufReader<TcpStream>, HttpClientError> {
+        let socket = match self.timeout {
+            None => TcpStream::connect((self.host.as_str(), self.port))?,
+            Some(timeout) => {
+                let mut last_error = None;
+                let mut connected = None;
+                for addr in (self.host.as_str(), self.port).to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&addr, timeout) {
+                        Ok(socket) => {
+                            connected = Some(socket);
+                            break;
+                        }
+                        Err(error) => last_error = Some(error),
+                    }
+                }
+                connected.ok_or_else(|| {
+                    last_error.unwrap_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, "host has no addresses")
+                    })
+                })?
+            }
+        };
+
+        socket.set_nodelay(true)?;
+        socket.set_read_timeout(self.timeout)?;
+        socket.set_write_timeout(self.timeout)?;
+        Ok(BufReader::new(socket))
+    }
+
+    /// Sends a request and reads the response.
+    ///
+    /// # Returns
+    ///
+    /// The response, or `None` if the connection was closed before the
+    /// response started.
+    fn try_exchange(
+        &self,
+        connection: &mut BufReader<TcpStream>,
+        request: &[u8],
+    ) -> Result<Option<RawResponse>, HttpClientError> {
+        if let Err(error) = connection.get_mut().write_all(request) {
+            return match error.kind() {
+                io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset => Ok(None),
+                _ => Err(error.into()),
+            };
+        }
+
+        // Interim responses such as 100 Continue precede the real one
+        let (status, head) = loop {
+            let head = match MessageHead::read_response(connection) {
+                Ok(Some(head)) => head,
+                Ok(None) => return Ok(None),
+                Err(TransportError::Io(error))
+                    if error.kind() == io::ErrorKind::ConnectionReset =>
+                {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error.into()),
+            };
+            let status = head.status().unwrap_or_default();
+            if !(100..200).contains(&status) {
+                break (status, head);
+            }
+        };
+
+        let mut keep_alive = if head.is_http_11() {
+            !head.has_token("Connection", "close")
+        } else {
+            head.has_token("Connection", "keep-alive")
+        };
+
+        let body = if status == 204 || status == 304 {
+            Vec::new()
+        } else if head.has_token("Transfer-Encoding", "chunked") {
+            read_chunked(connection, self.max_body_size)?
+        } else if let Some(length) = head.content_length()? {
+            if length > self.max_body_size {
+                return Err(HttpClientError::ResponseTooLarge {
+                    limit: self.max_body_size,
+                });
+            }
+            let mut body = vec![0; length];
+            connection.read_exact(&mut body)?;
+            body
+        } else {
+            // The body runs until the server closes the connection
+            keep_alive = false;
+            let mut body = Vec::new();
+            connection
+                .take(self.max_body_size as u64 + 1)
+                .read_to_end(&mut body)?;
+            if body.len() > self.max_body_size {
+                return Err(HttpClientError::ResponseTooLarge {
+                    limit: self.max_body_size,
+                });
+            }
+            body
+        };
+
+        Ok(Some(RawResponse {
+            status,
+            content_type: head.header("Content-Type").map(String::from),
+            body,
+            keep_alive,
+        }))
+    }
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("path", &self.path)
+            .field("idle", &self.idle.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// The parts of an HTTP response the client needs.
+struct RawResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+    keep_alive: bool,
+}
+
+/// Reports a reply that does not match what was sent.
+fn unexpected(reply: &str) -> HttpClientError {
+    HttpClientError::Invalid(ValidationError::new(
+        ValidationErrorKind::InvalidStructure,
+        RpcId::Null,
+        format!("server replied with {}", reply),
+    ))
+}
+
+/// Splits a `scheme://host[:port][/path]` URL into its host, port and path.
+///
+/// # Arguments
+///
+/// * `url` - The URL to split
+/// * `scheme` - The only scheme accepted, such as `http`; the default port is 80
+pub(crate) fn parse_url(url: &str, scheme: &str) -> Result<(String, u16, String), HttpClientError> {
+    let invalid = |message: &str| HttpClientError::InvalidUrl(format!("{} in `{}`", message, url));
+
+    let Some(rest) = url
+        .split_once("://")
+        .filter(|(prefix, _)| prefix.eq_ignore_ascii_case(scheme))
+        .map(|(_, rest)| rest)
+    else {
+        return Err(invalid(&format!("expected a {}:// scheme", scheme)));
+    };
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(index) if rest[index..].starts_with('/') => rest.split_at(index),
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, ""),
+    };
+    let path = match path {
+        "" => "/".to_string(),
+        path if path.starts_with('?') => format!("/{}", path),
+        path => path.to_string(),
+    };
+    if path.contains(|c: char| c.is_whitespace() || c == '#') || authority.contains('@') {
+        return Err(invalid("unsupported characters"));
+    }
+
+    // IPv6 hosts are written in brackets, which are not part of the address
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed
+                .split_once(']')
+                .ok_or_else(|| invalid("unclosed bracket"))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(invalid("missing host"));
+    }
+
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid("invalid port"))?,
+        None => 80,
+    };
+    Ok((host.to_string(), port, path))
+}
+
+/// Formats the value of the `Host` header for a server.
+pub(crate) fn host_header(host: &str, port: u16) -> String {
+    match (host.contains(':'), port) {
+        (false, 80) => host.to_string(),
+        (false, port) => format!("{}:{}", host, port),
+        (true, port) => format!("[{}]:{}", host, port),
+    }
+}
+
+/// Reads a body sent with `Transfer-Encoding: chunked`.
+fn read_chunked(reader: &mut dyn BufRead, limit: usize) -> Result<Vec<u8>, HttpClientError> {
+    let malformed = || HttpClientError::MalformedResponse("invalid chunked body".to_string());
+    let mut body = Vec::new();
+
+    loop {
+        let line = codec::read_line(reader, MAX_CHUNK_LINE)?.ok_or_else(malformed)?;
+        let size = std::str::from_utf8(&line)
+            .ok()
+            .map(|line| line.split(';').next().unwrap_or_default().trim())
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or_else(malformed)?;
+
+        if size == 0 {
+            // Trailer fields end with an empty line
+            while !codec::read_line(reader, MAX_CHUNK_LINE)?
+                .ok_or_else(malformed)?
+                .is_empty()
+            {}
+            return Ok(body);
+        }
+        if body.len().saturating_add(size) > limit {
+            return Err(HttpClientError::ResponseTooLarge { limit });
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !codec::read_line(reader, MAX_CHUNK_LINE)?
+            .ok_or_else(malformed)?
+            .is_empty()
+        {
+            return Err(malformed());
+        }
+    }
+}
+
+// ===========================================================================
+// TESTS: HttpClient
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HttpServer, RpcRouter};
+    use serde_json::json;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        router.register("fail", |_: ()| {
+            Err::<(), _>(RpcError::new(-32000, "failed"))
+        });
+        router
+    }
+
+    /// A stand-in server answering each request on one connection with the
+    /// next canned response, and reporting the requests it received.
+    fn stand_in(responses: Vec<&'static str>) -> (String, mpsc::Receiver<MessageHead>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/rpc", listener.local_addr().unwrap());
+        let (heads, received) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&socket);
+            for response in responses {
+                let head = MessageHead::read_request(&mut reader).unwrap().unwrap();
+                let mut body = vec![0; head.content_length().unwrap().unwrap()];
+                reader.read_exact(&mut body).unwrap();
+                let _ = heads.send(head);
+                (&socket).write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (url, received)
+    }
+
+    #[test]
+    fn test_calls_and_batches_over_keep_alive() {
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/rpc", server.local_addr().unwrap());
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        let client = HttpClient::new(&url).unwrap();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            assert_eq!(client.call("add", json!([2, 3])).unwrap(), json!(5));
+            let sum: i64 = client.call_typed("add", &(4, 5)).unwrap();
+            assert_eq!(sum, 9);
+            assert!(matches!(
+                client.call("fail", json!(null)),
+                Err(HttpClientError::Rpc(error)) if error.code() == -32000
+            ));
+            client.notify("add", json!([1, 1])).unwrap();
+
+            let mut batch = RpcBatchRequest::new();
+            let mut first = client.new_request("add");
+            first.set_params(json!([1, 2]));
+            let mut notification = RpcRequest::notification();
+            notification
+                .set_method("add".to_string())
+                .set_params(json!([0, 0]));
+            let mut second = client.new_request("add");
+            second.set_params(json!([3, 4]));
+            batch.push(first).push(notification).push(second);
+
+            let responses = client.send_batch(batch.clone()).unwrap();
+            let results: Vec<_> = batch
+                .pair_responses(&responses)
+                .into_iter()
+                .filter_map(|(_, response)| response.and_then(|r| r.result().cloned()))
+                .collect();
+            assert_eq!(results, vec![json!(3), json!(7)]);
+
+            // Every request went over the same connection
+            assert_eq!(handle.connection_count(), 1);
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+
+        // The closed idle connection is replaced by a failed reconnect
+        drop(server);
+        assert!(matches!(
+            client.call("add", json!([1, 2])),
+            Err(HttpClientError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let (url, _) = stand_in(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\n\r\nbusy",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 6\r\n\r\n<html>",
+            "HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\n{\"a\": 1}\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let client = HttpClient::new(&url).unwrap();
+
+        match client.call("a", json!(null)) {
+            Err(HttpClientError::Status { status: 503, body }) => assert_eq!(body, b"busy"),
+            other => panic!("expected a status error, got {:?}", other),
+        }
+        match client.call("a", json!(null)) {
+            Err(HttpClientError::NotJson { content_type, body }) => {
+                assert_eq!(content_type.as_deref(), Some("text/html"));
+                assert_eq!(body, b"<html>");
+            }
+            other => panic!("expected a body error, got {:?}", other),
+        }
+        assert!(matches!(
+            client.call("a", json!(null)),
+            Err(HttpClientError::Invalid(_))
+        ));
+        assert!(matches!(
+            client.call("a", json!(null)),
+            Err(HttpClientError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_headers_and_chunked_bodies() {
+        let (url, received) = stand_in(vec![
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+             a\r\n{\"jsonrpc\"\r\n1c;ext=1\r\n:\"2.0\",\"result\":\"ok\",\"id\":1}\r\n0\r\n\r\n",
+        ]);
+        let mut client = HttpClient::new(&url).unwrap();
+        client
+            .set_header("Authorization", "Bearer secret")
+            .unwrap()
+            .set_header("accept", "application/json-rpc")
+            .unwrap();
+
+        assert_eq!(client.call("a", json!(null)).unwrap(), json!("ok"));
+
+        let head = received.recv().unwrap();
+        assert_eq!(head.method(), Some("POST"));
+        assert_eq!(head.header("Authorization"), Some("Bearer secret"));
+        assert_eq!(head.header("Accept"), Some("application/json-rpc"));
+        assert_eq!(head.header("Content-Type"), Some("application/json"));
+        assert!(head.header("Host").unwrap().starts_with("127.0.0.1:"));
+    }
+
+    #[test]
+    fn test_invalid_headers_are_refused() {
+        let mut client = HttpClient::new("http://localhost").unwrap();
+        let defaults = client.headers().to_vec();
+        let mut refuse = |name: &str, value: &str| {
+            let error = client.set_header(name, value).unwrap_err();
+            assert!(matches!(error, HttpClientError::InvalidHeader(_)));
+            error.to_string()
+        };
+
+        assert!(refuse("content-length", "0").contains("managed by the client"));
+        assert!(refuse("Transfer-Encoding", "chunked").contains("managed by the client"));
+        assert!(refuse("X-Bad Name", "1").contains("not a valid header name"));
+        assert!(refuse("X-Injected", "1\r\nConnection: close").contains("line break"));
+
+        // Nothing was added by the refused calls
+        assert_eq!(client.headers(), defaults);
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let parse = |url| parse_url(url, "http").ok();
+
+        assert_eq!(
+            parse("http://localhost"),
+            Some(("localhost".to_string(), 80, "/".to_string()))
+        );
+        assert_eq!(
+            parse("HTTP://example.com:8545/v1/rpc?key=abc"),
+            Some((
+                "example.com".to_string(),
+                8545,
+                "/v1/rpc?key=abc".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("http://[::1]:9000"),
+            Some(("::1".to_string(), 9000, "/".to_string()))
+        );
+        assert_eq!(parse("https://example.com"), None);
+        assert_eq!(parse("http://:80/"), None);
+        assert_eq!(parse("http://example.com:http/"), None);
+    }
+}
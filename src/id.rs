@@ -0,0 +1,303 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// ===========================================================================
+// ENUM: RpcId
+// ===
+
+/// Represents the identifier of a JSON-RPC 2.0 request.
+///
+/// The JSON-RPC 2.0 specification allows a request ID to be a number, a string,
+/// or null. Responses must echo back the exact ID that was sent with the request,
+/// so the ID is kept in its original form rather than being coerced to a number.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::RpcId;
+///
+/// let numeric = RpcId::from(42);
+/// let string = RpcId::from("a1b2-c3d4");
+///
+/// assert_eq!(numeric.as_i64(), Some(42));
+/// assert_eq!(string.as_str(), Some("a1b2-c3d4"));
+/// assert!(RpcId::Null.is_null());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RpcId {
+    /// A numeric identifier, such as the auto-incremented IDs used by `RpcRequest::new()`.
+    ///
+    /// Any integer a peer may send is kept exactly, from `i64::MIN` up to
+    /// `u64::MAX`, which is why the value is stored as an `i128`.
+    Number(i128),
+
+    /// A string identifier, such as a UUID.
+    String(String),
+
+    /// A null identifier, used when the ID of a request could not be determined.
+    Null,
+}
+
+impl RpcId {
+    /// Returns the numeric value of this ID.
+    ///
+    /// # Returns
+    ///
+    /// `Some(i64)` if the ID is a number that fits an `i64`, `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcId;
+    /// assert_eq!(RpcId::from(-7).as_i64(), Some(-7));
+    /// assert_eq!(RpcId::from("7").as_i64(), None);
+    /// ```
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            RpcId::Number(n) => i64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the numeric value of this ID if it is not negative.
+    ///
+    /// # Returns
+    ///
+    /// `Some(u64)` if the ID is a non-negative number, `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcId;
+    /// assert_eq!(RpcId::from(u64::MAX).as_u64(), Some(u64::MAX));
+    /// assert_eq!(RpcId::from(-7).as_u64(), None);
+    /// assert_eq!(RpcId::from("7").as_u64(), None);
+    /// ```
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            RpcId::Number(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the string value of this ID.
+    ///
+    /// # Returns
+    ///
+    /// `Some(&str)` if the ID is a string, `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcId;
+    /// assert_eq!(RpcId::from("abc").as_str(), Some("abc"));
+    /// assert_eq!(RpcId::from(1).as_str(), None);
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RpcId::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Checks if this ID is null.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the ID is `RpcId::Null`, `false` otherwise.
+    pub fn is_null(&self) -> bool {
+        matches!(self, RpcId::Null)
+    }
+}
+
+impl From<i32> for RpcId {
+    fn from(id: i32) -> Self {
+        RpcId::Number(id.into())
+    }
+}
+
+impl From<i64> for RpcId {
+    fn from(id: i64) -> Self {
+        RpcId::Number(id.into())
+    }
+}
+
+impl From<u32> for RpcId {
+    fn from(id: u32) -> Self {
+        RpcId::Number(id.into())
+    }
+}
+
+impl From<u64> for RpcId {
+    fn from(id: u64) -> Self {
+        RpcId::Number(id.into())
+    }
+}
+
+impl From<String> for RpcId {
+    fn from(id: String) -> Self {
+        RpcId::String(id)
+    }
+}
+
+impl From<&str> for RpcId {
+    fn from(id: &str) -> Self {
+        RpcId::String(id.to_string())
+    }
+}
+
+impl From<&RpcId> for RpcId {
+    fn from(id: &RpcId) -> Self {
+        id.clone()
+    }
+}
+
+impl Serialize for RpcId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RpcId::Number(n) => match (u64::try_from(*n), i64::try_from(*n)) {
+                (Ok(n), _) => serializer.serialize_u64(n),
+                (_, Ok(n)) => serializer.serialize_i64(n),
+                _ => serializer.serialize_i128(*n),
+            },
+            RpcId::String(s) => serializer.serialize_str(s),
+            RpcId::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RpcId {
+    /// Accepts an integer, a string or null.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RpcIdVisitor)
+    }
+}
+
+/// Builds an `RpcId` from whichever JSON type the ID was sent as.
+struct RpcIdVisitor;
+
+impl Visitor<'_> for RpcIdVisitor {
+    type Value = RpcId;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an integer, a string or null")
+    }
+
+    fn visit_i64<E: de::Error>(self, id: i64) -> Result<RpcId, E> {
+        Ok(RpcId::from(id))
+    }
+
+    fn visit_u64<E: de::Error>(self, id: u64) -> Result<RpcId, E> {
+        Ok(RpcId::from(id))
+    }
+
+    fn visit_str<E: de::Error>(self, id: &str) -> Result<RpcId, E> {
+        Ok(RpcId::from(id))
+    }
+
+    fn visit_string<E: de::Error>(self, id: String) -> Result<RpcId, E> {
+        Ok(RpcId::from(id))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<RpcId, E> {
+        Ok(RpcId::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<RpcId, E> {
+        Ok(RpcId::Null)
+    }
+}
+
+impl fmt::Display for RpcId {
+    /// Formats the ID the way it appears in JSON.
+    ///
+    /// Numbers are written as-is, strings are quoted and null is written as `null`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcId::Number(n) => write!(f, "{}", n),
+            RpcId::String(s) => write!(f, "{:?}", s),
+            RpcId::Null => write!(f, "null"),
+        }
+    }
+}
+
+// ===========================================================================
+// TESTS: RpcId
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(RpcId::from(5), RpcId::Number(5));
+        assert_eq!(RpcId::from(5u64), RpcId::Number(5));
+        assert_eq!(RpcId::from(-5i64), RpcId::Number(-5));
+        assert_eq!(RpcId::from("abc"), RpcId::String("abc".to_string()));
+        assert_eq!(
+            RpcId::from("abc".to_string()),
+            RpcId::String("abc".to_string())
+        );
+        assert_eq!(RpcId::from(&RpcId::Null), RpcId::Null);
+    }
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(RpcId::Number(9).as_u64(), Some(9));
+        assert_eq!(RpcId::Number(-9).as_i64(), Some(-9));
+        assert_eq!(RpcId::Number(-9).as_u64(), None);
+        assert_eq!(RpcId::Number(9).as_str(), None);
+        assert_eq!(RpcId::from("x").as_str(), Some("x"));
+        assert_eq!(RpcId::from("x").as_u64(), None);
+        assert!(RpcId::Null.is_null());
+        assert!(!RpcId::Number(0).is_null());
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(serde_json::to_value(RpcId::Number(3)).unwrap(), json!(3));
+        assert_eq!(
+            serde_json::to_value(RpcId::from("id-3")).unwrap(),
+            json!("id-3")
+        );
+        assert_eq!(serde_json::to_value(RpcId::Null).unwrap(), json!(null));
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let number: RpcId = serde_json::from_value(json!(u64::MAX)).unwrap();
+        assert_eq!(number, RpcId::from(u64::MAX));
+        assert_eq!(serde_json::to_value(&number).unwrap(), json!(u64::MAX));
+
+        let number: RpcId = serde_json::from_str(&i64::MIN.to_string()).unwrap();
+        assert_eq!(number.as_i64(), Some(i64::MIN));
+
+        // Negative IDs round-trip unchanged
+        let negative: RpcId = serde_json::from_value(json!(-5)).unwrap();
+        assert_eq!(negative, RpcId::Number(-5));
+        assert_eq!(serde_json::to_value(&negative).unwrap(), json!(-5));
+
+        let uuid: RpcId =
+            serde_json::from_value(json!("0b7e2c1a-5d4f-4b8e-9a3c-2f1e6d7c8b9a")).unwrap();
+        assert_eq!(uuid.as_str(), Some("0b7e2c1a-5d4f-4b8e-9a3c-2f1e6d7c8b9a"));
+
+        let null: RpcId = serde_json::from_value(json!(null)).unwrap();
+        assert_eq!(null, RpcId::Null);
+
+        // Objects, arrays and booleans are not valid IDs
+        assert!(serde_json::from_value::<RpcId>(json!({"id": 1})).is_err());
+        assert!(serde_json::from_value::<RpcId>(json!([1])).is_err());
+        assert!(serde_json::from_value::<RpcId>(json!(true)).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(RpcId::Number(12).to_string(), "12");
+        assert_eq!(RpcId::Number(-12).to_string(), "-12");
+        assert_eq!(RpcId::from("abc").to_string(), "\"abc\"");
+        assert_eq!(RpcId::Null.to_string(), "null");
+    }
+}
@@ -0,0 +1,314 @@
+use crate::codec::{self, FrameCodec};
+use crate::{RpcMessage, TransportError};
+use futures::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::{self, BufRead, Read, Write};
+
+/// The size of the length prefix, in bytes.
+const PREFIX_SIZE: usize = 4;
+
+// ===========================================================================
+// STRUCT: LengthPrefixedCodec
+// ===
+
+/// Frames messages with a 4-byte big-endian length prefix.
+///
+/// Each message is the length of its body as an unsigned 32-bit integer in
+/// network byte order, followed by the body. Unlike newline-delimited JSON,
+/// the reader never scans the body, which makes this the cheapest framing for
+/// large messages between programs that both speak it.
+///
+/// Frames larger than the maximum frame size are skipped with a
+/// `TransportError::FrameTooLarge` error, without ever being buffered, and
+/// reading resumes at the next frame.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{FrameCodec, LengthPrefixedCodec, RpcMessage, RpcRequest};
+/// use std::io::Cursor;
+///
+/// let codec = LengthPrefixedCodec::new();
+/// let mut notification = RpcRequest::notification();
+/// notification.set_method("initialized".to_string());
+///
+/// let mut buffer = Vec::new();
+/// codec.write_message(&mut buffer, &RpcMessage::from(notification)).unwrap();
+/// assert_eq!(buffer[..4], 40u32.to_be_bytes());
+///
+/// let message = codec.read_message(&mut Cursor::new(buffer)).unwrap();
+/// assert!(matches!(message, Some(RpcMessage::Notification(_))));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LengthPrefixedCodec {
+    max_frame_size: usize,
+}
+
+impl LengthPrefixedCodec {
+    /// The default maximum frame size: 16 MiB.
+    pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Creates a codec with the default maximum frame size.
+    pub fn new() -> Self {
+        Self::with_max_frame_size(Self::DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    /// Creates a codec that rejects frames larger than `max_frame_size` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_frame_size` - The maximum size of a frame body in bytes
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self { max_frame_size }
+    }
+
+    /// Returns the maximum frame size in bytes.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Reads and validates the next message from an async stream.
+    ///
+    /// This is the async equivalent of `FrameCodec::read_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// The next message, `None` at the end of the stream, or the error that
+    /// prevented reading it.
+    pub async fn read_message_async<R>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Option<RpcMessage>, TransportError>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        // The stream may only end before the first byte of a prefix
+        if reader.fill_buf().await?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut prefix = [0; PREFIX_SIZE];
+        reader.read_exact(&mut prefix).await?;
+        let length = u32::from_be_bytes(prefix) as usize;
+
+        if length > self.max_frame_size {
+            let skipped =
+                futures::io::copy((&mut *reader).take(length as u64), &mut futures::io::sink())
+                    .await?;
+            codec::check_skipped(skipped, length)?;
+            return Err(TransportError::FrameTooLarge {
+                limit: self.max_frame_size,
+            });
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await?;
+        Ok(Some(codec::parse_frame(&body)?))
+    }
+
+    /// Writes a message to an async stream and flushes it.
+    ///
+    /// This is the async equivalent of `FrameCodec::write_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    /// * `message` - The message to write
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message has been written and flushed.
+    pub async fn write_message_async<W>(
+        &self,
+        writer: &mut W,
+        message: &RpcMessage,
+    ) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        writer
+            .write_all(&encode_frame(&codec::encode_message(message))?)
+            .await?;
+        writer.flush().await
+    }
+}
+
+impl Default for LengthPrefixedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameCodec for LengthPrefixedCodec {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError> {
+        // The stream may only end before the first byte of a prefix
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut prefix = [0; PREFIX_SIZE];
+        reader.read_exact(&mut prefix)?;
+        let length = u32::from_be_bytes(prefix) as usize;
+
+        if length > self.max_frame_size {
+            let skipped = io::copy(&mut Read::take(reader, length as u64), &mut io::sink())?;
+            codec::check_skipped(skipped, length)?;
+            return Err(TransportError::FrameTooLarge {
+                limit: self.max_frame_size,
+            });
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        Ok(Some(body))
+    }
+
+    /// Writes the length prefix followed by the frame.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if the frame is too large for
+    /// a 32-bit length.
+    fn write_frame(&self, writer: &mut dyn Write, frame: &[u8]) -> io::Result<()> {
+        writer.write_all(&encode_frame(frame)?)
+    }
+}
+
+/// Prepends the length prefix to the body of a frame.
+fn encode_frame(frame: &[u8]) -> io::Result<Vec<u8>> {
+    let length = u32::try_from(frame.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a length-prefixed frame must be smaller than 4 GiB",
+        )
+    })?;
+
+    let mut bytes = Vec::with_capacity(PREFIX_SIZE + frame.len());
+    bytes.extend_from_slice(&length.to_be_bytes());
+    bytes.extend_from_slice(frame);
+    Ok(bytes)
+}
+
+// ===========================================================================
+// TESTS: LengthPrefixedCodec
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RpcRequest, RpcResponse};
+    use futures::executor;
+    use serde_json::json;
+    use std::io::{BufReader, Cursor};
+
+    /// Reads through a tiny buffer so every frame arrives in partial reads.
+    fn chunked(input: &[u8]) -> BufReader<Cursor<Vec<u8>>> {
+        BufReader::with_capacity(3, Cursor::new(input.to_vec()))
+    }
+
+    fn frame(body: &str) -> Vec<u8> {
+        encode_frame(body.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read_frames_across_partial_reads() {
+        let input = [
+            frame(r#"{"jsonrpc":"2.0","method":"a","id":1}"#),
+            frame(r#"{"jsonrpc":"2.0","result":"line\nbreak","id":1}"#),
+        ]
+        .concat();
+        let codec = LengthPrefixedCodec::new();
+        let mut reader = chunked(&input);
+
+        assert!(matches!(
+            codec.read_message(&mut reader).unwrap(),
+            Some(RpcMessage::Request(_))
+        ));
+        match codec.read_message(&mut reader).unwrap() {
+            Some(RpcMessage::Response(response)) => {
+                assert_eq!(response.result(), Some(&json!("line\nbreak")))
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_max_frame_size() {
+        let codec = LengthPrefixedCodec::with_max_frame_size(40);
+        let long = format!(r#"{{"jsonrpc":"2.0","method":"{}"}}"#, "x".repeat(100));
+        let input = [frame(&long), frame(r#"{"jsonrpc":"2.0","method":"a"}"#)].concat();
+        let mut reader = chunked(&input);
+
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::FrameTooLarge { limit: 40 })
+        ));
+        assert!(codec.read_message(&mut reader).unwrap().is_some());
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let codec = LengthPrefixedCodec::new();
+
+        // The stream ends inside the prefix
+        let mut reader = chunked(&[0, 0]);
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::Io(_))
+        ));
+
+        // The stream ends inside the body
+        let mut input = frame(r#"{"jsonrpc":"2.0","method":"a"}"#);
+        input.truncate(10);
+        let mut reader = chunked(&input);
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_async_roundtrip() {
+        let codec = LengthPrefixedCodec::with_max_frame_size(64);
+
+        executor::block_on(async {
+            let mut buffer = futures::io::Cursor::new(Vec::new());
+            let mut request = RpcRequest::new();
+            request
+                .set_method("a".to_string())
+                .set_params(json!(["z".repeat(100)]));
+            codec
+                .write_message_async(&mut buffer, &RpcMessage::from(request))
+                .await
+                .unwrap();
+            codec
+                .write_message_async(
+                    &mut buffer,
+                    &RpcMessage::from(RpcResponse::success(1, json!("ok"))),
+                )
+                .await
+                .unwrap();
+
+            let bytes = buffer.into_inner();
+            let mut reader =
+                futures::io::BufReader::with_capacity(5, futures::io::Cursor::new(bytes));
+
+            assert!(matches!(
+                codec.read_message_async(&mut reader).await,
+                Err(TransportError::FrameTooLarge { limit: 64 })
+            ));
+            let second = codec.read_message_async(&mut reader).await.unwrap();
+            assert!(matches!(second, Some(RpcMessage::Response(_))));
+            assert!(
+                codec
+                    .read_message_async(&mut reader)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        });
+    }
+}
@@ -1,5 +1,91 @@
+mod connections;
+mod typed;
+
+pub mod id;
+pub use id::*;
+
+pub mod generator;
+pub use generator::*;
+
 pub mod request;
 pub use request::*;
 
 pub mod response;
 pub use response::*;
+
+pub mod batch;
+pub use batch::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod validation;
+pub use validation::*;
+
+pub mod message;
+pub use message::*;
+
+pub mod context;
+pub use context::*;
+
+pub mod cancellation;
+pub use cancellation::*;
+
+pub mod router;
+pub use router::*;
+
+pub mod client;
+pub use client::*;
+
+pub mod transport;
+pub use transport::*;
+
+pub mod memory;
+pub use memory::*;
+
+pub mod server;
+pub use server::*;
+
+pub mod peer;
+pub use peer::*;
+
+pub mod subscription;
+pub use subscription::*;
+
+pub mod codec;
+pub use codec::*;
+
+pub mod content_length;
+pub use content_length::*;
+
+pub mod ndjson;
+pub use ndjson::*;
+
+pub mod length_prefixed;
+pub use length_prefixed::*;
+
+pub mod stream;
+pub use stream::*;
+
+pub mod stdio;
+pub use stdio::*;
+
+pub mod process;
+pub use process::*;
+
+pub mod tcp;
+pub use tcp::*;
+
+#[cfg(unix)]
+pub mod unix;
+#[cfg(unix)]
+pub use unix::*;
+
+pub mod http;
+pub use http::*;
+
+pub mod http_client;
+pub use http_client::*;
+
+pub mod websocket;
+pub use websocket::*;
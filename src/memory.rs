@@ -0,0 +1,141 @@
+use crate::{RpcMessage, Transport, TransportError};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+// ===========================================================================
+// STRUCT: MemoryTransport
+// ===
+
+/// One end of an in-memory connection, for tests and in-process peers.
+///
+/// `MemoryTransport::pair()` returns two connected ends: whatever one end
+/// sends, the other receives. Messages are passed as values, without being
+/// serialized. Dropping or closing one end makes the other end's `receive()`
+/// return `Ok(None)` once it has drained the messages already sent.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{MemoryTransport, RpcMessage, RpcResponse, Transport};
+/// use serde_json::json;
+///
+/// let (left, right) = MemoryTransport::pair();
+/// right.send(RpcMessage::from(RpcResponse::success(1, json!("pong")))).unwrap();
+/// right.close().unwrap();
+///
+/// assert!(matches!(left.receive().unwrap(), Some(RpcMessage::Response(_))));
+/// assert!(left.receive().unwrap().is_none());
+/// ```
+pub struct MemoryTransport {
+    sender: Mutex<Option<Sender<RpcMessage>>>,
+    receiver: Mutex<Receiver<RpcMessage>>,
+}
+
+impl MemoryTransport {
+    /// Creates two connected transports.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of two ends; each receives what the other sends.
+    pub fn pair() -> (Self, Self) {
+        let (left_sender, right_receiver) = mpsc::channel();
+        let (right_sender, left_receiver) = mpsc::channel();
+
+        (
+            Self::new(left_sender, left_receiver),
+            Self::new(right_sender, right_receiver),
+        )
+    }
+
+    /// Creates one end from its channel halves.
+    fn new(sender: Sender<RpcMessage>, receiver: Receiver<RpcMessage>) -> Self {
+        Self {
+            sender: Mutex::new(Some(sender)),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        match self.sender.lock().unwrap().as_ref() {
+            Some(sender) => sender.send(message).map_err(|_| TransportError::Closed),
+            None => Err(TransportError::Closed),
+        }
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        Ok(self.receiver.lock().unwrap().recv().ok())
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        self.sender.lock().unwrap().take();
+        Ok(())
+    }
+}
+
+impl fmt::Debug for MemoryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryTransport")
+            .field("closed", &self.sender.lock().unwrap().is_none())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// TESTS: MemoryTransport
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RpcRequest, RpcResponse};
+    use serde_json::json;
+
+    #[test]
+    fn test_pair_delivers_in_both_directions() {
+        let (left, right) = MemoryTransport::pair();
+
+        let mut request = RpcRequest::new();
+        request.set_method("ping".to_string());
+        left.send(RpcMessage::from(request)).unwrap();
+        right
+            .send(RpcMessage::from(RpcResponse::success(1, json!("pong"))))
+            .unwrap();
+
+        match right.receive().unwrap() {
+            Some(RpcMessage::Request(request)) => assert_eq!(request.method(), "ping"),
+            other => panic!("expected a request, got {:?}", other),
+        }
+        assert!(matches!(
+            left.receive().unwrap(),
+            Some(RpcMessage::Response(_))
+        ));
+    }
+
+    #[test]
+    fn test_close_and_drop() {
+        let (left, right) = MemoryTransport::pair();
+
+        // Messages sent before closing are still delivered
+        left.send(RpcMessage::from(RpcRequest::notification()))
+            .unwrap();
+        left.close().unwrap();
+        assert!(right.receive().unwrap().is_some());
+        assert!(right.receive().unwrap().is_none());
+
+        // A closed end cannot send any more
+        assert!(matches!(
+            left.send(RpcMessage::from(RpcRequest::new())),
+            Err(TransportError::Closed)
+        ));
+
+        // Dropping an end closes the connection as well
+        drop(left);
+        assert!(matches!(
+            right.send(RpcMessage::from(RpcRequest::new())),
+            Err(TransportError::Closed)
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::validation::{self, ValidationError, ValidationErrorKind};
+use crate::{RpcBatchRequest, RpcBatchResponse, RpcId, RpcRequest, RpcResponse};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::str::FromStr;
+
+// ===========================================================================
+// ENUM: RpcMessage
+// ===
+
+/// Any JSON-RPC 2.0 message that can arrive on a connection.
+///
+/// Raw payloads read from a socket may be a request, a notification, a
+/// response, or a batch of either. `RpcMessage::parse()` classifies a payload
+/// and validates it into the matching message type.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::RpcMessage;
+///
+/// let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "ping", "id": 1}"#).unwrap();
+/// assert!(matches!(message, RpcMessage::Request(_)));
+///
+/// let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "result": "pong", "id": 1}"#).unwrap();
+/// assert!(matches!(message, RpcMessage::Response(_)));
+/// ```
+#[derive(Clone, Debug)]
+pub enum RpcMessage {
+    /// A request that expects a response.
+    Request(RpcRequest),
+
+    /// A request without an `id`, which must not be answered.
+    Notification(RpcRequest),
+
+    /// A response to a previously sent request.
+    Response(RpcResponse),
+
+    /// A batch of requests and notifications.
+    RequestBatch(RpcBatchRequest),
+
+    /// A batch of responses.
+    ResponseBatch(RpcBatchResponse),
+}
+
+impl RpcMessage {
+    /// Parses and classifies a message from JSON text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The raw JSON payload
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcMessage)` - The classified and validated message
+    /// * `Err(ValidationError)` - The payload is not valid JSON or not a valid
+    ///   JSON-RPC 2.0 message; the error carries the code to reply with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcMessage;
+    ///
+    /// let error = RpcMessage::parse("{\"jsonrpc\"").unwrap_err();
+    /// assert_eq!(error.code(), -32700);
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ValidationError> {
+        Self::from_json(validation::parse_json(text)?)
+    }
+
+    /// Classifies a message from a JSON value.
+    ///
+    /// An object with a `method` member is a request (or a notification when it
+    /// has no `id`), and an object with a `result` or `error` member is a
+    /// response. An array is a batch, classified by its first member.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The JSON value to classify
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcMessage)` - The classified and validated message
+    /// * `Err(ValidationError)` - The value is not a valid JSON-RPC 2.0 message
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcMessage;
+    /// use serde_json::json;
+    ///
+    /// let message = RpcMessage::from_json(json!([
+    ///     {"jsonrpc": "2.0", "method": "a", "id": 1},
+    ///     {"jsonrpc": "2.0", "method": "b"}
+    /// ]))
+    /// .unwrap();
+    /// assert!(matches!(message, RpcMessage::RequestBatch(_)));
+    /// ```
+    pub fn from_json(json: JsonValue) -> Result<Self, ValidationError> {
+        match &json {
+            JsonValue::Array(members) => match members.first() {
+                Some(first) if is_response(first) => {
+                    RpcBatchResponse::from_json(json).map(RpcMessage::ResponseBatch)
+                }
+                _ => RpcBatchRequest::from_json(json).map(RpcMessage::RequestBatch),
+            },
+            JsonValue::Object(_) if is_response(&json) => {
+                RpcResponse::from_json(json).map(RpcMessage::Response)
+            }
+            JsonValue::Object(_) => RpcRequest::from_json(json).map(RpcMessage::from),
+            _ => Err(ValidationError::new(
+                ValidationErrorKind::InvalidStructure,
+                RpcId::Null,
+                "a message must be a JSON object or array",
+            )),
+        }
+    }
+
+    /// Converts the message to a JSON value.
+    ///
+    /// # Returns
+    ///
+    /// The JSON object or array representing the message.
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            RpcMessage::Request(request) | RpcMessage::Notification(request) => request.to_json(),
+            RpcMessage::Response(response) => response.to_json(),
+            RpcMessage::RequestBatch(batch) => batch.to_json(),
+            RpcMessage::ResponseBatch(batch) => batch.to_json(),
+        }
+    }
+}
+
+/// Checks if a JSON value looks like a response rather than a request.
+///
+/// Requests are recognised by their `method` member; anything else carrying a
+/// `result` or `error` member is treated as a response.
+fn is_response(json: &JsonValue) -> bool {
+    json.get("method").is_none() && (json.get("result").is_some() || json.get("error").is_some())
+}
+
+impl From<RpcRequest> for RpcMessage {
+    /// Wraps a request, classifying it as a notification when it has no `id`.
+    fn from(request: RpcRequest) -> Self {
+        if request.is_notification() {
+            RpcMessage::Notification(request)
+        } else {
+            RpcMessage::Request(request)
+        }
+    }
+}
+
+impl From<RpcResponse> for RpcMessage {
+    fn from(response: RpcResponse) -> Self {
+        RpcMessage::Response(response)
+    }
+}
+
+impl From<RpcBatchRequest> for RpcMessage {
+    fn from(batch: RpcBatchRequest) -> Self {
+        RpcMessage::RequestBatch(batch)
+    }
+}
+
+impl From<RpcBatchResponse> for RpcMessage {
+    fn from(batch: RpcBatchResponse) -> Self {
+        RpcMessage::ResponseBatch(batch)
+    }
+}
+
+impl FromStr for RpcMessage {
+    type Err = ValidationError;
+
+    /// Parses and classifies a message from JSON text.
+    ///
+    /// Equivalent to calling `RpcMessage::parse()`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+impl fmt::Display for RpcMessage {
+    /// Formats the message as a JSON string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcMessage::Request(request) | RpcMessage::Notification(request) => {
+                write!(f, "{}", request)
+            }
+            RpcMessage::Response(response) => write!(f, "{}", response),
+            RpcMessage::RequestBatch(batch) => write!(f, "{}", batch),
+            RpcMessage::ResponseBatch(batch) => write!(f, "{}", batch),
+        }
+    }
+}
+
+// ===========================================================================
+// TESTS: RpcMessage
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_request_and_notification() {
+        let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "a", "id": "x"}"#).unwrap();
+        match message {
+            RpcMessage::Request(request) => assert_eq!(request.id(), Some(&RpcId::from("x"))),
+            other => panic!("expected a request, got {:?}", other),
+        }
+
+        let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "a"}"#).unwrap();
+        assert!(matches!(message, RpcMessage::Notification(_)));
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "result": null, "id": 1}"#).unwrap();
+        assert!(matches!(message, RpcMessage::Response(ref r) if r.is_success()));
+
+        let message = RpcMessage::parse(
+            r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 1}"#,
+        )
+        .unwrap();
+        assert!(matches!(message, RpcMessage::Response(ref r) if r.is_error()));
+    }
+
+    #[test]
+    fn test_parse_batches() {
+        let message = RpcMessage::from_json(json!([
+            {"jsonrpc": "2.0", "method": "a", "id": 1},
+            {"jsonrpc": "2.0", "method": "b"}
+        ]))
+        .unwrap();
+        assert!(matches!(message, RpcMessage::RequestBatch(ref b) if b.len() == 2));
+
+        let message = RpcMessage::from_json(json!([
+            {"jsonrpc": "2.0", "result": 1, "id": 1},
+            {"jsonrpc": "2.0", "result": 2, "id": 2}
+        ]))
+        .unwrap();
+        assert!(matches!(message, RpcMessage::ResponseBatch(ref b) if b.len() == 2));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        // Invalid JSON
+        let error = RpcMessage::parse("{").unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::Parse);
+
+        // Not an object or array
+        let error = RpcMessage::parse("42").unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+
+        // Empty batch
+        let error = RpcMessage::parse("[]").unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::EmptyBatch);
+
+        // An object that is neither a request nor a response
+        let error = RpcMessage::parse(r#"{"jsonrpc": "2.0", "id": 5}"#).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidMethod);
+        assert_eq!(error.id(), &RpcId::Number(5));
+
+        // A malformed response
+        let error = RpcMessage::parse(
+            r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": 1, "message": "m"}, "id": 5}"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidOutcome);
+    }
+
+    #[test]
+    fn test_conversions_and_roundtrip() {
+        let mut request = RpcRequest::new();
+        request.set_method("sum".to_string());
+
+        let messages = [
+            RpcMessage::from(request),
+            RpcMessage::from(RpcRequest::notification()),
+            RpcMessage::from(RpcResponse::success(3, json!(3))),
+        ];
+        assert!(matches!(messages[0], RpcMessage::Request(_)));
+        assert!(matches!(messages[1], RpcMessage::Notification(_)));
+        assert!(matches!(messages[2], RpcMessage::Response(_)));
+
+        for message in messages {
+            let text = message.to_string();
+            let parsed: RpcMessage = text.parse().unwrap();
+            assert_eq!(parsed.to_json(), message.to_json());
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::codec::{self, FrameCodec};
+use crate::{RpcMessage, TransportError};
+use futures::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt};
+use std::io::{self, BufRead, Write};
+
+// ===========================================================================
+// STRUCT: NdjsonCodec
+// ===
+
+/// Frames messages as newline-delimited JSON: one compact JSON value per line.
+///
+/// Messages are written without raw newlines, since newlines inside strings
+/// are escaped, so a line always holds exactly one message. When reading,
+/// `\r\n` line endings are accepted and blank lines are skipped. Lines longer
+/// than the maximum line length are discarded with a
+/// `TransportError::FrameTooLarge` error, without ever being buffered whole,
+/// and reading resumes at the next line.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{FrameCodec, NdjsonCodec, RpcMessage, RpcRequest};
+/// use std::io::Cursor;
+///
+/// let codec = NdjsonCodec::new();
+/// let mut request = RpcRequest::new();
+/// request.set_method("log".to_string()).set_params(serde_json::json!(["a\nb"]));
+///
+/// let mut buffer = Vec::new();
+/// codec.write_message(&mut buffer, &RpcMessage::from(request)).unwrap();
+/// assert_eq!(buffer.iter().filter(|&&b| b == b'\n').count(), 1);
+///
+/// let message = codec.read_message(&mut Cursor::new(buffer)).unwrap();
+/// assert!(matches!(message, Some(RpcMessage::Request(_))));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct NdjsonCodec {
+    max_line_length: usize,
+}
+
+impl NdjsonCodec {
+    /// The default maximum line length: 16 MiB.
+    pub const DEFAULT_MAX_LINE_LENGTH: usize = 16 * 1024 * 1024;
+
+    /// Creates a codec with the default maximum line length.
+    pub fn new() -> Self {
+        Self::with_max_line_length(Self::DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a codec that rejects lines longer than `max_line_length` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_line_length` - The maximum length of a line, excluding its line ending
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        Self { max_line_length }
+    }
+
+    /// Returns the maximum line length in bytes.
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Reads and validates the next message from an async stream.
+    ///
+    /// This is the async equivalent of `FrameCodec::read_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream to read from
+    ///
+    /// # Returns
+    ///
+    /// The next message, `None` at the end of the stream, or the error that
+    /// prevented reading it.
+    pub async fn read_message_async<R>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Option<RpcMessage>, TransportError>
+    where
+        R: AsyncBufRead + Unpin + ?Sized,
+    {
+        let line = loop {
+            match codec::read_line_async(reader, self.max_line_length).await? {
+                Some(line) if is_blank(&line) => continue,
+                line => break line,
+            }
+        };
+
+        match line {
+            Some(frame) => Ok(Some(codec::parse_frame(&frame)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes a message to an async stream and flushes it.
+    ///
+    /// This is the async equivalent of `FrameCodec::write_message()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The stream to write to
+    /// * `message` - The message to write
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message has been written and flushed.
+    pub async fn write_message_async<W>(
+        &self,
+        writer: &mut W,
+        message: &RpcMessage,
+    ) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let mut line = codec::encode_message(message);
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        writer.flush().await
+    }
+}
+
+impl Default for NdjsonCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameCodec for NdjsonCodec {
+    fn read_frame(&self, reader: &mut dyn BufRead) -> Result<Option<Vec<u8>>, TransportError> {
+        loop {
+            match codec::read_line(reader, self.max_line_length)? {
+                Some(line) if is_blank(&line) => continue,
+                line => return Ok(line),
+            }
+        }
+    }
+
+    /// Writes the frame followed by a newline.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if the frame contains a raw
+    /// newline, since the reader would split it into two frames.
+    fn write_frame(&self, writer: &mut dyn Write, frame: &[u8]) -> io::Result<()> {
+        if frame.contains(&b'\n') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "an NDJSON frame must not contain a raw newline",
+            ));
+        }
+
+        let mut line = Vec::with_capacity(frame.len() + 1);
+        line.extend_from_slice(frame);
+        line.push(b'\n');
+        writer.write_all(&line)
+    }
+}
+
+/// Checks if a line holds nothing but whitespace.
+///
+/// Blank lines between messages are skipped.
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+// ===========================================================================
+// TESTS: NdjsonCodec
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RpcRequest, RpcResponse, ValidationErrorKind};
+    use futures::executor;
+    use serde_json::json;
+    use std::io::{BufReader, Cursor};
+
+    /// Reads through a tiny buffer so every frame arrives in partial reads.
+    fn chunked(input: &[u8]) -> BufReader<Cursor<Vec<u8>>> {
+        BufReader::with_capacity(3, Cursor::new(input.to_vec()))
+    }
+
+    fn request(method: &str, params: serde_json::Value) -> RpcMessage {
+        let mut request = RpcRequest::new();
+        request.set_method(method.to_string()).set_params(params);
+        RpcMessage::from(request)
+    }
+
+    // ---------------------------------------------------------------------------
+    // Reading Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_read_messages_across_partial_reads() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"method\":\"a\",\"id\":1}\r\n\n  \n{\"jsonrpc\":\"2.0\",\"result\":2,\"id\":1}\n";
+        let mut reader = chunked(input);
+        let codec = NdjsonCodec::new();
+
+        assert!(matches!(
+            codec.read_message(&mut reader).unwrap(),
+            Some(RpcMessage::Request(_))
+        ));
+        assert!(matches!(
+            codec.read_message(&mut reader).unwrap(),
+            Some(RpcMessage::Response(_))
+        ));
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_final_line_without_newline() {
+        let mut reader = chunked(b"{\"jsonrpc\":\"2.0\",\"method\":\"a\"}");
+        let codec = NdjsonCodec::new();
+
+        assert!(matches!(
+            codec.read_message(&mut reader).unwrap(),
+            Some(RpcMessage::Notification(_))
+        ));
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_invalid_line_is_recoverable() {
+        let mut reader = chunked(b"{oops\n{\"jsonrpc\":\"2.0\",\"method\":\"a\"}\n");
+        let codec = NdjsonCodec::new();
+
+        match codec.read_message(&mut reader) {
+            Err(TransportError::Invalid(error)) => {
+                assert_eq!(error.kind(), ValidationErrorKind::Parse)
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(codec.read_message(&mut reader).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let codec = NdjsonCodec::with_max_line_length(40);
+        let long = format!(
+            "{{\"jsonrpc\":\"2.0\",\"method\":\"{}\"}}\n",
+            "x".repeat(100)
+        );
+        let short = "{\"jsonrpc\":\"2.0\",\"method\":\"a\"}\n";
+        let mut reader = chunked(format!("{}{}", long, short).as_bytes());
+
+        assert!(matches!(
+            codec.read_message(&mut reader),
+            Err(TransportError::FrameTooLarge { limit: 40 })
+        ));
+        assert!(codec.read_message(&mut reader).unwrap().is_some());
+        assert!(codec.read_message(&mut reader).unwrap().is_none());
+
+        // A line of exactly the limit is accepted, with or without `\r`
+        let exact = "{\"jsonrpc\":\"2.0\",\"method\":\"abcdefghijk\"}";
+        assert_eq!(exact.len(), 40);
+        let mut reader = chunked(format!("{}\r\n", exact).as_bytes());
+        assert!(codec.read_message(&mut reader).unwrap().is_some());
+    }
+
+    // ---------------------------------------------------------------------------
+    // Writing Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_write_escapes_embedded_newlines() {
+        let codec = NdjsonCodec::new();
+        let mut buffer = Vec::new();
+
+        let message = request("log", json!({"text": "line one\nline two"}));
+        codec.write_message(&mut buffer, &message).unwrap();
+        codec
+            .write_message(
+                &mut buffer,
+                &RpcMessage::from(RpcResponse::success(1, json!("ok"))),
+            )
+            .unwrap();
+        assert_eq!(buffer.iter().filter(|&&b| b == b'\n').count(), 2);
+
+        let mut reader = Cursor::new(buffer);
+        match codec.read_message(&mut reader).unwrap() {
+            Some(RpcMessage::Request(request)) => {
+                assert_eq!(request.params()["text"], "line one\nline two")
+            }
+            other => panic!("expected a request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_frame_rejects_raw_newlines() {
+        let codec = NdjsonCodec::new();
+        let error = codec.write_frame(&mut Vec::new(), b"{\n}").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let codec = NdjsonCodec::new();
+        let batch: crate::RpcBatchRequest =
+            vec![RpcRequest::new(), RpcRequest::notification()].into();
+
+        let mut buffer = Vec::new();
+        codec
+            .write_message(&mut buffer, &RpcMessage::from(batch))
+            .unwrap();
+
+        let message = codec.read_message(&mut Cursor::new(buffer)).unwrap();
+        assert!(matches!(message, Some(RpcMessage::RequestBatch(ref b)) if b.len() == 2));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Async Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_async_roundtrip() {
+        let codec = NdjsonCodec::with_max_line_length(64);
+
+        executor::block_on(async {
+            let mut buffer = futures::io::Cursor::new(Vec::new());
+            codec
+                .write_message_async(&mut buffer, &request("a", json!(["x\ny"])))
+                .await
+                .unwrap();
+            codec
+                .write_message_async(&mut buffer, &request("b", json!(["z".repeat(100)])))
+                .await
+                .unwrap();
+            codec
+                .write_message_async(&mut buffer, &request("c", json!(null)))
+                .await
+                .unwrap();
+
+            let bytes = buffer.into_inner();
+            let mut reader =
+                futures::io::BufReader::with_capacity(5, futures::io::Cursor::new(bytes));
+
+            let first = codec.read_message_async(&mut reader).await.unwrap();
+            assert!(matches!(first, Some(RpcMessage::Request(ref r)) if r.params()[0] == "x\ny"));
+            assert!(matches!(
+                codec.read_message_async(&mut reader).await,
+                Err(TransportError::FrameTooLarge { .. })
+            ));
+            let third = codec.read_message_async(&mut reader).await.unwrap();
+            assert!(matches!(third, Some(RpcMessage::Request(ref r)) if r.method() == "c"));
+            assert!(
+                codec
+                    .read_message_async(&mut reader)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        });
+    }
+}
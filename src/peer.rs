@@ -0,0 +1,423 @@
+use crate::server::{Dispatcher, error_reply};
+use crate::{IdGenerator, RpcClient, RpcContext, RpcRouter, SequentialIdGenerator, Transport};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+// ===========================================================================
+// STRUCT: RpcPeer
+// ===
+
+/// One endpoint of a connection on which both sides call and serve, as in
+/// the Language Server Protocol.
+///
+/// A background thread reads from the transport. Inbound responses complete
+/// the calls made through `client()`, matched by their ID; inbound requests
+/// and notifications are dispatched through the router, and the replies are
+/// sent back on the same transport. The `RpcContext` of every request holds
+/// the peer's client too, so a handler can call the other side while it
+/// works. A handler that waits on such a call must be async and `.await` it,
+/// since `PendingCall::wait()` cannot run inside the router.
+///
+/// Requests are handled on their own threads, so a slow handler does not
+/// hold up the responses its own calls are waiting for. At most 64 requests
+/// run at once; further ones are answered with `RpcError::server_busy()`.
+/// Notifications are handled in the order they arrive, on a thread of their
+/// own, so their handlers can call the other side too.
+///
+/// Dropping the peer closes the sending side of the transport without
+/// waiting; `close()` does the same and `wait()` then blocks until the other
+/// side has closed as well.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{MemoryTransport, RpcContext, RpcError, RpcPeer, RpcRouter};
+/// use serde_json::json;
+///
+/// let (left, right) = MemoryTransport::pair();
+///
+/// // The server asks the editor for its configuration before answering
+/// let mut server_router = RpcRouter::new();
+/// server_router.register_async_with_context("hover", |_: (), context: RpcContext| async move {
+///     let client = context.client().unwrap();
+///     let config = client.call("workspace/configuration", json!(null)).await;
+///     let config = config.map_err(|error| RpcError::new(-32000, error.to_string()))?;
+///     Ok::<_, RpcError>(format!("tab size {}", config["tabSize"]))
+/// });
+/// let server = RpcPeer::new(right, server_router);
+///
+/// let mut editor_router = RpcRouter::new();
+/// editor_router.register("workspace/configuration", |_: ()| {
+///     Ok::<_, RpcError>(json!({ "tabSize": 4 }))
+/// });
+/// let editor = RpcPeer::new(left, editor_router);
+///
+/// let hover = editor.client().call("hover", json!(null)).wait().unwrap();
+/// assert_eq!(hover, json!("tab size 4"));
+///
+/// editor.close();
+/// server.close();
+/// editor.wait();
+/// ```
+pub struct RpcPeer {
+    client: RpcClient,
+    transport: Arc<dyn Transport>,
+    session: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl RpcPeer {
+    /// Starts serving a connection and returns the endpoint calling the other
+    /// side.
+    ///
+    /// Request IDs are numbered 1, 2, 3, ... by a counter owned by this peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The connection to the other side
+    /// * `router` - The router dispatching the other side's calls
+    pub fn new(transport: impl Transport + 'static, router: RpcRouter) -> Self {
+        Self::with_generator(transport, router, SequentialIdGenerator::new())
+    }
+
+    /// Starts serving a connection, drawing the IDs of outgoing requests from
+    /// the given generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The connection to the other side
+    /// * `router` - The router dispatching the other side's calls
+    /// * `generator` - The generator providing request IDs
+    pub fn with_generator(
+        transport: impl Transport + 'static,
+        router: RpcRouter,
+        generator: impl IdGenerator + 'static,
+    ) -> Self {
+        let transport: Arc<dyn Transport> = Arc::new(transport);
+        let client = session_client(&transport, generator);
+
+        let session = {
+            let (transport, client) = (transport.clone(), client.clone());
+            thread::spawn(move || {
+                let mut context = RpcContext::new();
+                context.set_client(client.clone());
+                run_session(transport.as_ref(), &router, &client, &context);
+            })
+        };
+
+        Self {
+            client,
+            transport,
+            session: Mutex::new(Some(session)),
+        }
+    }
+
+    /// Returns the client calling the other side.
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// Closes the sending side of the transport.
+    ///
+    /// Requests from the other side are still served, and responses to calls
+    /// already made still arrive, until the other side closes too.
+    pub fn close(&self) {
+        let _ = self.transport.close();
+    }
+
+    /// Blocks until the other side has closed the connection and every
+    /// request it made has been answered.
+    ///
+    /// Calls still pending then fail with `ClientError::Disconnected`.
+    pub fn wait(&self) {
+        if let Some(session) = self.session.lock().unwrap().take() {
+            let _ = session.join();
+        }
+    }
+
+    /// Checks if the connection has closed.
+    pub fn is_closed(&self) -> bool {
+        self.client.is_closed()
+    }
+}
+
+impl Drop for RpcPeer {
+    fn drop(&mut self) {
+        let _ = self.transport.close();
+    }
+}
+
+impl fmt::Debug for RpcPeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcPeer")
+            .field("client", &self.client)
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// FUNCTIONS: Sessions
+// ===
+
+/// Runs one connection on which both sides call and serve, until it closes:
+/// inbound requests are dispatched through the router and inbound responses
+/// complete the calls made through `client`.
+///
+/// Messages are handled by a `Dispatcher`, off the reading thread, so a
+/// handler can wait on a call to the peer while this loop reads its response.
+pub(crate) fn run_session(
+    transport: &dyn Transport,
+    router: &RpcRouter,
+    client: &RpcClient,
+    context: &RpcContext,
+) {
+    thread::scope(|scope| {
+        let dispatcher = Dispatcher::new(scope, transport, router, context);
+        loop {
+            let inbound = match transport.receive() {
+                Ok(Some(message)) => client.handle_message(message),
+                Ok(None) => break,
+                Err(error) => match error_reply(error) {
+                    Ok(reply) => {
+                        let _ = transport.send(reply);
+                        None
+                    }
+                    Err(_) => break,
+                },
+            };
+
+            if let Some(message) = inbound {
+                dispatcher.dispatch(message);
+            }
+        }
+
+        // Handlers still waiting on the peer fail instead of blocking the session
+        client.close();
+    });
+}
+
+/// Creates a client that sends its calls over a transport whose inbound
+/// messages are read by `run_session()`.
+pub(crate) fn session_client<T>(
+    transport: &Arc<T>,
+    generator: impl IdGenerator + 'static,
+) -> RpcClient
+where
+    T: Transport + ?Sized + 'static,
+{
+    let transport = transport.clone();
+    RpcClient::with_generator(move |message| transport.send(message), generator)
+}
+
+// ===========================================================================
+// TESTS: RpcPeer
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::MAX_CONCURRENT_REQUESTS;
+    use crate::{ClientError, MemoryTransport, RpcError, RpcId, RpcMessage, RpcRequest};
+    use serde_json::json;
+    use std::sync::{Condvar, mpsc};
+    use std::time::Duration;
+
+    /// A router whose `countdown` calls the other side's `countdown` until
+    /// it reaches zero.
+    fn countdown_router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register_async_with_context(
+            "countdown",
+            |(n,): (u64,), context: RpcContext| async move {
+                if n == 0 {
+                    return Ok::<_, RpcError>(json!([]));
+                }
+                let client = context.client().unwrap();
+                let rest = client
+                    .call("countdown", json!([n - 1]))
+                    .await
+                    .map_err(|error| RpcError::new(-32000, error.to_string()))?;
+                let mut values = vec![json!(n)];
+                values.extend(rest.as_array().unwrap().iter().cloned());
+                Ok(json!(values))
+            },
+        );
+        router
+    }
+
+    #[test]
+    fn test_both_sides_call_and_serve() {
+        let (left, right) = MemoryTransport::pair();
+        let left = RpcPeer::new(left, countdown_router());
+        let right = RpcPeer::new(right, countdown_router());
+
+        // Each call bounces between the two sides before it resolves
+        let from_left = left.client().call("countdown", json!([5]));
+        let from_right = right.client().call("countdown", json!([4]));
+        assert_eq!(from_left.wait().unwrap(), json!([5, 4, 3, 2, 1]));
+        assert_eq!(from_right.wait().unwrap(), json!([4, 3, 2, 1]));
+
+        assert!(matches!(
+            left.client().call("missing", json!(null)).wait(),
+            Err(ClientError::Rpc(error)) if error.code() == -32601
+        ));
+    }
+
+    #[test]
+    fn test_notifications_and_invalid_messages() {
+        let (notified, notifications) = mpsc::channel();
+        let mut router = RpcRouter::new();
+        router.register("log", move |(text,): (String,)| {
+            notified.send(text).unwrap();
+            Ok::<_, RpcError>(())
+        });
+
+        let (local, remote) = MemoryTransport::pair();
+        let peer = RpcPeer::new(local, router);
+
+        // Notifications are handled in order and never answered
+        for text in ["one", "two"] {
+            let mut notification = RpcRequest::notification();
+            notification
+                .set_method("log".to_string())
+                .set_params(json!([text]));
+            remote.send(RpcMessage::from(notification)).unwrap();
+        }
+        assert_eq!(notifications.recv().unwrap(), "one");
+        assert_eq!(notifications.recv().unwrap(), "two");
+
+        // A request the router cannot parse is answered with an error
+        let mut request = RpcRequest::new();
+        request.set_method("log".to_string()).set_params(json!([1]));
+        remote.send(RpcMessage::from(request)).unwrap();
+        match remote.receive().unwrap() {
+            Some(RpcMessage::Response(response)) => {
+                assert_eq!(response.error().unwrap().code(), -32602)
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+
+        // Calls made by the peer arrive as requests with ids
+        let call = peer.client().call("ping", json!(null));
+        let Some(RpcMessage::Request(request)) = remote.receive().unwrap() else {
+            panic!("expected a request");
+        };
+        assert_eq!(request.method(), "ping");
+        assert_eq!(call.id(), request.id());
+        drop(peer);
+        assert!(remote.receive().unwrap().is_none());
+        drop(remote);
+        assert!(matches!(call.wait(), Err(ClientError::Disconnected)));
+    }
+
+    #[test]
+    fn test_notification_handlers_can_call_back() {
+        let (answered, answers) = mpsc::channel();
+        let mut router = RpcRouter::new();
+        router.register_async_with_context("changed", move |_: (), context: RpcContext| {
+            let answered = answered.clone();
+            async move {
+                let client = context.client().unwrap();
+                let version = client.call("version", json!(null)).await;
+                answered.send(version.ok()).unwrap();
+                Ok::<_, RpcError>(())
+            }
+        });
+        let mut editor_router = RpcRouter::new();
+        editor_router.register("version", |_: ()| Ok::<_, RpcError>(3));
+
+        let (left, right) = MemoryTransport::pair();
+        let _server = RpcPeer::new(right, router);
+        let editor = RpcPeer::new(left, editor_router);
+
+        // The reply to the handler's call is read while the handler waits
+        editor.client().notify("changed", json!(null)).unwrap();
+        let answer = answers.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(answer, Some(json!(3)));
+    }
+
+    #[test]
+    fn test_requests_beyond_the_limit_are_refused() {
+        let gate = Arc::new((Mutex::new(false), Condvar::new()));
+        let mut router = RpcRouter::new();
+        let open = gate.clone();
+        router.register("block", move |_: ()| {
+            let (lock, opened) = &*open;
+            let _open = opened.wait_while(lock.lock().unwrap(), |open| !*open);
+            Ok::<_, RpcError>(())
+        });
+
+        let (local, remote) = MemoryTransport::pair();
+        let _peer = RpcPeer::new(local, router);
+        for id in 0..=MAX_CONCURRENT_REQUESTS {
+            let mut request = RpcRequest::new();
+            request.set_method("block".to_string()).set_id(id as u64);
+            remote.send(RpcMessage::from(request)).unwrap();
+        }
+
+        // Every handler is blocked, so the one request over the limit is refused
+        let Some(RpcMessage::Response(refused)) = remote.receive().unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(refused.id(), &RpcId::from(MAX_CONCURRENT_REQUESTS as u64));
+        assert_eq!(refused.error().unwrap().code(), RpcError::SERVER_BUSY);
+
+        *gate.0.lock().unwrap() = true;
+        gate.1.notify_all();
+        for _ in 0..MAX_CONCURRENT_REQUESTS {
+            let Some(RpcMessage::Response(response)) = remote.receive().unwrap() else {
+                panic!("expected a response");
+            };
+            assert!(response.error().is_none());
+        }
+    }
+
+    #[test]
+    fn test_dropped_calls_are_cancelled_on_the_other_side() {
+        let (started, starts) = mpsc::channel();
+        let (stopped, stops) = mpsc::channel();
+        let mut router = RpcRouter::new();
+        router.register_with_context("index", move |_: (), context: &RpcContext| {
+            started.send(()).unwrap();
+            while !context.cancellation().is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            stopped.send(()).unwrap();
+            context.cancellation().check()?;
+            Ok::<_, RpcError>(())
+        });
+
+        let (left, right) = MemoryTransport::pair();
+        let left = RpcPeer::new(left, RpcRouter::new());
+        let _right = RpcPeer::new(right, router);
+
+        let call = left.client().call("index", json!(null));
+        starts.recv().unwrap();
+        drop(call);
+        stops.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(left.client().pending_count(), 0);
+    }
+
+    #[test]
+    fn test_close_fails_pending_calls() {
+        let (left, right) = MemoryTransport::pair();
+        let left = RpcPeer::new(left, RpcRouter::new());
+
+        // The other side never answers, then goes away
+        let call = left.client().call("slow", json!(null));
+        assert!(matches!(
+            right.receive().unwrap(),
+            Some(RpcMessage::Request(_))
+        ));
+        drop(right);
+
+        left.wait();
+        assert!(left.is_closed());
+        assert!(matches!(call.wait(), Err(ClientError::Disconnected)));
+        assert!(matches!(
+            left.client().call("late", json!(null)).wait(),
+            Err(ClientError::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,740 @@
+use crate::codec::{self, CodecFactory};
+use crate::{
+    FrameCodec, NdjsonCodec, RpcClient, RpcMessage, StreamTransport, Transport, TransportError,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufReader};
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The maximum amount of stderr output kept per process, in bytes.
+const MAX_STDERR_CAPTURE: usize = 64 * 1024;
+
+/// How often an exiting process is checked while waiting for it.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long to wait for the last stderr output after a process exits.
+const STDERR_GRACE: Duration = Duration::from_millis(100);
+
+// ===========================================================================
+// STRUCT: ChildTransport
+// ===
+
+/// A transport to a child process, over its stdin and stdout.
+///
+/// This is the client side of `serve_stdio()`: the child is spawned with
+/// piped stdin and stdout, requests are written to its stdin and responses
+/// read from its stdout. When the child exits, `receive()` fails with
+/// `TransportError::ProcessExited`, so an `RpcClient` running over the
+/// transport fails its pending calls with `ClientError::ProcessExited`.
+///
+/// The child's stderr is left as configured on the `Command`: inherited by
+/// default, so its logs appear on this process's stderr. If it is set to
+/// `Stdio::piped()`, the most recent output is captured instead and can be
+/// read with `stderr_output()`.
+///
+/// Closing the transport closes the child's stdin, which a stdio server takes
+/// as the signal to shut down. Dropping it does not kill the child.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::{ChildTransport, RpcClient};
+/// use serde_json::json;
+/// use std::process::Command;
+///
+/// let transport = ChildTransport::spawn(&mut Command::new("my-plugin"))?;
+/// let client = RpcClient::connect(transport);
+/// let sum = client.call("add", json!([2, 3])).wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ChildTransport {
+    stream: StreamTransport,
+    child: Mutex<Child>,
+    id: u32,
+    stderr: Arc<Mutex<StderrLog>>,
+    stderr_reader: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ChildTransport {
+    /// Spawns a child process framed as newline-delimited JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to run; its stdin and stdout are replaced with pipes
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented spawning the process.
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        Self::spawn_with_codec(command, NdjsonCodec::new())
+    }
+
+    /// Spawns a child process framed by `codec`.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to run; its stdin and stdout are replaced with pipes
+    /// * `codec` - The framing format used in both directions
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented spawning the process.
+    pub fn spawn_with_codec(
+        command: &mut Command,
+        codec: impl FrameCodec + 'static,
+    ) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let stderr = Arc::new(Mutex::new(StderrLog::default()));
+        let stderr_reader = child.stderr.take().map(|pipe| {
+            let log = stderr.clone();
+            thread::spawn(move || capture_stderr(pipe, &log))
+        });
+
+        Ok(Self {
+            stream: StreamTransport::new(stdout, stdin, codec),
+            id: child.id(),
+            child: Mutex::new(child),
+            stderr,
+            stderr_reader: Mutex::new(stderr_reader),
+        })
+    }
+
+    /// Returns the operating system's ID for the child process.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the captured stderr output of the child process.
+    ///
+    /// Only the most recent 64 KiB are kept. The output is empty unless the
+    /// command's stderr was set to `Stdio::piped()`.
+    pub fn stderr_output(&self) -> String {
+        self.stderr.lock().unwrap().output()
+    }
+
+    /// Checks if the child process has exited, without blocking.
+    ///
+    /// # Returns
+    ///
+    /// The exit status, or `None` if the process is still running.
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        self.child.lock().unwrap().try_wait()
+    }
+
+    /// Blocks until the child process exits.
+    ///
+    /// Once this returns, the stderr output written before the exit has been
+    /// captured.
+    ///
+    /// # Returns
+    ///
+    /// The exit status of the process.
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        // Polling leaves the child unlocked, so `kill()` works in the meantime
+        let status = loop {
+            if let Some(status) = self.try_wait()? {
+                break status;
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        };
+
+        self.finish_stderr();
+        Ok(status)
+    }
+
+    /// Kills the child process.
+    ///
+    /// Killing a process that has already exited is not an error.
+    pub fn kill(&self) -> io::Result<()> {
+        self.child.lock().unwrap().kill()
+    }
+
+    /// Waits briefly for the stderr reader to drain the pipe.
+    ///
+    /// The reader is not waited on indefinitely, since a grandchild process
+    /// may keep the pipe open after the child has exited.
+    fn finish_stderr(&self) {
+        let mut reader = self.stderr_reader.lock().unwrap();
+        let deadline = Instant::now() + STDERR_GRACE;
+
+        while reader.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            if Instant::now() >= deadline {
+                return;
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+
+        if let Some(handle) = reader.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Transport for ChildTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        self.stream.send(message)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        match self.stream.receive() {
+            // The child closed its stdout, which it only does when exiting
+            Ok(None) => Err(TransportError::ProcessExited(self.wait()?)),
+            result => result,
+        }
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        self.stream.close()
+    }
+}
+
+impl fmt::Debug for ChildTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildTransport")
+            .field("id", &self.id)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: StderrLog
+// ===
+
+/// The most recent lines written to a child's stderr.
+#[derive(Default)]
+struct StderrLog {
+    lines: VecDeque<String>,
+    size: usize,
+}
+
+impl StderrLog {
+    /// Appends a line, dropping the oldest lines once the log is full.
+    fn push(&mut self, line: String) {
+        self.size += line.len() + 1;
+        self.lines.push_back(line);
+
+        while self.size > MAX_STDERR_CAPTURE && self.lines.len() > 1 {
+            if let Some(oldest) = self.lines.pop_front() {
+                self.size -= oldest.len() + 1;
+            }
+        }
+    }
+
+    /// Returns the captured lines, each followed by a newline.
+    fn output(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+}
+
+/// Reads a child's stderr into the log until the pipe closes.
+fn capture_stderr(pipe: ChildStderr, log: &Mutex<StderrLog>) {
+    let mut reader = BufReader::new(pipe);
+
+    loop {
+        match codec::read_line(&mut reader, MAX_STDERR_CAPTURE) {
+            Ok(Some(line)) => log
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&line).into_owned()),
+            Err(error) if error.is_recoverable() => continue,
+            Ok(None) | Err(_) => return,
+        }
+    }
+}
+
+// ===========================================================================
+// STRUCT: ChildProcess
+// ===
+
+/// Decides whether to restart a process: receives the exit status and the
+/// number of restarts so far.
+type RestartPolicy = dyn Fn(ExitStatus, usize) -> bool + Send + Sync;
+
+/// Runs after a process has been restarted, with the new client.
+type RestartHook = dyn Fn(&RpcClient) + Send + Sync;
+
+/// Configures a child process to be spawned and supervised as an RPC server.
+///
+/// `spawn()` starts the process and returns a `ChildClient`. When the process
+/// exits unexpectedly, the restart policy decides whether to start it again;
+/// after a restart, the restart hook runs with the new client, for example to
+/// repeat an initialization handshake. Calls that were pending on the exited
+/// process fail with `ClientError::ProcessExited` once the restart is done.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::ChildProcess;
+/// use serde_json::json;
+/// use std::process::Command;
+///
+/// let mut process = ChildProcess::new(Command::new("my-plugin"));
+/// process
+///     .set_restart_policy(|status, restarts| {
+///         eprintln!("plugin exited ({}), restart #{}", status, restarts + 1);
+///         restarts < 3
+///     })
+///     .set_on_restart(|client| {
+///         let _ = client.notify("initialized", json!({}));
+///     });
+///
+/// let child = process.spawn()?;
+/// let sum = child.client().call("add", json!([2, 3])).wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ChildProcess {
+    command: Command,
+    codec: Box<CodecFactory>,
+    restart_policy: Box<RestartPolicy>,
+    on_restart: Box<RestartHook>,
+}
+
+impl ChildProcess {
+    /// Configures a process framed as newline-delimited JSON, which is never
+    /// restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to run; its stdin and stdout are replaced with pipes
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            codec: Box::new(|| Box::new(NdjsonCodec::new())),
+            restart_policy: Box::new(|_, _| false),
+            on_restart: Box::new(|_| {}),
+        }
+    }
+
+    /// Sets the framing format used with the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec; each spawned process gets its own copy
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_codec<C>(&mut self, codec: C) -> &mut Self
+    where
+        C: FrameCodec + Clone + 'static,
+    {
+        self.codec = Box::new(move || Box::new(codec.clone()));
+        self
+    }
+
+    /// Sets the policy deciding whether to restart the process when it exits.
+    ///
+    /// The policy is not consulted when the process exits because of
+    /// `ChildClient::close()`, `kill()` or `restart()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Receives the exit status and the number of restarts so
+    ///   far, and returns `true` to restart the process
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_restart_policy(
+        &mut self,
+        policy: impl Fn(ExitStatus, usize) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.restart_policy = Box::new(policy);
+        self
+    }
+
+    /// Sets a hook to run each time the process has been restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - Receives the client connected to the new process
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_on_restart(
+        &mut self,
+        hook: impl Fn(&RpcClient) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_restart = Box::new(hook);
+        self
+    }
+
+    /// Spawns the process and connects a client to it.
+    ///
+    /// # Returns
+    ///
+    /// The supervised process, or the error that prevented spawning it.
+    pub fn spawn(self) -> io::Result<ChildClient> {
+        let state = Arc::new(ChildState {
+            command: Mutex::new(self.command),
+            codec: self.codec,
+            restart_policy: self.restart_policy,
+            on_restart: self.on_restart,
+            slot: Mutex::new(Slot::default()),
+            restarts: AtomicUsize::new(0),
+        });
+
+        let connection = state.connect()?;
+        state.slot.lock().unwrap().connection = Some(connection);
+        Ok(ChildClient { state })
+    }
+}
+
+impl fmt::Debug for ChildProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildProcess")
+            .field("command", &self.command)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: ChildClient
+// ===
+
+/// A supervised child process and the client connected to it.
+///
+/// Created by `ChildProcess::spawn()`. The client is replaced when the process
+/// is restarted, so fetch it with `client()` for each call rather than
+/// keeping it. Cloning a `ChildClient` shares the same process.
+#[derive(Clone)]
+pub struct ChildClient {
+    state: Arc<ChildState>,
+}
+
+/// The state shared by a `ChildClient` and the transports it has spawned.
+struct ChildState {
+    command: Mutex<Command>,
+    codec: Box<CodecFactory>,
+    restart_policy: Box<RestartPolicy>,
+    on_restart: Box<RestartHook>,
+    slot: Mutex<Slot>,
+    restarts: AtomicUsize,
+}
+
+/// The current process, and whether it has been stopped on purpose.
+#[derive(Default)]
+struct Slot {
+    connection: Option<Connection>,
+    stopped: bool,
+}
+
+/// A running process and its client.
+#[derive(Clone)]
+struct Connection {
+    client: RpcClient,
+    transport: Arc<ChildTransport>,
+}
+
+impl ChildClient {
+    /// Returns the client connected to the current process.
+    ///
+    /// After the process has exited without being restarted, the client is
+    /// closed and calls fail with `ClientError::ProcessExited`.
+    pub fn client(&self) -> RpcClient {
+        self.state.current().client
+    }
+
+    /// Returns the transport to the current process, for its ID, exit status
+    /// and stderr output.
+    pub fn transport(&self) -> Arc<ChildTransport> {
+        self.state.current().transport
+    }
+
+    /// Returns the number of times the process has been restarted.
+    pub fn restarts(&self) -> usize {
+        self.state.restarts.load(Ordering::SeqCst)
+    }
+
+    /// Kills the current process and starts a new one.
+    ///
+    /// Calls pending on the old process fail with `ClientError::ProcessExited`.
+    /// The restart hook runs with the new client, and a stopped process is
+    /// started again.
+    ///
+    /// # Returns
+    ///
+    /// The client connected to the new process.
+    pub fn restart(&self) -> io::Result<RpcClient> {
+        let connection = self.state.connect()?;
+        let old = {
+            let mut slot = self.state.slot.lock().unwrap();
+            slot.stopped = false;
+            slot.connection.replace(connection.clone())
+        };
+        self.state.restarts.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(old) = old {
+            old.transport.kill()?;
+        }
+        (self.state.on_restart)(&connection.client);
+        Ok(connection.client)
+    }
+
+    /// Shuts the process down gracefully.
+    ///
+    /// The client is closed, which closes the process's stdin, and this blocks
+    /// until the process exits. The process is not restarted.
+    ///
+    /// # Returns
+    ///
+    /// The exit status of the process.
+    pub fn close(&self) -> io::Result<ExitStatus> {
+        let current = self.state.stop();
+        current.client.close();
+        current.transport.wait()
+    }
+
+    /// Kills the process without restarting it.
+    ///
+    /// Calls pending on the process fail with `ClientError::ProcessExited`.
+    pub fn kill(&self) -> io::Result<()> {
+        self.state.stop().transport.kill()
+    }
+}
+
+impl fmt::Debug for ChildClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildClient")
+            .field("id", &self.transport().id())
+            .field("restarts", &self.restarts())
+            .finish()
+    }
+}
+
+impl ChildState {
+    /// Returns the current process and client.
+    fn current(&self) -> Connection {
+        self.slot
+            .lock()
+            .unwrap()
+            .connection
+            .clone()
+            .expect("a process is spawned before the state is shared")
+    }
+
+    /// Marks the process as stopped on purpose, so it is not restarted.
+    fn stop(&self) -> Connection {
+        let mut slot = self.slot.lock().unwrap();
+        slot.stopped = true;
+        slot.connection
+            .clone()
+            .expect("a process is spawned before the state is shared")
+    }
+
+    /// Checks if an exited process should be replaced: it must still be the
+    /// current one, and must not have been stopped on purpose.
+    fn is_replaceable(slot: &Slot, transport: &Arc<ChildTransport>) -> bool {
+        !slot.stopped
+            && slot
+                .connection
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(&current.transport, transport))
+    }
+
+    /// Spawns a process and connects a client to it.
+    fn connect(self: &Arc<Self>) -> io::Result<Connection> {
+        let codec = (self.codec)();
+        let transport = Arc::new(ChildTransport::spawn_with_codec(
+            &mut self.command.lock().unwrap(),
+            codec,
+        )?);
+
+        let client = RpcClient::connect(Supervised {
+            transport: transport.clone(),
+            state: Arc::downgrade(self),
+        });
+        Ok(Connection { client, transport })
+    }
+
+    /// Handles the exit of a process, restarting it if the policy allows.
+    ///
+    /// Runs on the reader thread of the exited process's client, before its
+    /// pending calls fail, so a caller retrying after the failure already
+    /// finds the new client.
+    fn exited(self: &Arc<Self>, transport: &Arc<ChildTransport>, status: ExitStatus) {
+        if !Self::is_replaceable(&self.slot.lock().unwrap(), transport) {
+            return;
+        }
+        if !(self.restart_policy)(status, self.restarts.load(Ordering::SeqCst)) {
+            return;
+        }
+
+        // If the process cannot be spawned again, the old client stays
+        // current and closed; `ChildClient::restart()` can try again later.
+        let Ok(connection) = self.connect() else {
+            return;
+        };
+
+        {
+            let mut slot = self.slot.lock().unwrap();
+            if !Self::is_replaceable(&slot, transport) {
+                // Stopped or restarted while the new process was spawning
+                drop(slot);
+                let _ = connection.transport.kill();
+                return;
+            }
+            slot.connection = Some(connection.clone());
+        }
+
+        self.restarts.fetch_add(1, Ordering::SeqCst);
+        (self.on_restart)(&connection.client);
+    }
+}
+
+/// A child transport that reports its process's exit to the supervisor.
+struct Supervised {
+    transport: Arc<ChildTransport>,
+    state: Weak<ChildState>,
+}
+
+impl Transport for Supervised {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        self.transport.send(message)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        let result = self.transport.receive();
+        if let Err(TransportError::ProcessExited(status)) = &result
+            && let Some(state) = self.state.upgrade()
+        {
+            state.exited(&self.transport, *status);
+        }
+        result
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        self.transport.close()
+    }
+}
+
+// ===========================================================================
+// TESTS: ChildTransport
+// ===
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ClientError;
+    use serde_json::{Value as JsonValue, json};
+
+    /// A shell script standing in for a plugin process.
+    fn script(source: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(source);
+        command
+    }
+
+    const PONG: &str = r#"echo '{"jsonrpc":"2.0","result":"pong","id":1}'"#;
+
+    #[test]
+    fn test_exit_fails_pending_calls() {
+        let mut command = script(&format!(
+            "read line; {}; echo 'fatal: out of cheese' >&2; read line; exit 3",
+            PONG
+        ));
+        command.stderr(Stdio::piped());
+
+        let transport = Arc::new(ChildTransport::spawn(&mut command).unwrap());
+        let client = RpcClient::connect(transport.clone());
+
+        assert_eq!(client.call("ping", JsonValue::Null).wait().unwrap(), "pong");
+        match client.call("ping", JsonValue::Null).wait() {
+            Err(ClientError::ProcessExited(status)) => assert_eq!(status.code(), Some(3)),
+            other => panic!("expected the process to exit, got {:?}", other),
+        }
+
+        // Later calls fail the same way
+        assert!(matches!(
+            client.notify("ping", JsonValue::Null),
+            Err(ClientError::ProcessExited(_))
+        ));
+        assert_eq!(transport.stderr_output(), "fatal: out of cheese\n");
+        assert_eq!(transport.try_wait().unwrap().unwrap().code(), Some(3));
+    }
+
+    #[test]
+    fn test_spawn_failure() {
+        let error = ChildTransport::spawn(&mut Command::new("/nonexistent/plugin")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    // ---------------------------------------------------------------------------
+    // Supervision Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_restart_policy_and_hook() {
+        let hooks = Arc::new(AtomicUsize::new(0));
+        let counter = hooks.clone();
+
+        let mut process =
+            ChildProcess::new(script(&format!("read line; {}; read line; exit 1", PONG)));
+        process
+            .set_restart_policy(|status, restarts| status.code() == Some(1) && restarts < 1)
+            .set_on_restart(move |client| {
+                assert!(!client.is_closed());
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        let child = process.spawn().unwrap();
+
+        let first = child.client();
+        assert_eq!(first.call("ping", json!([])).wait().unwrap(), "pong");
+        assert!(matches!(
+            first.call("ping", json!([])).wait(),
+            Err(ClientError::ProcessExited(_))
+        ));
+
+        // The process was restarted before the pending call failed
+        assert_eq!(child.restarts(), 1);
+        assert_eq!(hooks.load(Ordering::SeqCst), 1);
+        let second = child.client();
+        assert!(!second.is_closed());
+        assert_eq!(second.call("ping", json!([])).wait().unwrap(), "pong");
+
+        // The policy allows only one restart
+        assert!(matches!(
+            second.call("ping", json!([])).wait(),
+            Err(ClientError::ProcessExited(_))
+        ));
+        assert_eq!(child.restarts(), 1);
+        assert!(child.client().is_closed());
+    }
+
+    #[test]
+    fn test_close_and_manual_restart() {
+        let mut process = ChildProcess::new(script("while read line; do :; done; exit 0"));
+        process.set_restart_policy(|_, _| true);
+        let child = process.spawn().unwrap();
+
+        // Closing stdin shuts the process down, without restarting it
+        let first = child.transport().id();
+        assert!(child.close().unwrap().success());
+        assert_eq!(child.restarts(), 0);
+
+        // A manual restart starts a new process
+        let client = child.restart().unwrap();
+        assert!(!client.is_closed());
+        assert_ne!(child.transport().id(), first);
+        assert_eq!(child.restarts(), 1);
+
+        child.kill().unwrap();
+        assert!(!child.transport().wait().unwrap().success());
+    }
+}
@@ -1,10 +1,15 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Error as JsonError;
+use crate::typed::{self, TypeMismatch};
+use crate::validation::{self, ValidationError};
+use crate::{IdGenerator, RpcError, RpcId, SequentialIdGenerator};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value as JsonValue;
+use serde_json::json;
 use std::fmt;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::str::FromStr;
 
-static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// The process-wide generator used by `RpcRequest::new()`.
+static DEFAULT_GENERATOR: SequentialIdGenerator = SequentialIdGenerator::new();
 
 /// Checks if a JSON value is null.
 ///
@@ -23,19 +28,50 @@ fn is_null(v: &JsonValue) -> bool {
     v.is_null()
 }
 
+/// Deserializes a present `id` member, including an explicit null.
+///
+/// Serde maps both a missing field and a null value to `None` for `Option`
+/// fields. JSON-RPC 2.0 treats these differently: a missing `id` marks a
+/// notification while `"id": null` is a (discouraged) request with a null ID.
+/// This helper is only invoked when the member is present, so it always
+/// returns `Some`.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer positioned at the `id` value
+///
+/// # Returns
+///
+/// The parsed ID wrapped in `Some`, or a deserialization error.
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<RpcId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    RpcId::deserialize(deserializer).map(Some)
+}
+
 // ===========================================================================
 // STRUCT: RpcRequest
 // ===
 
+/// Represents a JSON-RPC 2.0 request or notification message.
+///
+/// A request carries an `id` and expects a response. A notification is a
+/// request without an `id`; the receiver must not reply to it.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RpcRequest {
     jsonrpc: String,
     method: String,
 
-    #[serde(skip_serializing_if = "is_null")]
+    #[serde(default, skip_serializing_if = "is_null")]
     params: JsonValue,
 
-    id: u64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_id"
+    )]
+    id: Option<RpcId>,
 }
 
 impl RpcRequest {
@@ -45,8 +81,11 @@ impl RpcRequest {
     /// - `jsonrpc`: "2.0" (JSON-RPC protocol version)
     /// - `method`: Empty string (to be set later)
     /// - `params`: JSON null value
-    /// - `id`: Auto-incremented unique identifier
-    ///    /// # Examples
+    /// - `id`: Auto-incremented identifier from a process-wide counter
+    ///
+    /// Use `with_generator()` to draw the ID from a generator of your own.
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -54,11 +93,56 @@ impl RpcRequest {
     /// assert_eq!(request.method(), "");
     /// ```
     pub fn new() -> Self {
+        Self::with_generator(&DEFAULT_GENERATOR)
+    }
+
+    /// Creates a new RPC request whose ID is drawn from the given generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `generator` - The generator providing the request ID
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcRequest, UuidIdGenerator};
+    /// let request = RpcRequest::with_generator(&UuidIdGenerator);
+    /// assert!(request.id().unwrap().as_str().is_some());
+    /// ```
+    pub fn with_generator(generator: &dyn IdGenerator) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             method: String::new(),
             params: JsonValue::Null,
-            id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+            id: Some(generator.next_id()),
+        }
+    }
+
+    /// Creates a new RPC notification.
+    ///
+    /// A notification is a request without an `id`. It is serialized without an
+    /// `id` member and the receiver must not send a response for it.
+    ///
+    /// The notification is initialized with:
+    /// - `jsonrpc`: "2.0" (JSON-RPC protocol version)
+    /// - `method`: Empty string (to be set later)
+    /// - `params`: JSON null value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::request::RpcRequest;
+    /// let mut notification = RpcRequest::notification();
+    /// notification.set_method("log_event".to_string());
+    /// assert!(notification.is_notification());
+    /// assert!(notification.to_json().get("id").is_none());
+    /// ```
+    pub fn notification() -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: String::new(),
+            params: JsonValue::Null,
+            id: None,
         }
     }
 
@@ -70,7 +154,8 @@ impl RpcRequest {
     /// # Returns
     ///
     /// A `JsonValue` representation of the request, or `JsonValue::Null` on error.
-    ///    /// # Examples
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -79,13 +164,16 @@ impl RpcRequest {
     /// let json = request.to_json();
     /// ```
     pub fn to_json(&self) -> JsonValue {
-        serde_json::to_value(self).unwrap_or_else(|_| JsonValue::Null)
+        serde_json::to_value(self).unwrap_or(JsonValue::Null)
     }
 
     /// Creates an RPC request from a JSON value.
     ///
-    /// Deserializes a JSON value into an RpcRequest struct. The JSON must contain
-    /// the required fields: `jsonrpc`, `method`, `params`, and `id`.
+    /// Validates and deserializes a JSON value into an RpcRequest struct. The JSON
+    /// must be an object with `jsonrpc` set to exactly "2.0" and a string `method`.
+    /// The `params` field is optional but must be an array or an object when present
+    /// (null is treated as omitted). The `id` must be a number, a string or null,
+    /// and a message without an `id` field is parsed as a notification.
     ///
     /// # Arguments
     ///
@@ -94,8 +182,10 @@ impl RpcRequest {
     /// # Returns
     ///
     /// * `Ok(RpcRequest)` - Successfully deserialized request
-    /// * `Err(JsonError)` - Deserialization failed
-    ///    /// # Examples
+    /// * `Err(ValidationError)` - The JSON is not a valid request; the error
+    ///   carries the JSON-RPC error code to reply with
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -107,29 +197,82 @@ impl RpcRequest {
     /// });
     /// let request = RpcRequest::from_json(json).unwrap();
     /// ```
-    pub fn from_json(json: JsonValue) -> Result<Self, JsonError> {
-        serde_json::from_value(json)
+    pub fn from_json(json: JsonValue) -> Result<Self, ValidationError> {
+        validation::validate_request(&json)?;
+        serde_json::from_value(json).map_err(validation::invalid_message)
     }
 
     /// Returns the unique identifier of this RPC request.
     ///
-    /// Each request is assigned a unique ID when created using `new()`.
+    /// Each request is assigned a unique numeric ID when created using `new()`.
     /// This ID is used to match requests with their corresponding responses.
+    /// Notifications have no ID.
     ///
     /// # Returns
     ///
-    /// The request's unique identifier as a `u64`.
-    ///    /// # Examples
+    /// `Some(&RpcId)` for a request, or `None` for a notification.
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
     /// let request = RpcRequest::new();
-    /// let id = request.id();
-    /// assert!(id > 0);
+    /// let id = request.id().unwrap();
+    /// assert!(id.as_u64().unwrap() > 0);
+    ///
+    /// assert!(RpcRequest::notification().id().is_none());
     /// ```
     // Accessor for id
-    pub fn id(&self) -> u64 {
-        self.id
+    pub fn id(&self) -> Option<&RpcId> {
+        self.id.as_ref()
+    }
+
+    /// Sets the identifier of this RPC request.
+    ///
+    /// Replaces the auto-generated ID, which is useful when talking to peers
+    /// that use string identifiers such as UUIDs. This function uses the builder
+    /// pattern, returning a mutable reference to self for method chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The new identifier (an integer, a string or an `RpcId`)
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcId, RpcRequest};
+    /// let mut request = RpcRequest::new();
+    /// request.set_id("req-7f3a").set_method("get_balance".to_string());
+    /// assert_eq!(request.id(), Some(&RpcId::from("req-7f3a")));
+    /// ```
+    // Mutator for id
+    pub fn set_id(&mut self, id: impl Into<RpcId>) -> &mut Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Checks if this message is a notification.
+    ///
+    /// Notifications are requests without an `id`. Handlers must not generate
+    /// a response for them.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the message has no `id`, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::request::RpcRequest;
+    /// assert!(RpcRequest::notification().is_notification());
+    /// assert!(!RpcRequest::new().is_notification());
+    /// ```
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
     }
 
     /// Returns the method name of this RPC request.
@@ -139,7 +282,8 @@ impl RpcRequest {
     /// # Returns
     ///
     /// A string slice containing the method name.
-    ///    /// # Examples
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -165,7 +309,8 @@ impl RpcRequest {
     /// # Returns
     ///
     /// A mutable reference to self, allowing for method chaining.
-    ///    /// # Examples
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -187,7 +332,8 @@ impl RpcRequest {
     /// # Returns
     ///
     /// A reference to the JSON value containing the parameters.
-    ///    /// # Examples
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -215,7 +361,8 @@ impl RpcRequest {
     /// # Returns
     ///
     /// A mutable reference to self, allowing for method chaining.
-    ///    /// # Examples
+    ///
+    /// # Examples
     ///
     /// ```
     /// use ink_rpc::request::RpcRequest;
@@ -229,6 +376,143 @@ impl RpcRequest {
         self.params = params;
         self
     }
+
+    /// Sets the parameters for this RPC request from a serializable value.
+    ///
+    /// The value is serialized to JSON, so domain structs can be passed directly.
+    /// It must serialize to an object, an array or null, since JSON-RPC 2.0 only
+    /// allows structured parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The value containing the method parameters
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut Self)` - The parameters were set, allowing for method chaining
+    /// * `Err(RpcError)` - An "Invalid params" error naming the method and field
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::request::RpcRequest;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Transfer {
+    ///     to: String,
+    ///     amount: u64,
+    /// }
+    ///
+    /// let mut request = RpcRequest::new();
+    /// request
+    ///     .set_method("transfer".to_string())
+    ///     .set_params_typed(&Transfer { to: "acc_1".to_string(), amount: 100 })
+    ///     .unwrap();
+    /// assert_eq!(request.params()["amount"], 100);
+    /// ```
+    pub fn set_params_typed<T: Serialize>(&mut self, params: &T) -> Result<&mut Self, RpcError> {
+        let params = typed::to_value(params).map_err(|mismatch| self.invalid_params(mismatch))?;
+
+        match params {
+            JsonValue::Null | JsonValue::Array(_) | JsonValue::Object(_) => {
+                self.params = params;
+                Ok(self)
+            }
+            _ => Err(self.invalid_params(TypeMismatch {
+                field: ".".to_string(),
+                reason: "params must serialize to an array or an object".to_string(),
+            })),
+        }
+    }
+
+    /// Converts the parameters of this RPC request into a typed value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - The deserialized parameters
+    /// * `Err(RpcError)` - An "Invalid params" (-32602) error naming the method and
+    ///   the offending field, ready to be sent back to the caller
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::request::RpcRequest;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Transfer {
+    ///     to: String,
+    ///     amount: u64,
+    /// }
+    ///
+    /// let mut request = RpcRequest::new();
+    /// request
+    ///     .set_method("transfer".to_string())
+    ///     .set_params(serde_json::json!({"to": "acc_1", "amount": "lots"}));
+    ///
+    /// let error = request.params_as::<Transfer>().unwrap_err();
+    /// assert_eq!(error.code(), -32602);
+    /// assert_eq!(error.data().unwrap()["field"], "amount");
+    /// ```
+    pub fn params_as<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        typed::from_value(&self.params).map_err(|mismatch| self.invalid_params(mismatch))
+    }
+
+    /// Builds the "Invalid params" error for a parameter type mismatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `mismatch` - The offending field and the reason it did not match
+    ///
+    /// # Returns
+    ///
+    /// An `RpcError` whose message and data name the method and field.
+    fn invalid_params(&self, mismatch: TypeMismatch) -> RpcError {
+        RpcError::new(
+            RpcError::INVALID_PARAMS,
+            format!(
+                "Invalid params for method `{}` at `{}`: {}",
+                self.method, mismatch.field, mismatch.reason
+            ),
+        )
+        .with_data(json!({
+            "method": self.method,
+            "field": mismatch.field,
+            "reason": mismatch.reason,
+        }))
+    }
+}
+
+impl Default for RpcRequest {
+    /// Creates a new RPC request with default values.
+    ///
+    /// Equivalent to calling `RpcRequest::new()`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for RpcRequest {
+    type Err = ValidationError;
+
+    /// Parses and validates an RPC request from JSON text.
+    ///
+    /// Invalid JSON is reported as a `ValidationErrorKind::Parse` error, which
+    /// maps to the "Parse error" (-32700) code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcRequest;
+    /// let request: RpcRequest = r#"{"jsonrpc": "2.0", "method": "ping", "id": 1}"#
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(request.method(), "ping");
+    /// ```
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_json(validation::parse_json(text)?)
+    }
 }
 
 impl fmt::Debug for RpcRequest {
@@ -300,6 +584,8 @@ impl fmt::Display for RpcRequest {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ValidationErrorKind;
+    use serde::Deserialize;
 
     // ---------------------------------------------------------------------------
     // Constructor and Core Functionality Tests
@@ -316,8 +602,67 @@ mod tests {
 
         // Test unique ID generation
         assert_ne!(request1.id(), request2.id());
-        assert!(request1.id() > 0);
-        assert!(request2.id() > 0);
+        assert!(request1.id().unwrap().as_u64().unwrap() > 0);
+        assert!(request2.id().unwrap().as_u64().unwrap() > 0);
+        assert!(!request1.is_notification());
+    }
+
+    #[test]
+    fn test_with_generator() {
+        let generator = crate::SeededIdGenerator::new(1);
+        let replay = crate::SeededIdGenerator::new(1);
+
+        let request = RpcRequest::with_generator(&generator);
+        assert_eq!(request.id(), Some(&replay.next_id()));
+        assert!(!request.is_notification());
+
+        // A per-client counter is not affected by the process-wide one
+        let generator = SequentialIdGenerator::new();
+        RpcRequest::new();
+        assert_eq!(
+            RpcRequest::with_generator(&generator).id(),
+            Some(&RpcId::Number(1))
+        );
+    }
+
+    #[test]
+    fn test_new_notification_initialization() {
+        let notification = RpcRequest::notification();
+
+        assert_eq!(notification.method(), "");
+        assert_eq!(notification.params(), &JsonValue::Null);
+        assert_eq!(notification.id(), None);
+        assert!(notification.is_notification());
+    }
+
+    // ---------------------------------------------------------------------------
+    // Id Field Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_id_operations() {
+        let mut request = RpcRequest::new();
+
+        // Test string ids
+        request.set_id("5f0c6a2e-uuid");
+        assert_eq!(request.id(), Some(&RpcId::from("5f0c6a2e-uuid")));
+        assert_eq!(request.to_json()["id"], "5f0c6a2e-uuid");
+
+        // Test null ids
+        request.set_id(RpcId::Null);
+        assert!(request.id().unwrap().is_null());
+        assert!(!request.is_notification());
+        assert_eq!(request.to_json()["id"], JsonValue::Null);
+
+        // Test numeric ids and method chaining
+        request.set_id(77).set_method("ping".to_string());
+        assert_eq!(request.id(), Some(&RpcId::Number(77)));
+        assert_eq!(request.method(), "ping");
+
+        // Test setting an id turns a notification into a request
+        let mut notification = RpcRequest::notification();
+        notification.set_id(1);
+        assert!(!notification.is_notification());
     }
 
     // ---------------------------------------------------------------------------
@@ -409,6 +754,82 @@ mod tests {
         assert_eq!(params["options"][0], "fast");
     }
 
+    // ---------------------------------------------------------------------------
+    // Typed Params Tests
+    // ---------------------------------------------------------------------------
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Transfer {
+        from: String,
+        to: String,
+        amount: u64,
+    }
+
+    #[test]
+    fn test_typed_params_roundtrip() {
+        let transfer = Transfer {
+            from: "addr1".to_string(),
+            to: "addr2".to_string(),
+            amount: 100,
+        };
+
+        let mut request = RpcRequest::new();
+        request
+            .set_method("transfer".to_string())
+            .set_params_typed(&transfer)
+            .expect("Failed to set params");
+        assert_eq!(request.params()["amount"], 100);
+
+        let params: Transfer = request.params_as().expect("Failed to convert params");
+        assert_eq!(params, transfer);
+
+        // Positional params work with tuples
+        request.set_params_typed(&(1, "two")).unwrap();
+        assert_eq!(request.params(), &serde_json::json!([1, "two"]));
+        assert_eq!(
+            request.params_as::<(u8, String)>().unwrap(),
+            (1, "two".to_string())
+        );
+    }
+
+    #[test]
+    fn test_typed_params_mismatch() {
+        let mut request = RpcRequest::new();
+        request
+            .set_method("transfer".to_string())
+            .set_params(serde_json::json!({"from": "a", "to": "b", "amount": "lots"}));
+
+        let error = request.params_as::<Transfer>().unwrap_err();
+        assert_eq!(error.code(), RpcError::INVALID_PARAMS);
+        assert!(error.message().contains("`transfer`"));
+        assert!(error.message().contains("`amount`"));
+        let data = error.data().unwrap();
+        assert_eq!(data["method"], "transfer");
+        assert_eq!(data["field"], "amount");
+        assert!(data["reason"].as_str().unwrap().contains("invalid type"));
+
+        // Missing fields are reported as well
+        request.set_params(serde_json::json!({"from": "a"}));
+        let error = request.params_as::<Transfer>().unwrap_err();
+        assert!(
+            error.data().unwrap()["reason"]
+                .as_str()
+                .unwrap()
+                .contains("`to`")
+        );
+    }
+
+    #[test]
+    fn test_typed_params_must_be_structured() {
+        let mut request = RpcRequest::new();
+        request.set_method("echo".to_string());
+
+        let error = request.set_params_typed(&42).unwrap_err();
+        assert_eq!(error.code(), RpcError::INVALID_PARAMS);
+        assert_eq!(error.data().unwrap()["method"], "echo");
+        assert_eq!(request.params(), &JsonValue::Null);
+    }
+
     // ---------------------------------------------------------------------------
     // JSON Serialization Tests
     // ---------------------------------------------------------------------------
@@ -426,7 +847,7 @@ mod tests {
         assert_eq!(json["jsonrpc"], "2.0");
         assert_eq!(json["method"], "test_method");
         assert_eq!(json["params"]["key"], "value");
-        assert_eq!(json["id"], request.id());
+        assert_eq!(json["id"], request.id().unwrap().as_u64().unwrap());
 
         // Test roundtrip
         let deserialized = RpcRequest::from_json(json).expect("Failed to deserialize");
@@ -448,7 +869,18 @@ mod tests {
         let request = RpcRequest::from_json(valid_json).expect("Failed to deserialize");
         assert_eq!(request.method(), "test_method");
         assert_eq!(request.params()["account"], "123");
-        assert_eq!(request.id(), 42);
+        assert_eq!(request.id(), Some(&RpcId::Number(42)));
+
+        // Test string id roundtrip
+        let string_id_json = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "test_method",
+            "params": null,
+            "id": "abc-123"
+        });
+        let request = RpcRequest::from_json(string_id_json).expect("Failed to deserialize");
+        assert_eq!(request.id(), Some(&RpcId::from("abc-123")));
+        assert_eq!(request.to_json()["id"], "abc-123");
 
         // Test invalid JSON
         let invalid_json = serde_json::json!({"invalid": "structure"});
@@ -456,6 +888,86 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_json_validation() {
+        // Test wrong protocol version
+        let error = RpcRequest::from_json(serde_json::json!({
+            "jsonrpc": "1.0",
+            "method": "test_method",
+            "id": 1
+        }))
+        .unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+        assert_eq!(error.id(), &RpcId::Number(1));
+
+        // Test params that are not structured
+        let error = RpcRequest::from_json(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "test_method",
+            "params": "not structured",
+            "id": "p"
+        }))
+        .unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidParams);
+        assert_eq!(error.to_response().id(), &RpcId::from("p"));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let request: RpcRequest =
+            r#"{"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 3}"#
+                .parse()
+                .expect("Failed to parse");
+        assert_eq!(request.method(), "sum");
+        assert_eq!(request.params(), &serde_json::json!([1, 2]));
+
+        let error = "{\"jsonrpc\": ".parse::<RpcRequest>().unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::Parse);
+        assert_eq!(error.code(), -32700);
+    }
+
+    #[test]
+    fn test_notification_serialization() {
+        let mut notification = RpcRequest::notification();
+        notification
+            .set_method("progress".to_string())
+            .set_params(serde_json::json!({"percent": 50}));
+
+        // Notifications are serialized without an id member
+        let json = notification.to_json();
+        assert_eq!(json["method"], "progress");
+        assert!(json.get("id").is_none());
+
+        // Test roundtrip
+        let deserialized = RpcRequest::from_json(json).expect("Failed to deserialize");
+        assert!(deserialized.is_notification());
+        assert_eq!(deserialized.params()["percent"], 50);
+    }
+
+    #[test]
+    fn test_notification_deserialization() {
+        // A message without an id is a notification, and params may be omitted
+        let notification_json = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "exit"
+        });
+        let notification = RpcRequest::from_json(notification_json).expect("Failed to deserialize");
+        assert!(notification.is_notification());
+        assert_eq!(notification.method(), "exit");
+        assert_eq!(notification.params(), &JsonValue::Null);
+
+        // An explicit null id is a request, not a notification
+        let null_id_json = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "ping",
+            "id": null
+        });
+        let request = RpcRequest::from_json(null_id_json).expect("Failed to deserialize");
+        assert!(!request.is_notification());
+        assert_eq!(request.id(), Some(&RpcId::Null));
+        assert_eq!(request.to_json()["id"], JsonValue::Null);
+    }
+
     // ---------------------------------------------------------------------------
     // Display Trait Tests
     // ---------------------------------------------------------------------------
@@ -467,7 +979,7 @@ mod tests {
             .set_method("get_balance".to_string())
             .set_params(serde_json::json!({"account": "test"}));
 
-        let display_string = format!("{}", request);
+        let display_string = format!("{:?}", request);
 
         // Verify JSON structure and content
         assert!(display_string.starts_with('{'));
@@ -475,7 +987,7 @@ mod tests {
         assert!(display_string.contains("\"jsonrpc\": \"2.0\""));
         assert!(display_string.contains("\"method\": \"get_balance\""));
         assert!(display_string.contains("\"account\": \"test\""));
-        assert!(display_string.contains(&format!("\"id\": {}", request.id())));
+        assert!(display_string.contains(&format!("\"id\": {}", request.id().unwrap())));
 
         // Verify it's valid JSON by parsing it back
         let parsed: serde_json::Value =
@@ -1,7 +1,50 @@
-use serde::{Deserialize, Serialize};
+use crate::typed;
+use crate::validation::{self, ValidationError};
+use crate::{RpcError, RpcId, RpcRequest};
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use serde_json::json;
 use std::fmt;
+use std::str::FromStr;
+
+/// Deserializes a present `result` member, including an explicit null.
+///
+/// Serde maps both a missing field and a null value to `None` for `Option`
+/// fields, but `"result": null` is a valid successful response. This helper
+/// is only invoked when the member is present, so it always returns `Some`.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer positioned at the `result` value
+///
+/// # Returns
+///
+/// The parsed result wrapped in `Some`, or a deserialization error.
+fn deserialize_result<'de, D>(deserializer: D) -> Result<Option<JsonValue>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    JsonValue::deserialize(deserializer).map(Some)
+}
+
+// ===========================================================================
+// ENUM: RpcOutcome
+// ===
+
+/// The outcome carried by a JSON-RPC 2.0 response.
+///
+/// The specification requires a response to contain exactly one of `result`
+/// or `error`, so the two are modelled as the variants of a single enum.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RpcOutcome {
+    /// The request succeeded; serialized as the `result` member.
+    Success(JsonValue),
+
+    /// The request failed; serialized as the `error` member.
+    Failure(RpcError),
+}
 
 // ===========================================================================
 // STRUCT: RpcResponse
@@ -9,51 +52,129 @@ use std::fmt;
 
 /// Represents a JSON-RPC 2.0 response message.
 ///
-/// This struct wraps a JSON value that contains the response data according to the
-/// JSON-RPC 2.0 specification. It provides methods to create, manipulate, and access
-/// the response data including ID, result, and error information.
-#[derive(Serialize, Deserialize, Clone)]
+/// A response carries the ID of the request it answers and either a successful
+/// result or an error, never both. It provides methods to create, manipulate,
+/// and access the response data including ID, result, and error information.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "RawRpcResponse")]
 pub struct RpcResponse {
-    value: JsonValue,
+    id: RpcId,
+    outcome: RpcOutcome,
 }
 
 impl RpcResponse {
     /// Creates a new RPC response with the specified ID.
     ///
-    /// This creates a minimal JSON-RPC 2.0 response containing only the protocol version
-    /// and ID. The result field should be set later using `set_result()`.
+    /// This creates a successful JSON-RPC 2.0 response with a null result. The
+    /// outcome should be set later using `set_result()` or `set_error()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The request ID that this response corresponds to (an integer, a string or an `RpcId`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcId, RpcRequest, RpcResponse};
+    /// let response = RpcResponse::new(123);
+    /// assert_eq!(response.id(), &RpcId::Number(123));
+    ///
+    /// let request = RpcRequest::new();
+    /// let response = RpcResponse::new(request.id().unwrap());
+    /// assert_eq!(Some(response.id()), request.id());
+    /// ```
+    pub fn new(id: impl Into<RpcId>) -> Self {
+        Self::success(id, JsonValue::Null)
+    }
+
+    /// Creates a successful RPC response.
     ///
     /// # Arguments
     ///
     /// * `id` - The request ID that this response corresponds to
+    /// * `result` - The result of the call
     ///
     /// # Examples
     ///
     /// ```
     /// use ink_rpc::RpcResponse;
-    /// let response = RpcResponse::new(123);
-    /// assert_eq!(response.id(), 123);
+    /// use serde_json::json;
+    ///
+    /// let response = RpcResponse::success(1, json!({"balance": 10}));
+    /// assert!(response.is_success());
     /// ```
-    pub fn new(id: u64) -> Self {
-        let value = json!(
-            {
-                "jsonrpc": "2.0",
-                "id": id,
-            }
-        );
+    pub fn success(id: impl Into<RpcId>, result: JsonValue) -> Self {
+        RpcResponse {
+            id: id.into(),
+            outcome: RpcOutcome::Success(result),
+        }
+    }
 
-        RpcResponse { value }
+    /// Creates a failed RPC response.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The request ID that this response corresponds to
+    /// * `error` - The error describing the failure
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcResponse};
+    ///
+    /// let response = RpcResponse::failure(1, RpcError::method_not_found());
+    /// assert!(response.is_error());
+    /// ```
+    pub fn failure(id: impl Into<RpcId>, error: RpcError) -> Self {
+        RpcResponse {
+            id: id.into(),
+            outcome: RpcOutcome::Failure(error),
+        }
+    }
+
+    /// Creates an RPC response that replies to the given request.
+    ///
+    /// The response carries the exact ID of the request. Notifications must not
+    /// be answered, so no response is created for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request being answered
+    ///
+    /// # Returns
+    ///
+    /// `Some(RpcResponse)` for a request, or `None` for a notification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcRequest, RpcResponse};
+    ///
+    /// let request = RpcRequest::new();
+    /// let response = RpcResponse::for_request(&request).unwrap();
+    /// assert_eq!(Some(response.id()), request.id());
+    ///
+    /// let notification = RpcRequest::notification();
+    /// assert!(RpcResponse::for_request(&notification).is_none());
+    /// ```
+    pub fn for_request(request: &RpcRequest) -> Option<Self> {
+        request.id().map(RpcResponse::new)
     }
 
-    /// Creates an RPC response from an existing JSON value.
+    /// Creates an RPC response from a JSON value.
     ///
-    /// This constructor allows creating a response from a pre-built JSON object,
-    /// useful when parsing responses from external sources or when you have
-    /// already constructed the response JSON.
+    /// Validates and deserializes a JSON value into an RpcResponse. The JSON must
+    /// be an object with `jsonrpc` set to exactly "2.0", an `id`, and exactly one
+    /// of `result` or a well-formed `error` object.
     ///
     /// # Arguments
     ///
-    /// * `value` - A JSON value representing the complete RPC response
+    /// * `json` - A JSON value representing the complete RPC response
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RpcResponse)` - Successfully deserialized response
+    /// * `Err(ValidationError)` - The JSON is not a valid response
     ///
     /// # Examples
     ///
@@ -66,55 +187,83 @@ impl RpcResponse {
     ///     "id": 1,
     ///     "result": "success"
     /// });
-    /// let response = RpcResponse::from_json(json_response);
+    /// let response = RpcResponse::from_json(json_response).unwrap();
+    /// assert!(response.is_success());
     /// ```
-    pub fn from_json(value: JsonValue) -> Self {
-        RpcResponse { value }
+    pub fn from_json(json: JsonValue) -> Result<Self, ValidationError> {
+        validation::validate_response(&json)?;
+        serde_json::from_value(json).map_err(validation::invalid_message)
     }
 
-    /// Returns a reference to the underlying JSON value.
-    ///
-    /// This provides direct access to the raw JSON representation of the response,
-    /// allowing for custom manipulation or serialization.
+    /// Converts the RPC response to a JSON value.
     ///
     /// # Returns
     ///
-    /// A reference to the internal `JsonValue`
-    pub fn as_json(&self) -> &JsonValue {
-        &self.value
+    /// A `JsonValue` representation of the response, or `JsonValue::Null` on error.
+    pub fn to_json(&self) -> JsonValue {
+        serde_json::to_value(self).unwrap_or(JsonValue::Null)
     }
 
-    /// Extracts the ID from the response.
+    /// Returns the ID of the request this response corresponds to.
     ///
-    /// Returns the request ID that this response corresponds to. If the ID is not
-    /// present or cannot be parsed as a u64, returns 0 as a fallback.
+    /// The ID is exactly the one sent by the peer.
     ///
     /// # Returns
     ///
-    /// The response ID as a `u64`, or 0 if not found or invalid
+    /// A reference to the response ID.
     ///
     /// # Examples
     ///
     /// ```
-    /// use ink_rpc::RpcResponse;
+    /// use ink_rpc::{RpcId, RpcResponse};
     /// let response = RpcResponse::new(42);
-    /// assert_eq!(response.id(), 42);
+    /// assert_eq!(response.id(), &RpcId::Number(42));
+    ///
+    /// let response = RpcResponse::new("req-42");
+    /// assert_eq!(response.id(), &RpcId::from("req-42"));
     /// ```
-    pub fn id(&self) -> u64 {
-        self.value
-            .get("id")
-            .and_then(JsonValue::as_u64)
-            .unwrap_or(0)
+    pub fn id(&self) -> &RpcId {
+        &self.id
     }
 
-    /// Returns a reference to the result field of the response.
+    /// Returns the outcome of the response.
     ///
-    /// Gets the "result" field from the JSON-RPC response. If no result field
-    /// is present, returns a reference to `JsonValue::Null`.
+    /// # Returns
+    ///
+    /// A reference to the `RpcOutcome`, either a success or a failure.
+    pub fn outcome(&self) -> &RpcOutcome {
+        &self.outcome
+    }
+
+    /// Checks if the response carries a successful result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcResponse;
+    /// assert!(RpcResponse::new(1).is_success());
+    /// ```
+    pub fn is_success(&self) -> bool {
+        matches!(self.outcome, RpcOutcome::Success(_))
+    }
+
+    /// Checks if the response carries an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcResponse};
+    /// assert!(RpcResponse::failure(1, RpcError::internal_error()).is_error());
+    /// ```
+    pub fn is_error(&self) -> bool {
+        matches!(self.outcome, RpcOutcome::Failure(_))
+    }
+
+    /// Returns the result of the response.
     ///
     /// # Returns
     ///
-    /// A reference to the result `JsonValue`, or `&JsonValue::Null` if not present
+    /// `Some(&JsonValue)` for a successful response, `None` for an error response
     ///
     /// # Examples
     ///
@@ -124,16 +273,19 @@ impl RpcResponse {
     ///
     /// let mut response = RpcResponse::new(1);
     /// response.set_result(json!("success"));
-    /// assert_eq!(response.result(), &json!("success"));
+    /// assert_eq!(response.result(), Some(&json!("success")));
     /// ```
-    pub fn result(&self) -> &JsonValue {
-        self.value.get("result").unwrap_or(&JsonValue::Null)
+    pub fn result(&self) -> Option<&JsonValue> {
+        match &self.outcome {
+            RpcOutcome::Success(result) => Some(result),
+            RpcOutcome::Failure(_) => None,
+        }
     }
 
-    /// Sets the result field of the response.
+    /// Sets the result of the response.
     ///
-    /// Updates the "result" field in the JSON-RPC response with the provided value.
-    /// This is used to populate the response with the actual result data.
+    /// Turns the response into a successful response carrying the provided
+    /// value. Any previously set error is replaced.
     ///
     /// # Arguments
     ///
@@ -149,60 +301,228 @@ impl RpcResponse {
     /// response.set_result(json!({"status": "ok", "data": [1, 2, 3]}));
     /// ```
     pub fn set_result(&mut self, result: JsonValue) {
-        self.value["result"] = result;
+        self.outcome = RpcOutcome::Success(result);
     }
 
-    /// Returns a reference to the error field of the response.
+    /// Sets the result of the response from a serializable value.
+    ///
+    /// # Arguments
     ///
-    /// Gets the "error" field from the JSON-RPC response. If no error field
-    /// is present, returns a reference to `JsonValue::Null`.
+    /// * `result` - The value to serialize into the result
     ///
     /// # Returns
     ///
-    /// A reference to the error `JsonValue`, or `&JsonValue::Null` if not present
+    /// * `Ok(())` - The response is now a success carrying the value
+    /// * `Err(RpcError)` - An "Internal error" naming the field that failed to serialize
     ///
     /// # Examples
     ///
     /// ```
     /// use ink_rpc::RpcResponse;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Balance {
+    ///     amount: u64,
+    /// }
+    ///
+    /// let mut response = RpcResponse::new(1);
+    /// response.set_result_typed(&Balance { amount: 10 }).unwrap();
+    /// assert_eq!(response.result().unwrap()["amount"], 10);
+    /// ```
+    pub fn set_result_typed<T: Serialize>(&mut self, result: &T) -> Result<(), RpcError> {
+        let result = typed::to_value(result).map_err(|mismatch| {
+            RpcError::new(
+                RpcError::INTERNAL_ERROR,
+                format!(
+                    "Invalid result at `{}`: {}",
+                    mismatch.field, mismatch.reason
+                ),
+            )
+            .with_data(json!({"field": mismatch.field, "reason": mismatch.reason}))
+        })?;
+        self.set_result(result);
+        Ok(())
+    }
+
+    /// Converts the result of the response into a typed value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` - The deserialized result
+    /// * `Err(RpcError)` - The error carried by an error response, or an
+    ///   "Internal error" naming the response ID and the field that did not match
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcResponse;
+    /// use serde::Deserialize;
     /// use serde_json::json;
     ///
+    /// #[derive(Deserialize)]
+    /// struct Balance {
+    ///     amount: u64,
+    /// }
+    ///
+    /// let response = RpcResponse::success(1, json!({"amount": 10}));
+    /// let balance: Balance = response.result_as().unwrap();
+    /// assert_eq!(balance.amount, 10);
+    /// ```
+    pub fn result_as<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        match &self.outcome {
+            RpcOutcome::Success(result) => typed::from_value(result).map_err(|mismatch| {
+                RpcError::new(
+                    RpcError::INTERNAL_ERROR,
+                    format!(
+                        "Invalid result for request {} at `{}`: {}",
+                        self.id, mismatch.field, mismatch.reason
+                    ),
+                )
+                .with_data(json!({
+                    "id": self.id,
+                    "field": mismatch.field,
+                    "reason": mismatch.reason,
+                }))
+            }),
+            RpcOutcome::Failure(error) => Err(error.clone()),
+        }
+    }
+
+    /// Returns the error of the response.
+    ///
+    /// # Returns
+    ///
+    /// `Some(&RpcError)` for an error response, `None` for a successful response
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcResponse};
+    ///
     /// let mut response = RpcResponse::new(1);
-    /// response.set_error(json!({"code": -1, "message": "Invalid request"}));
-    /// assert_eq!(response.error(), &json!({"code": -1, "message": "Invalid request"}));
+    /// response.set_error(RpcError::invalid_request());
+    /// assert_eq!(response.error(), Some(&RpcError::invalid_request()));
     /// ```
-    pub fn error(&self) -> &JsonValue {
-        self.value.get("error").unwrap_or(&JsonValue::Null)
+    pub fn error(&self) -> Option<&RpcError> {
+        match &self.outcome {
+            RpcOutcome::Success(_) => None,
+            RpcOutcome::Failure(error) => Some(error),
+        }
     }
 
-    /// Sets the error field of the response.
+    /// Sets the error of the response.
     ///
-    /// Updates the "error" field in the JSON-RPC response with the provided value.
-    /// This is used to populate the response with error information when a request fails.
+    /// Turns the response into an error response carrying the provided error.
+    /// Any previously set result is replaced.
     ///
     /// # Arguments
     ///
-    /// * `error` - The error value to set in the response
+    /// * `error` - The error to set in the response
     ///
     /// # Examples
     ///
     /// ```
-    /// use ink_rpc::RpcResponse;
+    /// use ink_rpc::{RpcError, RpcResponse};
     /// use serde_json::json;
     ///
     /// let mut response = RpcResponse::new(1);
-    /// response.set_error(json!({"code": -32600, "message": "Invalid Request"}));
+    /// response.set_error(RpcError::method_not_found().with_data(json!({"method": "foo"})));
     /// ```
-    pub fn set_error(&mut self, error: JsonValue) {
-        self.value["error"] = error;
+    pub fn set_error(&mut self, error: RpcError) {
+        self.outcome = RpcOutcome::Failure(error);
+    }
+
+    /// Converts the response into a standard `Result`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(JsonValue)` - The result of a successful response
+    /// * `Err(RpcError)` - The error of an error response
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcResponse};
+    /// use serde_json::json;
+    ///
+    /// let response = RpcResponse::success(1, json!(3));
+    /// assert_eq!(response.into_result(), Ok(json!(3)));
+    ///
+    /// let response = RpcResponse::failure(1, RpcError::internal_error());
+    /// assert_eq!(response.into_result(), Err(RpcError::internal_error()));
+    /// ```
+    pub fn into_result(self) -> Result<JsonValue, RpcError> {
+        match self.outcome {
+            RpcOutcome::Success(result) => Ok(result),
+            RpcOutcome::Failure(error) => Err(error),
+        }
+    }
+}
+
+impl Serialize for RpcResponse {
+    /// Serializes the response with exactly one of `result` or `error`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("RpcResponse", 3)?;
+        state.serialize_field("jsonrpc", "2.0")?;
+        match &self.outcome {
+            RpcOutcome::Success(result) => state.serialize_field("result", result)?,
+            RpcOutcome::Failure(error) => state.serialize_field("error", error)?,
+        }
+        state.serialize_field("id", &self.id)?;
+        state.end()
+    }
+}
+
+/// The wire shape of a response, before `result`/`error` exclusivity is checked.
+#[derive(Deserialize)]
+struct RawRpcResponse {
+    #[serde(default, deserialize_with = "deserialize_result")]
+    result: Option<JsonValue>,
+
+    #[serde(default)]
+    error: Option<RpcError>,
+
+    id: RpcId,
+}
+
+impl TryFrom<RawRpcResponse> for RpcResponse {
+    type Error = String;
+
+    fn try_from(raw: RawRpcResponse) -> Result<Self, Self::Error> {
+        match (raw.result, raw.error) {
+            (Some(result), None) => Ok(RpcResponse::success(raw.id, result)),
+            (None, Some(error)) => Ok(RpcResponse::failure(raw.id, error)),
+            (Some(_), Some(_)) => Err("response contains both `result` and `error`".to_string()),
+            (None, None) => Err("response contains neither `result` nor `error`".to_string()),
+        }
+    }
+}
+
+impl FromStr for RpcResponse {
+    type Err = ValidationError;
+
+    /// Parses and validates an RPC response from JSON text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcResponse;
+    /// let response: RpcResponse = r#"{"jsonrpc": "2.0", "result": 19, "id": 1}"#
+    ///     .parse()
+    ///     .unwrap();
+    /// assert!(response.is_success());
+    /// ```
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_json(validation::parse_json(text)?)
     }
 }
 
 impl fmt::Debug for RpcResponse {
     /// Formats the RPC response as a pretty-printed JSON string.
     ///
-    /// This implementation of the `Debug` trait converts the internal JSON value
-    /// to a human-readable, indented JSON string. If serialization fails, it falls
+    /// This implementation of the `Debug` trait converts the response to a
+    /// human-readable, indented JSON string. If serialization fails, it falls
     /// back to displaying "Null".
     ///
     /// # Arguments
@@ -221,10 +541,10 @@ impl fmt::Debug for RpcResponse {
     ///
     /// let mut response = RpcResponse::new(1);
     /// response.set_result(json!({"status": "ok"}));
-    /// println!("{}", response); // Prints pretty-formatted JSON
+    /// println!("{:?}", response); // Prints pretty-formatted JSON
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let json_string = serde_json::to_string_pretty(&self.value).unwrap_or("Null".to_string());
+        let json_string = serde_json::to_string_pretty(self).unwrap_or("Null".to_string());
         write!(f, "{}", &json_string)
     }
 }
@@ -232,8 +552,8 @@ impl fmt::Debug for RpcResponse {
 impl fmt::Display for RpcResponse {
     /// Formats the RPC response as a JSON string.
     ///
-    /// This implementation of the `Display` trait converts the internal JSON value
-    /// to JSON string. If serialization fails, it falls back to displaying "Null".
+    /// This implementation of the `Display` trait converts the response to a
+    /// JSON string. If serialization fails, it falls back to displaying "Null".
     ///
     /// # Arguments
     ///
@@ -254,7 +574,7 @@ impl fmt::Display for RpcResponse {
     /// println!("{}", response); // Prints the JSON
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let json_string = serde_json::to_string(&self.value).unwrap_or("Null".to_string());
+        let json_string = serde_json::to_string(self).unwrap_or("Null".to_string());
         write!(f, "{}", &json_string)
     }
 }
@@ -266,25 +586,28 @@ impl fmt::Display for RpcResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ValidationErrorKind;
     use serde_json::json;
 
     #[test]
     fn test_new_response() {
         let response = RpcResponse::new(123);
 
-        assert_eq!(response.id(), 123);
-        assert_eq!(response.as_json()["jsonrpc"], "2.0");
-        assert_eq!(response.as_json()["id"], 123);
-        assert!(!response.as_json().get("result").is_some());
+        assert_eq!(response.id(), &RpcId::Number(123));
+        assert_eq!(response.to_json()["jsonrpc"], "2.0");
+        assert_eq!(response.to_json()["id"], 123);
+        assert!(response.is_success());
+        assert_eq!(response.result(), Some(&JsonValue::Null));
+        assert!(response.to_json().get("error").is_none());
     }
 
     #[test]
     fn test_new_response_zero_id() {
         let response = RpcResponse::new(0);
 
-        assert_eq!(response.id(), 0);
-        assert_eq!(response.as_json()["jsonrpc"], "2.0");
-        assert_eq!(response.as_json()["id"], 0);
+        assert_eq!(response.id(), &RpcId::Number(0));
+        assert_eq!(response.to_json()["jsonrpc"], "2.0");
+        assert_eq!(response.to_json()["id"], 0);
     }
 
     #[test]
@@ -292,8 +615,36 @@ mod tests {
         let large_id = u64::MAX;
         let response = RpcResponse::new(large_id);
 
-        assert_eq!(response.id(), large_id);
-        assert_eq!(response.as_json()["id"], large_id);
+        assert_eq!(response.id(), &RpcId::from(large_id));
+        assert_eq!(response.to_json()["id"], large_id);
+    }
+
+    #[test]
+    fn test_success_and_failure_constructors() {
+        let response = RpcResponse::success("a", json!([1, 2]));
+        assert!(response.is_success());
+        assert!(!response.is_error());
+        assert_eq!(response.outcome(), &RpcOutcome::Success(json!([1, 2])));
+
+        let response = RpcResponse::failure("b", RpcError::invalid_params());
+        assert!(response.is_error());
+        assert!(!response.is_success());
+        assert_eq!(
+            response.outcome(),
+            &RpcOutcome::Failure(RpcError::invalid_params())
+        );
+    }
+
+    #[test]
+    fn test_for_request() {
+        let mut request = RpcRequest::new();
+        request.set_id("req-1");
+
+        let response = RpcResponse::for_request(&request).unwrap();
+        assert_eq!(response.id(), &RpcId::from("req-1"));
+
+        let notification = RpcRequest::notification();
+        assert!(RpcResponse::for_request(&notification).is_none());
     }
 
     #[test]
@@ -304,11 +655,11 @@ mod tests {
             "result": "success"
         });
 
-        let response = RpcResponse::from_json(json_value.clone());
+        let response = RpcResponse::from_json(json_value.clone()).unwrap();
 
-        assert_eq!(response.id(), 456);
-        assert_eq!(response.result(), &json!("success"));
-        assert_eq!(response.as_json(), &json_value);
+        assert_eq!(response.id(), &RpcId::Number(456));
+        assert_eq!(response.result(), Some(&json!("success")));
+        assert_eq!(response.to_json(), json_value);
     }
 
     #[test]
@@ -322,52 +673,147 @@ mod tests {
             }
         });
 
-        let response = RpcResponse::from_json(json_value.clone());
+        let response = RpcResponse::from_json(json_value.clone()).unwrap();
 
-        assert_eq!(response.id(), 789);
-        assert_eq!(response.as_json(), &json_value);
-        // Result should be null when there's an error
-        assert_eq!(response.result(), &JsonValue::Null);
+        assert_eq!(response.id(), &RpcId::Number(789));
+        assert_eq!(response.to_json(), json_value);
+        assert_eq!(
+            response.error(),
+            Some(&RpcError::new(-1, "Something went wrong"))
+        );
+        // Result should be absent when there's an error
+        assert_eq!(response.result(), None);
     }
 
     #[test]
-    fn test_as_json() {
+    fn test_from_json_null_result() {
+        let response = RpcResponse::from_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": null
+        }))
+        .unwrap();
+
+        assert!(response.is_success());
+        assert_eq!(response.result(), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn test_from_json_rejects_both_result_and_error() {
+        let result = RpcResponse::from_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": "ok",
+            "error": {"code": -1, "message": "failed"}
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_neither_result_nor_error() {
+        let result = RpcResponse::from_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_invalid_values() {
+        // Not an object
+        assert!(RpcResponse::from_json(json!("success")).is_err());
+        assert!(RpcResponse::from_json(json!([1, 2])).is_err());
+
+        // Wrong protocol version
+        let error = RpcResponse::from_json(json!({
+            "jsonrpc": "1.0",
+            "id": 1,
+            "result": "ok"
+        }))
+        .unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let response: RpcResponse = r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "a"}"#
+            .parse()
+            .expect("Failed to parse");
+        assert_eq!(response.id(), &RpcId::from("a"));
+        assert_eq!(response.error(), Some(&RpcError::method_not_found()));
+
+        let error = "not json".parse::<RpcResponse>().unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::Parse);
+    }
+
+    #[test]
+    fn test_to_json() {
         let mut response = RpcResponse::new(42);
         response.set_result(json!({"data": [1, 2, 3]}));
 
-        let json_ref = response.as_json();
-        assert_eq!(json_ref["jsonrpc"], "2.0");
-        assert_eq!(json_ref["id"], 42);
-        assert_eq!(json_ref["result"], json!({"data": [1, 2, 3]}));
+        let json = response.to_json();
+        assert_eq!(json["jsonrpc"], "2.0");
+        assert_eq!(json["id"], 42);
+        assert_eq!(json["result"], json!({"data": [1, 2, 3]}));
     }
 
     #[test]
     fn test_id_extraction() {
         let response = RpcResponse::new(999);
-        assert_eq!(response.id(), 999);
+        assert_eq!(response.id(), &RpcId::Number(999));
     }
 
     #[test]
-    fn test_id_missing_returns_zero() {
+    fn test_new_response_string_id() {
+        let response = RpcResponse::new("9d3c1f0e-uuid");
+
+        assert_eq!(response.id(), &RpcId::from("9d3c1f0e-uuid"));
+        assert_eq!(response.to_json()["id"], "9d3c1f0e-uuid");
+    }
+
+    #[test]
+    fn test_new_response_null_id() {
+        let response = RpcResponse::new(RpcId::Null);
+
+        assert_eq!(response.id(), &RpcId::Null);
+        assert_eq!(response.to_json()["id"], JsonValue::Null);
+        assert!(response.to_json().get("id").is_some());
+    }
+
+    #[test]
+    fn test_id_missing_is_rejected() {
         let json_without_id = json!({
             "jsonrpc": "2.0",
             "result": "test"
         });
 
-        let response = RpcResponse::from_json(json_without_id);
-        assert_eq!(response.id(), 0);
+        assert!(RpcResponse::from_json(json_without_id).is_err());
     }
 
     #[test]
-    fn test_id_invalid_type_returns_zero() {
+    fn test_id_string_is_preserved() {
         let json_with_string_id = json!({
             "jsonrpc": "2.0",
             "id": "not_a_number",
             "result": "test"
         });
 
-        let response = RpcResponse::from_json(json_with_string_id);
-        assert_eq!(response.id(), 0);
+        let response = RpcResponse::from_json(json_with_string_id).unwrap();
+        assert_eq!(response.id(), &RpcId::from("not_a_number"));
+        assert_eq!(response.to_json()["id"], "not_a_number");
+    }
+
+    #[test]
+    fn test_id_invalid_type_is_rejected() {
+        let json_with_object_id = json!({
+            "jsonrpc": "2.0",
+            "id": {"nested": 1},
+            "result": "test"
+        });
+
+        assert!(RpcResponse::from_json(json_with_object_id).is_err());
     }
 
     #[test]
@@ -375,11 +821,11 @@ mod tests {
         let mut response = RpcResponse::new(1);
 
         // Initially result should be null
-        assert_eq!(response.result(), &JsonValue::Null);
+        assert_eq!(response.result(), Some(&JsonValue::Null));
 
         // Set a string result
         response.set_result(json!("success"));
-        assert_eq!(response.result(), &json!("success"));
+        assert_eq!(response.result(), Some(&json!("success")));
 
         // Set an object result
         let complex_result = json!({
@@ -390,7 +836,7 @@ mod tests {
             }
         });
         response.set_result(complex_result.clone());
-        assert_eq!(response.result(), &complex_result);
+        assert_eq!(response.result(), Some(&complex_result));
     }
 
     #[test]
@@ -398,8 +844,8 @@ mod tests {
         let mut response = RpcResponse::new(1);
         response.set_result(json!("hello world"));
 
-        assert_eq!(response.result(), &json!("hello world"));
-        assert_eq!(response.as_json()["result"], "hello world");
+        assert_eq!(response.result(), Some(&json!("hello world")));
+        assert_eq!(response.to_json()["result"], "hello world");
     }
 
     #[test]
@@ -407,8 +853,8 @@ mod tests {
         let mut response = RpcResponse::new(1);
         response.set_result(json!(42));
 
-        assert_eq!(response.result(), &json!(42));
-        assert_eq!(response.as_json()["result"], 42);
+        assert_eq!(response.result(), Some(&json!(42)));
+        assert_eq!(response.to_json()["result"], 42);
     }
 
     #[test]
@@ -416,8 +862,8 @@ mod tests {
         let mut response = RpcResponse::new(1);
         response.set_result(json!(true));
 
-        assert_eq!(response.result(), &json!(true));
-        assert_eq!(response.as_json()["result"], true);
+        assert_eq!(response.result(), Some(&json!(true)));
+        assert_eq!(response.to_json()["result"], true);
     }
 
     #[test]
@@ -426,8 +872,8 @@ mod tests {
         let array_result = json!([1, "two", 3.0, true]);
         response.set_result(array_result.clone());
 
-        assert_eq!(response.result(), &array_result);
-        assert_eq!(response.as_json()["result"], array_result);
+        assert_eq!(response.result(), Some(&array_result));
+        assert_eq!(response.to_json()["result"], array_result);
     }
 
     #[test]
@@ -441,8 +887,8 @@ mod tests {
         });
         response.set_result(object_result.clone());
 
-        assert_eq!(response.result(), &object_result);
-        assert_eq!(response.as_json()["result"], object_result);
+        assert_eq!(response.result(), Some(&object_result));
+        assert_eq!(response.to_json()["result"], object_result);
     }
 
     #[test]
@@ -450,8 +896,9 @@ mod tests {
         let mut response = RpcResponse::new(1);
         response.set_result(JsonValue::Null);
 
-        assert_eq!(response.result(), &JsonValue::Null);
-        assert_eq!(response.as_json()["result"], JsonValue::Null);
+        assert_eq!(response.result(), Some(&JsonValue::Null));
+        assert_eq!(response.to_json()["result"], JsonValue::Null);
+        assert!(response.to_json().get("result").is_some());
     }
 
     #[test]
@@ -459,40 +906,33 @@ mod tests {
         let mut response = RpcResponse::new(1);
 
         response.set_result(json!("first"));
-        assert_eq!(response.result(), &json!("first"));
+        assert_eq!(response.result(), Some(&json!("first")));
 
         response.set_result(json!("second"));
-        assert_eq!(response.result(), &json!("second"));
+        assert_eq!(response.result(), Some(&json!("second")));
 
         response.set_result(json!({"final": "value"}));
-        assert_eq!(response.result(), &json!({"final": "value"}));
+        assert_eq!(response.result(), Some(&json!({"final": "value"})));
     }
 
     #[test]
     fn test_error_getter() {
         let mut response = RpcResponse::new(1);
 
-        // Initially error should be null
-        assert_eq!(response.error(), &JsonValue::Null);
+        // Initially there is no error
+        assert_eq!(response.error(), None);
 
         // Set a simple error
-        response.set_error(json!({"code": -1, "message": "Test error"}));
-        assert_eq!(
-            response.error(),
-            &json!({"code": -1, "message": "Test error"})
-        );
+        response.set_error(RpcError::new(-1, "Test error"));
+        assert_eq!(response.error(), Some(&RpcError::new(-1, "Test error")));
 
         // Set a complex error with additional data
-        let complex_error = json!({
-            "code": -32600,
-            "message": "Invalid Request",
-            "data": {
-                "details": "Missing required parameter",
-                "line": 42
-            }
-        });
+        let complex_error = RpcError::invalid_request().with_data(json!({
+            "details": "Missing required parameter",
+            "line": 42
+        }));
         response.set_error(complex_error.clone());
-        assert_eq!(response.error(), &complex_error);
+        assert_eq!(response.error(), Some(&complex_error));
     }
 
     #[test]
@@ -500,97 +940,183 @@ mod tests {
         let mut response = RpcResponse::new(1);
 
         // Test parse error
-        response.set_error(json!({"code": -32700, "message": "Parse error"}));
+        response.set_error(RpcError::parse_error());
+        assert_eq!(response.error(), Some(&RpcError::parse_error()));
         assert_eq!(
-            response.error(),
-            &json!({"code": -32700, "message": "Parse error"})
+            response.to_json()["error"],
+            json!({"code": -32700, "message": "Parse error"})
         );
-        assert_eq!(response.as_json()["error"]["code"], -32700);
 
         // Test invalid request
-        response.set_error(json!({"code": -32600, "message": "Invalid Request"}));
+        response.set_error(RpcError::invalid_request());
+        assert_eq!(response.error(), Some(&RpcError::invalid_request()));
         assert_eq!(
-            response.error(),
-            &json!({"code": -32600, "message": "Invalid Request"})
+            response.to_json()["error"],
+            json!({"code": -32600, "message": "Invalid Request"})
         );
-        assert_eq!(response.as_json()["error"]["code"], -32600);
 
         // Test method not found
-        response.set_error(json!({"code": -32601, "message": "Method not found"}));
+        response.set_error(RpcError::method_not_found());
+        assert_eq!(response.error(), Some(&RpcError::method_not_found()));
         assert_eq!(
-            response.error(),
-            &json!({"code": -32601, "message": "Method not found"})
+            response.to_json()["error"],
+            json!({"code": -32601, "message": "Method not found"})
+        );
+
+        // Test invalid params
+        response.set_error(RpcError::invalid_params());
+        assert_eq!(response.to_json()["error"]["code"], -32602);
+
+        // Test internal error
+        response.set_error(RpcError::internal_error());
+        assert_eq!(response.to_json()["error"]["code"], -32603);
+
+        // Test server error
+        response.set_error(RpcError::server_error(-32001, "Server busy"));
+        assert_eq!(
+            response.to_json()["error"],
+            json!({"code": -32001, "message": "Server busy"})
         );
-        assert_eq!(response.as_json()["error"]["code"], -32601);
     }
 
     #[test]
     fn test_set_error_with_data() {
         let mut response = RpcResponse::new(1);
-        let error_with_data = json!({
-            "code": -32602,
-            "message": "Invalid params",
-            "data": {
-                "expected": "string",
-                "received": "number",
-                "parameter": "username"
-            }
-        });
+        let error_with_data = RpcError::invalid_params().with_data(json!({
+            "expected": "string",
+            "received": "number",
+            "parameter": "username"
+        }));
         response.set_error(error_with_data.clone());
 
-        assert_eq!(response.error(), &error_with_data);
-        assert_eq!(response.as_json()["error"], error_with_data);
+        assert_eq!(response.error(), Some(&error_with_data));
+        assert_eq!(
+            response.to_json()["error"],
+            json!({
+                "code": -32602,
+                "message": "Invalid params",
+                "data": {
+                    "expected": "string",
+                    "received": "number",
+                    "parameter": "username"
+                }
+            })
+        );
     }
 
     #[test]
-    fn test_set_error_null() {
-        let mut response = RpcResponse::new(1);
-        response.set_error(JsonValue::Null);
+    fn test_error_malformed_is_rejected() {
+        let result = RpcResponse::from_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": "not an error object"
+        }));
 
-        assert_eq!(response.error(), &JsonValue::Null);
-        assert_eq!(response.as_json()["error"], JsonValue::Null);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_set_error_overwrites_previous() {
         let mut response = RpcResponse::new(1);
 
-        response.set_error(json!({"code": -1, "message": "first error"}));
-        assert_eq!(
-            response.error(),
-            &json!({"code": -1, "message": "first error"})
-        );
+        response.set_error(RpcError::new(-1, "first error"));
+        assert_eq!(response.error(), Some(&RpcError::new(-1, "first error")));
 
-        response.set_error(json!({"code": -2, "message": "second error"}));
-        assert_eq!(
-            response.error(),
-            &json!({"code": -2, "message": "second error"})
-        );
+        response.set_error(RpcError::new(-2, "second error"));
+        assert_eq!(response.error(), Some(&RpcError::new(-2, "second error")));
 
-        response.set_error(json!({"code": -32700, "message": "Parse error"}));
-        assert_eq!(
-            response.error(),
-            &json!({"code": -32700, "message": "Parse error"})
-        );
+        response.set_error(RpcError::parse_error());
+        assert_eq!(response.error(), Some(&RpcError::parse_error()));
     }
 
     #[test]
-    fn test_error_and_result_independence() {
+    fn test_error_and_result_exclusivity() {
         let mut response = RpcResponse::new(1);
 
         // Set result first
         response.set_result(json!("success"));
-        assert_eq!(response.result(), &json!("success"));
-        assert_eq!(response.error(), &JsonValue::Null);
+        assert_eq!(response.result(), Some(&json!("success")));
+        assert_eq!(response.error(), None);
 
-        // Set error - should not affect result
-        response.set_error(json!({"code": -1, "message": "error"}));
-        assert_eq!(response.result(), &json!("success"));
-        assert_eq!(response.error(), &json!({"code": -1, "message": "error"}));
+        // Set error - replaces the result
+        response.set_error(RpcError::new(-1, "error"));
+        assert_eq!(response.result(), None);
+        assert_eq!(response.error(), Some(&RpcError::new(-1, "error")));
+        assert!(response.to_json().get("result").is_none());
 
-        // Update result - should not affect error
+        // Update result - replaces the error
         response.set_result(json!("updated"));
-        assert_eq!(response.result(), &json!("updated"));
-        assert_eq!(response.error(), &json!({"code": -1, "message": "error"}));
+        assert_eq!(response.result(), Some(&json!("updated")));
+        assert_eq!(response.error(), None);
+        assert!(response.to_json().get("error").is_none());
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Balance {
+        account: String,
+        amount: u64,
+    }
+
+    #[test]
+    fn test_typed_result_roundtrip() {
+        let balance = Balance {
+            account: "acc_1".to_string(),
+            amount: 250,
+        };
+
+        let mut response = RpcResponse::new(1);
+        response.set_result_typed(&balance).unwrap();
+        assert_eq!(
+            response.result(),
+            Some(&json!({"account": "acc_1", "amount": 250}))
+        );
+        assert_eq!(response.result_as::<Balance>().unwrap(), balance);
+    }
+
+    #[test]
+    fn test_typed_result_mismatch() {
+        let response = RpcResponse::success("r1", json!({"account": "acc_1", "amount": -5}));
+
+        let error = response.result_as::<Balance>().unwrap_err();
+        assert_eq!(error.code(), RpcError::INTERNAL_ERROR);
+        assert!(error.message().contains("`amount`"));
+        assert_eq!(error.data().unwrap()["field"], "amount");
+        assert_eq!(error.data().unwrap()["id"], "r1");
+    }
+
+    #[test]
+    fn test_typed_result_of_error_response() {
+        let response = RpcResponse::failure(1, RpcError::method_not_found());
+
+        let error = response.result_as::<Balance>().unwrap_err();
+        assert_eq!(error, RpcError::method_not_found());
+    }
+
+    #[test]
+    fn test_into_result() {
+        let response = RpcResponse::success(1, json!({"ok": true}));
+        assert_eq!(response.into_result(), Ok(json!({"ok": true})));
+
+        let response = RpcResponse::failure(1, RpcError::method_not_found());
+        assert_eq!(response.into_result(), Err(RpcError::method_not_found()));
+    }
+
+    #[test]
+    fn test_display_formatting() {
+        let response = RpcResponse::failure(7, RpcError::invalid_request());
+
+        let display_string = format!("{}", response);
+        let parsed: JsonValue = serde_json::from_str(&display_string).unwrap();
+        assert_eq!(
+            parsed,
+            json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32600, "message": "Invalid Request"},
+                "id": 7
+            })
+        );
+
+        let debug_string = format!("{:?}", response);
+        assert!(debug_string.contains("\"code\": -32600"));
     }
 }
@@ -0,0 +1,1201 @@
+use crate::{
+    CANCEL_REQUEST_METHOD, RpcBatchRequest, RpcBatchResponse, RpcContext, RpcError, RpcId,
+    RpcMessage, RpcRequest, RpcResponse, ValidationError,
+};
+use futures::executor;
+use futures::future::{self, BoxFuture, Either, FutureExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+
+// ===========================================================================
+// TRAIT: RpcHandler
+// ===
+
+/// A handler for a single JSON-RPC method.
+///
+/// Most handlers are registered as closures with `RpcRouter::register()`, which
+/// takes care of converting params and results. Implement this trait directly
+/// when a handler needs the whole request, such as its method name or ID.
+/// Handlers that need the connection the request arrived on also override
+/// `call_with_context()`.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcError, RpcHandler, RpcRequest, RpcRouter};
+/// use serde_json::{Value as JsonValue, json};
+///
+/// struct Echo;
+///
+/// impl RpcHandler for Echo {
+///     fn call(&self, request: &RpcRequest) -> Result<JsonValue, RpcError> {
+///         Ok(json!({"method": request.method(), "params": request.params()}))
+///     }
+/// }
+///
+/// let mut router = RpcRouter::new();
+/// router.register_handler("echo", Echo);
+/// ```
+pub trait RpcHandler: Send + Sync {
+    /// Handles a request routed to this handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to handle
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(JsonValue)` - The result to send back
+    /// * `Err(RpcError)` - The error to send back
+    fn call(&self, request: &RpcRequest) -> Result<JsonValue, RpcError>;
+
+    /// Handles a request along with the context it arrived in.
+    ///
+    /// The router always calls this method. The default implementation
+    /// ignores the context and calls `call()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to handle
+    /// * `context` - The connection the request arrived on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(JsonValue)` - The result to send back
+    /// * `Err(RpcError)` - The error to send back
+    fn call_with_context(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> Result<JsonValue, RpcError> {
+        let _ = context;
+        self.call(request)
+    }
+}
+
+// ===========================================================================
+// TRAIT: AsyncRpcHandler
+// ===
+
+/// A handler for a single JSON-RPC method that completes asynchronously.
+///
+/// Handlers doing I/O return a future instead of blocking. Most are registered
+/// as `async` closures with `RpcRouter::register_async()`; implement this trait
+/// directly when a handler needs the whole request. Every `RpcHandler` is also
+/// an `AsyncRpcHandler` whose future is ready immediately.
+///
+/// The returned future must not borrow the request, so copy out whatever the
+/// handler needs before building it.
+///
+/// # Examples
+///
+/// ```
+/// use futures::future::{BoxFuture, FutureExt};
+/// use ink_rpc::{AsyncRpcHandler, RpcError, RpcRequest, RpcRouter};
+/// use serde_json::{Value as JsonValue, json};
+///
+/// struct Echo;
+///
+/// impl AsyncRpcHandler for Echo {
+///     fn call(&self, request: &RpcRequest) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+///         let params = request.params().clone();
+///         async move { Ok(json!({"params": params})) }.boxed()
+///     }
+/// }
+///
+/// let mut router = RpcRouter::new();
+/// router.register_async_handler("echo", Echo);
+/// ```
+pub trait AsyncRpcHandler: Send + Sync {
+    /// Starts handling a request routed to this handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to handle
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the result or error to send back.
+    fn call(&self, request: &RpcRequest) -> BoxFuture<'static, Result<JsonValue, RpcError>>;
+
+    /// Starts handling a request along with the context it arrived in.
+    ///
+    /// The router always calls this method. The default implementation
+    /// ignores the context and calls `call()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to handle
+    /// * `context` - The connection the request arrived on
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the result or error to send back.
+    fn call_with_context(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        let _ = context;
+        self.call(request)
+    }
+}
+
+impl<H: RpcHandler> AsyncRpcHandler for H {
+    fn call(&self, request: &RpcRequest) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        future::ready(RpcHandler::call(self, request)).boxed()
+    }
+
+    fn call_with_context(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        future::ready(RpcHandler::call_with_context(self, request, context)).boxed()
+    }
+}
+
+/// Adapts a closure taking typed params and the request's context, and
+/// returning a typed result.
+struct TypedHandler<F, P, R> {
+    handler: F,
+    types: PhantomData<fn(P) -> R>,
+}
+
+impl<F, P, R> RpcHandler for TypedHandler<F, P, R>
+where
+    F: Fn(P, &RpcContext) -> Result<R, RpcError> + Send + Sync,
+    P: DeserializeOwned,
+    R: Serialize,
+{
+    fn call(&self, request: &RpcRequest) -> Result<JsonValue, RpcError> {
+        RpcHandler::call_with_context(self, request, &RpcContext::default())
+    }
+
+    fn call_with_context(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> Result<JsonValue, RpcError> {
+        let params = request.params_as::<P>()?;
+        let result = (self.handler)(params, context)?;
+        serde_json::to_value(result).map_err(|error| {
+            RpcError::internal_error().with_data(json!({
+                "method": request.method(),
+                "reason": error.to_string(),
+            }))
+        })
+    }
+}
+
+/// Adapts a closure taking typed params and the request's context, and
+/// returning a future of a typed result.
+struct AsyncTypedHandler<F, P, R> {
+    handler: F,
+    types: PhantomData<fn(P) -> R>,
+}
+
+impl<F, P, R, Fut> AsyncRpcHandler for AsyncTypedHandler<F, P, R>
+where
+    F: Fn(P, RpcContext) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<R, RpcError>> + Send + 'static,
+    P: DeserializeOwned,
+    R: Serialize,
+{
+    fn call(&self, request: &RpcRequest) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        self.call_with_context(request, &RpcContext::default())
+    }
+
+    fn call_with_context(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        let params = match request.params_as::<P>() {
+            Ok(params) => params,
+            Err(error) => return future::ready(Err(error)).boxed(),
+        };
+        let method = request.method().to_string();
+        let pending = (self.handler)(params, context.clone());
+
+        async move {
+            let result = pending.await?;
+            serde_json::to_value(result).map_err(|error| {
+                RpcError::internal_error().with_data(json!({
+                    "method": method,
+                    "reason": error.to_string(),
+                }))
+            })
+        }
+        .boxed()
+    }
+}
+
+// ===========================================================================
+// STRUCT: RpcRouter
+// ===
+
+/// Dispatches requests to handlers registered by method name.
+///
+/// The router replaces the hand-written `match request.method()` found in most
+/// servers. Unknown methods are answered with "Method not found" (-32601) and
+/// params that do not match the handler's type with "Invalid params" (-32602).
+/// Notifications are dispatched too, but never produce a response.
+/// `$/cancelRequest` notifications cancel the request they name, unless a
+/// handler is registered for that method; see `CancellationToken`.
+///
+/// Handlers may be synchronous or asynchronous. The router does not depend on
+/// any async runtime: `handle_async()` and friends return plain futures that
+/// can be awaited on tokio, async-std or any other executor, while `handle()`
+/// and friends drive them to completion on the calling thread.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcError, RpcRequest, RpcRouter};
+/// use serde_json::json;
+///
+/// let mut router = RpcRouter::new();
+/// router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+///
+/// let mut request = RpcRequest::new();
+/// request.set_method("add".to_string()).set_params(json!([2, 3]));
+///
+/// let response = router.handle(request).unwrap();
+/// assert_eq!(response.result(), Some(&json!(5)));
+/// ```
+#[derive(Default)]
+pub struct RpcRouter {
+    handlers: HashMap<String, Box<dyn AsyncRpcHandler>>,
+}
+
+impl RpcRouter {
+    /// Creates a new router with no registered methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::RpcRouter;
+    /// let router = RpcRouter::new();
+    /// assert!(!router.has_method("ping"));
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Registers a closure as the handler for a method.
+    ///
+    /// The request params are deserialized into `P` before the closure is
+    /// called, and its `Ok` value is serialized as the result. Use `()` for
+    /// methods without params, a tuple for positional params and a struct for
+    /// named params. Registering a method again replaces its handler.
+    ///
+    /// This function uses the builder pattern, returning a mutable reference
+    /// to self for method chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The closure handling the method
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcRouter};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Greet {
+    ///     name: String,
+    /// }
+    ///
+    /// let mut router = RpcRouter::new();
+    /// router
+    ///     .register("ping", |_: ()| Ok::<_, RpcError>("pong"))
+    ///     .register("greet", |params: Greet| {
+    ///         Ok::<_, RpcError>(format!("Hello, {}!", params.name))
+    ///     });
+    /// assert!(router.has_method("greet"));
+    /// ```
+    pub fn register<P, R, F>(&mut self, method: impl Into<String>, handler: F) -> &mut Self
+    where
+        P: DeserializeOwned + 'static,
+        R: Serialize + 'static,
+        F: Fn(P) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        self.register_with_context(method, move |params: P, _: &RpcContext| handler(params))
+    }
+
+    /// Registers a closure that also receives the request's context.
+    ///
+    /// Works like `register()`, except that the closure is passed the
+    /// `RpcContext` of the connection the request arrived on, such as the
+    /// peer's address.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The closure handling the method
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn register_with_context<P, R, F>(
+        &mut self,
+        method: impl Into<String>,
+        handler: F,
+    ) -> &mut Self
+    where
+        P: DeserializeOwned + 'static,
+        R: Serialize + 'static,
+        F: Fn(P, &RpcContext) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        self.register_handler(
+            method,
+            TypedHandler {
+                handler,
+                types: PhantomData,
+            },
+        )
+    }
+
+    /// Registers an async closure as the handler for a method.
+    ///
+    /// Works like `register()`, except that the closure returns a future. The
+    /// params are deserialized before the closure is called, so a mismatch is
+    /// reported without ever starting the future.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The closure returning the future that handles the method
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcError, RpcRequest, RpcRouter};
+    /// use serde_json::json;
+    ///
+    /// let mut router = RpcRouter::new();
+    /// router.register_async("double", |(n,): (i64,)| async move { Ok::<_, RpcError>(n * 2) });
+    ///
+    /// let mut request = RpcRequest::new();
+    /// request.set_method("double".to_string()).set_params(json!([21]));
+    ///
+    /// let response = futures::executor::block_on(router.handle_async(request)).unwrap();
+    /// assert_eq!(response.result(), Some(&json!(42)));
+    /// ```
+    pub fn register_async<P, R, F, Fut>(
+        &mut self,
+        method: impl Into<String>,
+        handler: F,
+    ) -> &mut Self
+    where
+        P: DeserializeOwned + 'static,
+        R: Serialize + 'static,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, RpcError>> + Send + 'static,
+    {
+        self.register_async_with_context(method, move |params: P, _: RpcContext| handler(params))
+    }
+
+    /// Registers an async closure that also receives the request's context.
+    ///
+    /// Works like `register_async()`, except that the closure is passed its
+    /// own copy of the `RpcContext`, which the future may keep.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The closure returning the future that handles the method
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn register_async_with_context<P, R, F, Fut>(
+        &mut self,
+        method: impl Into<String>,
+        handler: F,
+    ) -> &mut Self
+    where
+        P: DeserializeOwned + 'static,
+        R: Serialize + 'static,
+        F: Fn(P, RpcContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, RpcError>> + Send + 'static,
+    {
+        self.register_async_handler(
+            method,
+            AsyncTypedHandler {
+                handler,
+                types: PhantomData,
+            },
+        )
+    }
+
+    /// Registers a trait object as the handler for a method.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The handler receiving the whole request
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn register_handler(
+        &mut self,
+        method: impl Into<String>,
+        handler: impl RpcHandler + 'static,
+    ) -> &mut Self {
+        self.register_async_handler(method, handler)
+    }
+
+    /// Registers an asynchronous trait object as the handler for a method.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to route
+    /// * `handler` - The handler receiving the whole request
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn register_async_handler(
+        &mut self,
+        method: impl Into<String>,
+        handler: impl AsyncRpcHandler + 'static,
+    ) -> &mut Self {
+        self.handlers.insert(method.into(), Box::new(handler));
+        self
+    }
+
+    /// Checks if a handler is registered for a method.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method name to look up
+    ///
+    /// # Returns
+    ///
+    /// `true` if the method is routed to a handler.
+    pub fn has_method(&self, method: &str) -> bool {
+        self.handlers.contains_key(method)
+    }
+
+    /// Returns the names of all registered methods, in no particular order.
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Dispatches a request to its handler, blocking until it completes.
+    ///
+    /// Async handlers are driven to completion on the calling thread. Inside an
+    /// async task, use `handle_async()` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to dispatch
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RpcResponse)` - The response to a request, which carries a
+    ///   "Method not found" or "Invalid params" error when dispatch fails
+    /// * `None` - The request was a notification
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcRequest, RpcRouter};
+    ///
+    /// let router = RpcRouter::new();
+    /// let mut request = RpcRequest::new();
+    /// request.set_method("missing".to_string());
+    ///
+    /// let response = router.handle(request).unwrap();
+    /// assert_eq!(response.error().unwrap().code(), -32601);
+    /// ```
+    pub fn handle(&self, request: RpcRequest) -> Option<RpcResponse> {
+        executor::block_on(self.handle_async(request))
+    }
+
+    /// Dispatches a request to its handler asynchronously.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request or notification to dispatch
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the response, or to `None` for a notification.
+    pub async fn handle_async(&self, request: RpcRequest) -> Option<RpcResponse> {
+        self.respond(request, &RpcContext::default()).await
+    }
+
+    /// Dispatches a request and builds its response.
+    ///
+    /// A request with an ID can be cancelled while it runs: an async handler
+    /// is dropped as soon as its token is cancelled, and the request is
+    /// answered with "Request cancelled".
+    async fn respond(&self, request: RpcRequest, context: &RpcContext) -> Option<RpcResponse> {
+        let Some(id) = request.id() else {
+            match request.method() {
+                CANCEL_REQUEST_METHOD if !self.has_method(CANCEL_REQUEST_METHOD) => {
+                    Self::cancel(&request, context)
+                }
+                _ => {
+                    let _ = self.dispatch(&request, context).await;
+                }
+            }
+            return None;
+        };
+
+        let token = context.in_flight().start(id);
+        let handler = self.dispatch(&request, &context.with_cancellation(token.clone()));
+        let outcome = match future::select(handler, token.cancelled()).await {
+            Either::Left((outcome, _)) => outcome,
+            Either::Right(_) => Err(RpcError::request_cancelled()),
+        };
+        context.in_flight().finish(id, &token);
+        let mut response = RpcResponse::for_request(&request)?;
+
+        match outcome {
+            Ok(result) => response.set_result(result),
+            Err(error) => response.set_error(error),
+        }
+
+        Some(response)
+    }
+
+    /// Dispatches every member of a batch, blocking until all complete.
+    ///
+    /// The members are processed concurrently, as with `handle_batch_async()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch of requests and notifications to dispatch
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RpcBatchResponse)` - The responses, in the order of the requests,
+    ///   followed by an "Invalid Request" error for every invalid member
+    /// * `None` - The batch contained only notifications, so nothing is sent back
+    pub fn handle_batch(&self, batch: RpcBatchRequest) -> Option<RpcBatchResponse> {
+        executor::block_on(self.handle_batch_async(batch))
+    }
+
+    /// Dispatches every member of a batch concurrently.
+    ///
+    /// All members are started together, so a slow async handler does not hold
+    /// up the rest of the batch. The responses keep the order of the requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The batch of requests and notifications to dispatch
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the responses, or to `None` if the batch contained
+    /// only notifications.
+    pub async fn handle_batch_async(&self, batch: RpcBatchRequest) -> Option<RpcBatchResponse> {
+        self.respond_batch(batch, &RpcContext::default()).await
+    }
+
+    /// Dispatches every member of a batch concurrently and collects the
+    /// responses. Invalid members are answered with their validation error.
+    async fn respond_batch(
+        &self,
+        batch: RpcBatchRequest,
+        context: &RpcContext,
+    ) -> Option<RpcBatchResponse> {
+        let invalid: Vec<RpcResponse> = batch
+            .invalid()
+            .iter()
+            .map(ValidationError::to_response)
+            .collect();
+        let responses: RpcBatchResponse = future::join_all(
+            batch
+                .into_iter()
+                .map(|request| self.respond(request, context)),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .chain(invalid)
+        .collect();
+
+        if responses.is_empty() {
+            None
+        } else {
+            Some(responses)
+        }
+    }
+
+    /// Dispatches an inbound message and returns the reply to send, if any.
+    ///
+    /// Responses and response batches are not meant for a router and are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The classified inbound message
+    ///
+    /// # Returns
+    ///
+    /// The `Response` or `ResponseBatch` to send back, or `None` if there is
+    /// nothing to reply.
+    pub fn handle_message(&self, message: RpcMessage) -> Option<RpcMessage> {
+        executor::block_on(self.handle_message_async(message))
+    }
+
+    /// Dispatches an inbound message asynchronously.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The classified inbound message
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the reply to send back, if any.
+    pub async fn handle_message_async(&self, message: RpcMessage) -> Option<RpcMessage> {
+        self.handle_message_async_with_context(message, &RpcContext::default())
+            .await
+    }
+
+    /// Dispatches an inbound message that arrived in the given context.
+    ///
+    /// This is how servers dispatch: handlers registered with
+    /// `register_with_context()` receive `context`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The classified inbound message
+    /// * `context` - The connection the message arrived on
+    ///
+    /// # Returns
+    ///
+    /// The `Response` or `ResponseBatch` to send back, or `None` if there is
+    /// nothing to reply.
+    pub fn handle_message_with_context(
+        &self,
+        message: RpcMessage,
+        context: &RpcContext,
+    ) -> Option<RpcMessage> {
+        executor::block_on(self.handle_message_async_with_context(message, context))
+    }
+
+    /// Dispatches an inbound message that arrived in the given context,
+    /// asynchronously.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The classified inbound message
+    /// * `context` - The connection the message arrived on
+    ///
+    /// # Returns
+    ///
+    /// A future resolving to the reply to send back, if any.
+    pub async fn handle_message_async_with_context(
+        &self,
+        message: RpcMessage,
+        context: &RpcContext,
+    ) -> Option<RpcMessage> {
+        match message {
+            RpcMessage::Request(request) | RpcMessage::Notification(request) => self
+                .respond(request, context)
+                .await
+                .map(RpcMessage::Response),
+            RpcMessage::RequestBatch(batch) => self
+                .respond_batch(batch, context)
+                .await
+                .map(RpcMessage::ResponseBatch),
+            RpcMessage::Response(_) | RpcMessage::ResponseBatch(_) => None,
+        }
+    }
+
+    /// Cancels the request named by a `$/cancelRequest` notification, if it
+    /// is still being handled on the same connection.
+    fn cancel(notification: &RpcRequest, context: &RpcContext) {
+        let id = notification.params().get("id").cloned();
+        if let Some(Ok(id)) = id.map(serde_json::from_value::<RpcId>) {
+            context.in_flight().cancel(&id);
+        }
+    }
+
+    /// Starts the handler for a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to dispatch
+    /// * `context` - The connection the request arrived on
+    ///
+    /// # Returns
+    ///
+    /// A future of the handler's outcome, or of a "Method not found" error
+    /// naming the method.
+    fn dispatch(
+        &self,
+        request: &RpcRequest,
+        context: &RpcContext,
+    ) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+        match self.handlers.get(request.method()) {
+            Some(handler) => handler.call_with_context(request, context),
+            None => future::ready(Err(RpcError::method_not_found().with_data(json!({
+                "method": request.method(),
+            }))))
+            .boxed(),
+        }
+    }
+}
+
+impl fmt::Debug for RpcRouter {
+    /// Formats the router as the sorted list of its methods.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut methods: Vec<&str> = self.methods().collect();
+        methods.sort_unstable();
+        f.debug_struct("RpcRouter")
+            .field("methods", &methods)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// TESTS: RpcRouter
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Deserialize)]
+    struct Transfer {
+        to: String,
+        amount: u64,
+    }
+
+    fn request(method: &str, params: JsonValue) -> RpcRequest {
+        let mut request = RpcRequest::new();
+        request.set_method(method.to_string()).set_params(params);
+        request
+    }
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router
+            .register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b))
+            .register("ping", |_: ()| Ok::<_, RpcError>("pong"))
+            .register("transfer", |params: Transfer| {
+                if params.amount > 1000 {
+                    return Err(RpcError::server_error(-32001, "Insufficient funds"));
+                }
+                Ok(json!({"to": params.to, "status": "completed"}))
+            });
+        router
+    }
+
+    // ---------------------------------------------------------------------------
+    // Dispatch Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_handle_typed_handlers() {
+        let router = router();
+
+        let response = router.handle(request("add", json!([2, 3]))).unwrap();
+        assert_eq!(response.result(), Some(&json!(5)));
+
+        let response = router.handle(request("ping", JsonValue::Null)).unwrap();
+        assert_eq!(response.result(), Some(&json!("pong")));
+
+        let response = router
+            .handle(request("transfer", json!({"to": "acc_2", "amount": 10})))
+            .unwrap();
+        assert_eq!(response.result().unwrap()["status"], "completed");
+    }
+
+    #[test]
+    fn test_handle_preserves_id() {
+        let router = router();
+        let mut request = request("ping", JsonValue::Null);
+        request.set_id("abc");
+
+        let response = router.handle(request).unwrap();
+        assert_eq!(response.id(), &crate::RpcId::from("abc"));
+    }
+
+    #[test]
+    fn test_handle_errors() {
+        let router = router();
+
+        // Errors returned by the handler are passed through
+        let response = router
+            .handle(request("transfer", json!({"to": "acc_2", "amount": 5000})))
+            .unwrap();
+        assert_eq!(response.error().unwrap().code(), -32001);
+
+        // Unknown methods
+        let response = router.handle(request("missing", JsonValue::Null)).unwrap();
+        let error = response.error().unwrap();
+        assert_eq!(error.code(), RpcError::METHOD_NOT_FOUND);
+        assert_eq!(error.data().unwrap()["method"], "missing");
+
+        // Params that do not match the handler's type
+        let response = router
+            .handle(request(
+                "transfer",
+                json!({"to": "acc_2", "amount": "lots"}),
+            ))
+            .unwrap();
+        let error = response.error().unwrap();
+        assert_eq!(error.code(), RpcError::INVALID_PARAMS);
+        assert_eq!(error.data().unwrap()["field"], "amount");
+    }
+
+    #[test]
+    fn test_handle_notification() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut router = RpcRouter::new();
+        router.register("log", move |_: JsonValue| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, RpcError>(())
+        });
+
+        let mut notification = RpcRequest::notification();
+        notification.set_method("log".to_string());
+        assert!(router.handle(notification).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Unknown notifications are silently dropped
+        let mut notification = RpcRequest::notification();
+        notification.set_method("missing".to_string());
+        assert!(router.handle(notification).is_none());
+    }
+
+    #[test]
+    fn test_register_handler_trait_object() {
+        struct MethodName;
+
+        impl RpcHandler for MethodName {
+            fn call(&self, request: &RpcRequest) -> Result<JsonValue, RpcError> {
+                Ok(json!(request.method()))
+            }
+        }
+
+        let mut router = RpcRouter::new();
+        router.register_handler("whoami", MethodName);
+
+        let response = router.handle(request("whoami", JsonValue::Null)).unwrap();
+        assert_eq!(response.result(), Some(&json!("whoami")));
+        assert_eq!(router.methods().collect::<Vec<_>>(), vec!["whoami"]);
+    }
+
+    // ---------------------------------------------------------------------------
+    // Async Handler Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_handle_async_handlers() {
+        let mut router = router();
+        router.register_async(
+            "double",
+            |(n,): (i64,)| async move { Ok::<_, RpcError>(n * 2) },
+        );
+
+        let response = executor::block_on(router.handle_async(request("double", json!([21]))));
+        assert_eq!(response.unwrap().result(), Some(&json!(42)));
+
+        // Sync handlers are served through the async path as well
+        let response = executor::block_on(router.handle_async(request("add", json!([1, 2]))));
+        assert_eq!(response.unwrap().result(), Some(&json!(3)));
+
+        // The blocking entry point drives async handlers too
+        let response = router.handle(request("double", json!([5]))).unwrap();
+        assert_eq!(response.result(), Some(&json!(10)));
+
+        // Params are checked before the handler runs
+        let response = router.handle(request("double", json!(["x"]))).unwrap();
+        assert_eq!(response.error().unwrap().code(), RpcError::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_handle_batch_concurrently() {
+        use futures::channel::oneshot;
+        use std::sync::Mutex;
+
+        // "wait" only completes once "signal" has run, which can only happen if
+        // the batch members are processed concurrently.
+        let (sender, receiver) = oneshot::channel::<i64>();
+        let sender = Arc::new(Mutex::new(Some(sender)));
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+
+        let mut router = RpcRouter::new();
+        router
+            .register_async("wait", move |_: ()| {
+                let receiver = receiver.lock().unwrap().take().unwrap();
+                async move { receiver.await.map_err(|_| RpcError::internal_error()) }
+            })
+            .register_async("signal", move |(value,): (i64,)| {
+                let sender = sender.lock().unwrap().take().unwrap();
+                async move {
+                    sender.send(value).unwrap();
+                    Ok::<_, RpcError>(true)
+                }
+            });
+
+        let batch: RpcBatchRequest = vec![
+            request("wait", JsonValue::Null),
+            request("signal", json!([7])),
+        ]
+        .into();
+
+        let responses = executor::block_on(router.handle_batch_async(batch)).unwrap();
+        assert_eq!(responses.responses()[0].result(), Some(&json!(7)));
+        assert_eq!(responses.responses()[1].result(), Some(&json!(true)));
+    }
+
+    #[test]
+    fn test_register_async_handler_trait_object() {
+        struct Delayed;
+
+        impl AsyncRpcHandler for Delayed {
+            fn call(
+                &self,
+                request: &RpcRequest,
+            ) -> BoxFuture<'static, Result<JsonValue, RpcError>> {
+                let method = request.method().to_string();
+                async move { Ok(json!(method)) }.boxed()
+            }
+        }
+
+        let mut router = RpcRouter::new();
+        router.register_async_handler("delayed", Delayed);
+
+        let response = router.handle(request("delayed", JsonValue::Null)).unwrap();
+        assert_eq!(response.result(), Some(&json!("delayed")));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Batch and Message Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_handle_batch() {
+        let router = router();
+        let mut notification = RpcRequest::notification();
+        notification.set_method("ping".to_string());
+
+        let first = request("add", json!([1, 1]));
+        let second = request("missing", JsonValue::Null);
+        let batch: RpcBatchRequest =
+            vec![first.clone(), notification.clone(), second.clone()].into();
+
+        let responses = router.handle_batch(batch).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses.responses()[0].id(), first.id().unwrap());
+        assert!(responses.responses()[1].is_error());
+
+        // A batch of notifications gets no reply at all
+        let batch: RpcBatchRequest = vec![notification].into();
+        assert!(router.handle_batch(batch).is_none());
+    }
+
+    #[test]
+    fn test_handle_batch_with_invalid_members() {
+        let router = router();
+
+        // The valid member still runs next to the invalid one
+        let message = RpcMessage::parse(
+            r#"[{"jsonrpc": "2.0", "method": "add", "params": [2, 3], "id": 1}, {"foo": 1}]"#,
+        )
+        .unwrap();
+        let Some(RpcMessage::ResponseBatch(responses)) = router.handle_message(message) else {
+            panic!("expected a response batch");
+        };
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses.get(&RpcId::Number(1)).unwrap().result(),
+            Some(&json!(5))
+        );
+        let invalid = responses.get(&RpcId::Null).unwrap();
+        assert_eq!(invalid.error().unwrap().code(), RpcError::INVALID_REQUEST);
+
+        // Every invalid member gets its own error
+        let message = RpcMessage::parse("[1, 2, 3]").unwrap();
+        let Some(RpcMessage::ResponseBatch(responses)) = router.handle_message(message) else {
+            panic!("expected a response batch");
+        };
+        assert_eq!(responses.len(), 3);
+        for response in responses {
+            assert_eq!(response.id(), &RpcId::Null);
+            assert_eq!(response.error().unwrap().code(), RpcError::INVALID_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_handle_message() {
+        let router = router();
+
+        let message =
+            RpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "ping", "id": 7}"#).unwrap();
+        let reply = router.handle_message(message).unwrap();
+        assert!(matches!(reply, RpcMessage::Response(ref r) if r.is_success()));
+
+        let message = RpcMessage::parse(r#"{"jsonrpc": "2.0", "result": 1, "id": 7}"#).unwrap();
+        assert!(router.handle_message(message).is_none());
+    }
+
+    // ---------------------------------------------------------------------------
+    // Cancellation Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_cancel_request() {
+        let mut router = router();
+        router.register_async_with_context("wait", |_: (), _: RpcContext| async move {
+            future::pending::<()>().await;
+            Ok::<_, RpcError>(())
+        });
+        let context = RpcContext::new();
+
+        let wait = request("wait", JsonValue::Null);
+        let mut cancel = RpcRequest::notification();
+        cancel
+            .set_method(CANCEL_REQUEST_METHOD.to_string())
+            .set_params(json!({ "id": wait.id() }));
+
+        // The async handler is dropped once its request is cancelled
+        let (reply, none) = executor::block_on(future::join(
+            router.handle_message_async_with_context(RpcMessage::from(wait), &context),
+            router.handle_message_async_with_context(RpcMessage::from(cancel.clone()), &context),
+        ));
+        let Some(RpcMessage::Response(response)) = reply else {
+            panic!("expected a response, got {:?}", reply);
+        };
+        assert_eq!(
+            response.error().unwrap().code(),
+            RpcError::REQUEST_CANCELLED
+        );
+        assert!(none.is_none());
+
+        // Cancelling a request that already completed changes nothing
+        assert!(
+            router
+                .handle_message_with_context(RpcMessage::from(cancel), &context)
+                .is_none()
+        );
+        let reply = router
+            .handle_message_with_context(RpcMessage::from(request("add", json!([1, 2]))), &context);
+        let Some(RpcMessage::Response(response)) = reply else {
+            panic!("expected a response, got {:?}", reply);
+        };
+        assert_eq!(response.result(), Some(&json!(3)));
+    }
+
+    #[test]
+    fn test_sync_handlers_check_their_token() {
+        let mut router = RpcRouter::new();
+        router.register_with_context("check", |_: (), context: &RpcContext| {
+            context.cancellation().check()?;
+            Ok::<_, RpcError>(context.cancellation().is_cancelled())
+        });
+
+        // Every request gets a fresh token
+        let context = RpcContext::new();
+        for _ in 0..2 {
+            let reply = router.handle_message_with_context(
+                RpcMessage::from(request("check", JsonValue::Null)),
+                &context,
+            );
+            let Some(RpcMessage::Response(response)) = reply else {
+                panic!("expected a response, got {:?}", reply);
+            };
+            assert_eq!(response.result(), Some(&json!(false)));
+        }
+    }
+
+    // ---------------------------------------------------------------------------
+    // Context Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_handlers_receive_context() {
+        struct Peer;
+
+        impl RpcHandler for Peer {
+            fn call(&self, _: &RpcRequest) -> Result<JsonValue, RpcError> {
+                Ok(JsonValue::Null)
+            }
+
+            fn call_with_context(
+                &self,
+                _: &RpcRequest,
+                context: &RpcContext,
+            ) -> Result<JsonValue, RpcError> {
+                Ok(json!(context.peer_addr()))
+            }
+        }
+
+        let mut router = router();
+        router
+            .register_with_context("sync", |_: (), context: &RpcContext| {
+                Ok::<_, RpcError>(context.peer_addr())
+            })
+            .register_async_with_context("async", |_: (), context: RpcContext| async move {
+                Ok::<_, RpcError>(context.peer_addr())
+            })
+            .register_handler("trait", Peer);
+
+        let mut context = RpcContext::new();
+        context.set_peer_addr("10.0.0.1:5000".parse().unwrap());
+
+        let batch: RpcBatchRequest = vec![
+            request("sync", JsonValue::Null),
+            request("async", JsonValue::Null),
+            request("trait", JsonValue::Null),
+            request("add", json!([1, 2])),
+        ]
+        .into();
+        let reply = router.handle_message_with_context(RpcMessage::from(batch), &context);
+        let Some(RpcMessage::ResponseBatch(responses)) = reply else {
+            panic!("expected a response batch, got {:?}", reply);
+        };
+        let results: Vec<_> = responses
+            .responses()
+            .iter()
+            .map(|r| r.result().unwrap().clone())
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                json!("10.0.0.1:5000"),
+                json!("10.0.0.1:5000"),
+                json!("10.0.0.1:5000"),
+                json!(3)
+            ]
+        );
+
+        // Without a connection, handlers see an empty context
+        let response = router.handle(request("sync", JsonValue::Null)).unwrap();
+        assert_eq!(response.result(), Some(&JsonValue::Null));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    RpcBatchResponse, RpcContext, RpcError, RpcId, RpcMessage, RpcResponse, RpcRouter, Transport,
+    TransportError,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread::{self, Scope};
+
+/// The most requests one connection may have in progress at once.
+pub(crate) const MAX_CONCURRENT_REQUESTS: usize = 64;
+
+/// Serves requests arriving on a transport until the peer disconnects.
+///
+/// Every inbound request and batch is dispatched through the router and its
+/// reply, if any, is sent back on the same transport. Messages that are not
+/// valid JSON-RPC 2.0, are badly framed or exceed the codec's size limit, are answered
+/// with the matching error response, and reading continues. Inbound responses
+/// are ignored.
+///
+/// Requests run on their own threads while reading continues, so a slow
+/// handler does not hold up the others and a `$/cancelRequest` notification
+/// reaches the request it names. At most 64 requests run at once; further
+/// ones are answered with `RpcError::server_busy()`. Notifications run in the
+/// order they arrive. Once the peer disconnects, the requests still running
+/// are finished and answered before this returns.
+///
+/// # Arguments
+///
+/// * `transport` - The connection to serve
+/// * `router` - The router dispatching requests to handlers
+///
+/// # Returns
+///
+/// * `Ok(())` - The peer closed the connection
+/// * `Err(TransportError)` - The connection failed
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{MemoryTransport, RpcClient, RpcError, RpcRouter, serve};
+/// use serde_json::json;
+/// use std::thread;
+///
+/// let (client_end, server_end) = MemoryTransport::pair();
+///
+/// let server = thread::spawn(move || {
+///     let mut router = RpcRouter::new();
+///     router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+///     serve(&server_end, &router)
+/// });
+///
+/// let client = RpcClient::connect(client_end);
+/// assert_eq!(client.call("add", json!([2, 3])).wait().unwrap(), json!(5));
+///
+/// client.close();
+/// server.join().unwrap().unwrap();
+/// ```
+pub fn serve(transport: &dyn Transport, router: &RpcRouter) -> Result<(), TransportError> {
+    serve_with_context(transport, router, &RpcContext::default())
+}
+
+/// Serves requests arriving on a transport, dispatching them with the given
+/// context.
+///
+/// This is `serve()` for connections with metadata: every request is
+/// dispatched with `context`, so handlers registered with
+/// `RpcRouter::register_with_context()` can see where it came from.
+///
+/// # Arguments
+///
+/// * `transport` - The connection to serve
+/// * `router` - The router dispatching requests to handlers
+/// * `context` - The context describing the connection
+///
+/// # Returns
+///
+/// * `Ok(())` - The peer closed the connection
+/// * `Err(TransportError)` - The connection failed
+pub fn serve_with_context(
+    transport: &dyn Transport,
+    router: &RpcRouter,
+    context: &RpcContext,
+) -> Result<(), TransportError> {
+    thread::scope(|scope| {
+        let dispatcher = Dispatcher::new(scope, transport, router, context);
+        loop {
+            match transport.receive() {
+                Ok(Some(message)) => dispatcher.dispatch(message),
+                Ok(None) => return Ok(()),
+                Err(error) => transport.send(error_reply(error)?)?,
+            }
+        }
+    })
+}
+
+/// Returns the error response to a message that could not be received.
+///
+/// # Returns
+///
+/// The reply for a recoverable error, or the error itself if the connection
+/// cannot continue.
+pub(crate) fn error_reply(error: TransportError) -> Result<RpcMessage, TransportError> {
+    match error {
+        TransportError::Invalid(error) => Ok(RpcMessage::Response(error.to_response())),
+        error @ (TransportError::FrameTooLarge { .. } | TransportError::MalformedFrame(_)) => {
+            Ok(RpcMessage::Response(RpcResponse::failure(
+                RpcId::Null,
+                RpcError::invalid_request().with_data(json!(error.to_string())),
+            )))
+        }
+        error => Err(error),
+    }
+}
+
+// ===========================================================================
+// STRUCT: Dispatcher
+// ===
+
+/// Dispatches the messages read from one connection without holding up the
+/// thread reading them.
+///
+/// Requests and batches each run on their own scoped thread and send their
+/// reply when done; the transport serializes the replies. At most
+/// `MAX_CONCURRENT_REQUESTS` run at once, and requests beyond that are
+/// answered with `RpcError::server_busy()` rather than waited for, since the
+/// running handlers may themselves be waiting on messages this thread has
+/// yet to read. Notifications run one at a time, in the order they arrive,
+/// on a thread of their own.
+pub(crate) struct Dispatcher<'scope, 'env> {
+    scope: &'scope Scope<'scope, 'env>,
+    transport: &'env dyn Transport,
+    router: &'env RpcRouter,
+    context: &'env RpcContext,
+    active: Arc<AtomicUsize>,
+    notifications: mpsc::Sender<RpcMessage>,
+}
+
+impl<'scope, 'env> Dispatcher<'scope, 'env> {
+    /// Creates a dispatcher whose threads belong to `scope`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - The scope the handler threads run in
+    /// * `transport` - The connection replies are sent on
+    /// * `router` - The router dispatching every message
+    /// * `context` - The context every message is dispatched with
+    pub(crate) fn new(
+        scope: &'scope Scope<'scope, 'env>,
+        transport: &'env dyn Transport,
+        router: &'env RpcRouter,
+        context: &'env RpcContext,
+    ) -> Self {
+        let (notifications, queue) = mpsc::channel();
+        // Ends once the dispatcher is dropped and the queue is drained
+        scope.spawn(move || {
+            for message in queue {
+                router.handle_message_with_context(message, context);
+            }
+        });
+
+        Self {
+            scope,
+            transport,
+            router,
+            context,
+            active: Arc::new(AtomicUsize::new(0)),
+            notifications,
+        }
+    }
+
+    /// Starts handling an inbound message. Responses are ignored.
+    pub(crate) fn dispatch(&self, message: RpcMessage) {
+        match message {
+            RpcMessage::Notification(_) => {
+                let _ = self.notifications.send(message);
+            }
+            RpcMessage::Request(_) | RpcMessage::RequestBatch(_) => self.spawn(message),
+            RpcMessage::Response(_) | RpcMessage::ResponseBatch(_) => {}
+        }
+    }
+
+    /// Runs a request or batch on its own thread, unless too many are running.
+    fn spawn(&self, message: RpcMessage) {
+        if self.active.fetch_add(1, Ordering::SeqCst) >= MAX_CONCURRENT_REQUESTS {
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if let Some(reply) = busy_reply(&message) {
+                let _ = self.transport.send(reply);
+            }
+            return;
+        }
+
+        let (transport, router, context) = (self.transport, self.router, self.context);
+        let active = self.active.clone();
+        self.scope.spawn(move || {
+            if let Some(reply) = router.handle_message_with_context(message, context) {
+                let _ = transport.send(reply);
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Returns the reply refusing a request or batch because the connection has
+/// too many requests in progress.
+fn busy_reply(message: &RpcMessage) -> Option<RpcMessage> {
+    match message {
+        RpcMessage::Request(request) => {
+            let id = request.id()?.clone();
+            Some(RpcMessage::Response(RpcResponse::failure(
+                id,
+                RpcError::server_busy(),
+            )))
+        }
+        RpcMessage::RequestBatch(batch) => {
+            let refused = batch
+                .requests()
+                .iter()
+                .filter_map(|request| request.id())
+                .map(|id| RpcResponse::failure(id, RpcError::server_busy()));
+            let invalid = batch.invalid().iter().map(|error| error.to_response());
+            let responses: RpcBatchResponse = refused.chain(invalid).collect();
+            (!responses.is_empty()).then_some(RpcMessage::ResponseBatch(responses))
+        }
+        _ => None,
+    }
+}
+
+// ===========================================================================
+// TESTS: serve
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryTransport, RpcRequest};
+    use std::thread;
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("echo", |params: serde_json::Value| {
+            Ok::<_, RpcError>(params)
+        });
+        router
+    }
+
+    #[test]
+    fn test_serve_replies_to_requests() {
+        let (client, server) = MemoryTransport::pair();
+        let handle = thread::spawn(move || serve(&server, &router()));
+
+        let mut request = RpcRequest::new();
+        request
+            .set_method("echo".to_string())
+            .set_params(json!(["hi"]));
+        let mut notification = RpcRequest::notification();
+        notification.set_method("echo".to_string());
+
+        client.send(RpcMessage::from(notification)).unwrap();
+        client.send(RpcMessage::from(request.clone())).unwrap();
+
+        // The notification gets no reply, so the first reply is the request's
+        match client.receive().unwrap() {
+            Some(RpcMessage::Response(response)) => {
+                assert_eq!(response.id(), request.id().unwrap());
+                assert_eq!(response.result(), Some(&json!(["hi"])));
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+
+        // Inbound responses are ignored
+        client
+            .send(RpcMessage::from(RpcResponse::success(1, json!(1))))
+            .unwrap();
+
+        client.close().unwrap();
+        handle.join().unwrap().unwrap();
+        assert!(client.receive().unwrap().is_none());
+    }
+}
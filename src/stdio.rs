@@ -0,0 +1,164 @@
+use crate::{
+    FrameCodec, NdjsonCodec, RpcRouter, StreamTransport, Transport, TransportError, serve,
+};
+
+/// Serves requests over the process's stdin and stdout, framed as
+/// newline-delimited JSON, until stdin closes.
+///
+/// This is the main loop of a plugin spawned as a child process: requests
+/// are read from stdin, dispatched through the router, and their responses
+/// written to stdout. Stderr is never touched, so the plugin can log there
+/// freely. Handlers must not print to stdout, since that would corrupt the
+/// framing seen by the parent process.
+///
+/// When the parent closes the plugin's stdin, every response is flushed to
+/// stdout and the function returns `Ok(())`, letting `main()` exit cleanly.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching requests to handlers
+///
+/// # Returns
+///
+/// * `Ok(())` - Stdin was closed and every response has been flushed
+/// * `Err(TransportError)` - Stdin or stdout failed
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::{RpcError, RpcRouter, serve_stdio};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut router = RpcRouter::new();
+///     router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+///
+///     eprintln!("plugin ready");
+///     serve_stdio(&router)?;
+///     Ok(())
+/// }
+/// ```
+pub fn serve_stdio(router: &RpcRouter) -> Result<(), TransportError> {
+    serve_stdio_with_codec(router, NdjsonCodec::new())
+}
+
+/// Serves requests over the process's stdin and stdout, framed by `codec`,
+/// until stdin closes.
+///
+/// This is the same as `serve_stdio()` with a different framing format, such
+/// as `ContentLengthCodec` for Language Server Protocol peers.
+///
+/// # Arguments
+///
+/// * `router` - The router dispatching requests to handlers
+/// * `codec` - The framing format used in both directions
+///
+/// # Returns
+///
+/// * `Ok(())` - Stdin was closed and every response has been flushed
+/// * `Err(TransportError)` - Stdin or stdout failed
+pub fn serve_stdio_with_codec(
+    router: &RpcRouter,
+    codec: impl FrameCodec + 'static,
+) -> Result<(), TransportError> {
+    serve_until_eof(&StreamTransport::stdio(codec), router)
+}
+
+/// Serves a transport, then closes it so buffered output is flushed.
+fn serve_until_eof(transport: &dyn Transport, router: &RpcRouter) -> Result<(), TransportError> {
+    let result = serve(transport, router);
+    let closed = transport.close();
+    result.and(closed)
+}
+
+// ===========================================================================
+// TESTS: serve_stdio
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentLengthCodec, RpcError};
+    use serde_json::{Value as JsonValue, json};
+    use std::io::{self, Cursor, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// A writer that, like a piped stdout, only publishes output on flush.
+    #[derive(Clone, Default)]
+    struct BufferedOutput {
+        pending: Vec<u8>,
+        flushed: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for BufferedOutput {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.pending.write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed.lock().unwrap().append(&mut self.pending);
+            Ok(())
+        }
+    }
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        router
+    }
+
+    #[test]
+    fn test_shutdown_on_eof_flushes_output() {
+        let input = concat!(
+            "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[1,2],\"id\":1}\n",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[3,4]}\n",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[5,6],\"id\":2}\n",
+        );
+        let output = BufferedOutput::default();
+        let transport =
+            StreamTransport::new(Cursor::new(input), output.clone(), NdjsonCodec::new());
+
+        serve_until_eof(&transport, &router()).unwrap();
+
+        let flushed = output.flushed.lock().unwrap();
+        let lines: Vec<JsonValue> = String::from_utf8_lossy(&flushed)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                json!({"jsonrpc": "2.0", "result": 3, "id": 1}),
+                json!({"jsonrpc": "2.0", "result": 11, "id": 2}),
+            ]
+        );
+
+        // The transport is closed once serving ends
+        assert!(matches!(
+            transport.send(crate::RpcMessage::from(crate::RpcRequest::new())),
+            Err(TransportError::Closed)
+        ));
+    }
+
+    #[test]
+    fn test_content_length_framing() {
+        let body = "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[1,2],\"id\":1}";
+        let input = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let output = BufferedOutput::default();
+        let transport = StreamTransport::new(
+            Cursor::new(input),
+            output.clone(),
+            ContentLengthCodec::new(),
+        );
+
+        serve_until_eof(&transport, &router()).unwrap();
+
+        let flushed = output.flushed.lock().unwrap();
+        let text = String::from_utf8_lossy(&flushed);
+        let (headers, body) = text.split_once("\r\n\r\n").unwrap();
+        assert_eq!(headers, format!("Content-Length: {}", body.len()));
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(body).unwrap(),
+            json!({"jsonrpc": "2.0", "result": 3, "id": 1})
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{ContentLengthCodec, FrameCodec, NdjsonCodec, RpcMessage, Transport, TransportError};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::Mutex;
+
+// ===========================================================================
+// STRUCT: StreamTransport
+// ===
+
+/// A transport over a pair of byte streams, framed by a `FrameCodec`.
+///
+/// The reading and writing halves are locked separately, so one thread can
+/// wait in `receive()` while others `send()`. Closing the transport drops the
+/// writing half, which lets the peer see the end of the stream.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcMessage, StreamTransport, Transport};
+/// use std::io::Cursor;
+///
+/// let input = "{\"jsonrpc\":\"2.0\",\"method\":\"ping\",\"id\":1}\n";
+/// let transport = StreamTransport::ndjson(Cursor::new(input), Vec::new());
+///
+/// assert!(matches!(transport.receive().unwrap(), Some(RpcMessage::Request(_))));
+/// assert!(transport.receive().unwrap().is_none());
+/// ```
+pub struct StreamTransport {
+    reader: Mutex<Box<dyn BufRead + Send>>,
+    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    codec: Box<dyn FrameCodec>,
+}
+
+impl StreamTransport {
+    /// Creates a transport over a reader and a writer, framed by `codec`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream messages are read from
+    /// * `writer` - The stream messages are written to
+    /// * `codec` - The framing format used in both directions
+    pub fn new(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+        codec: impl FrameCodec + 'static,
+    ) -> Self {
+        Self {
+            reader: Mutex::new(Box::new(BufReader::new(reader))),
+            writer: Mutex::new(Some(Box::new(writer))),
+            codec: Box::new(codec),
+        }
+    }
+
+    /// Creates a transport framed as newline-delimited JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream messages are read from
+    /// * `writer` - The stream messages are written to
+    pub fn ndjson(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) -> Self {
+        Self::new(reader, writer, NdjsonCodec::new())
+    }
+
+    /// Creates a transport framed with `Content-Length` headers, as used by
+    /// the Language Server Protocol.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The stream messages are read from
+    /// * `writer` - The stream messages are written to
+    pub fn content_length(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> Self {
+        Self::new(reader, writer, ContentLengthCodec::new())
+    }
+
+    /// Creates a transport over the process's stdin and stdout, framed by
+    /// `codec`.
+    ///
+    /// Only stdout carries messages, so stderr stays free for logging. Nothing
+    /// else in the process may write to stdout while the transport is in use,
+    /// or the peer will see corrupted frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The framing format used in both directions
+    pub fn stdio(codec: impl FrameCodec + 'static) -> Self {
+        Self::new(io::stdin(), io::stdout(), codec)
+    }
+}
+
+impl Transport for StreamTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        match self.writer.lock().unwrap().as_mut() {
+            Some(writer) => Ok(self.codec.write_message(writer, &message)?),
+            None => Err(TransportError::Closed),
+        }
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        let mut reader = self.reader.lock().unwrap();
+        self.codec.read_message(&mut *reader)
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        if let Some(mut writer) = self.writer.lock().unwrap().take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for StreamTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamTransport")
+            .field("closed", &self.writer.lock().unwrap().is_none())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// TESTS: StreamTransport
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RpcError, RpcRouter, serve};
+    use serde_json::{Value as JsonValue, json};
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    /// A writer whose contents can be inspected after the transport is done.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn lines(&self) -> Vec<JsonValue> {
+            let bytes = self.0.lock().unwrap();
+            String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_serve_over_ndjson_stream() {
+        let input = concat!(
+            "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[1,2],\"id\":1}\n",
+            "not json\n",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[3,4]}\n",
+            "[{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[5,6],\"id\":\"b\"}]\n",
+        );
+        let output = SharedBuffer::default();
+        let transport = StreamTransport::ndjson(Cursor::new(input), output.clone());
+
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        serve(&transport, &router).unwrap();
+
+        // Requests run concurrently, so their replies may come in any order
+        let lines = output.lines();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.contains(&json!({"jsonrpc": "2.0", "result": 3, "id": 1})));
+        assert!(lines.contains(&json!([{"jsonrpc": "2.0", "result": 11, "id": "b"}])));
+        let error = lines
+            .iter()
+            .find(|line| line.get("error").is_some())
+            .unwrap();
+        assert_eq!(error["error"]["code"], RpcError::PARSE_ERROR);
+        assert_eq!(error["id"], JsonValue::Null);
+    }
+
+    #[test]
+    fn test_serve_over_content_length_stream() {
+        let body = "{\"jsonrpc\":\"2.0\",\"method\":\"add\",\"params\":[1,2],\"id\":1}";
+        let input = format!(
+            "Content-Length: oops\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+            body,
+            body.len(),
+            body
+        );
+        let output = SharedBuffer::default();
+        let transport = StreamTransport::content_length(Cursor::new(input), output.clone());
+
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        serve(&transport, &router).unwrap();
+
+        let bytes = output.0.lock().unwrap().clone();
+        let text = String::from_utf8(bytes).unwrap();
+        let bodies: Vec<JsonValue> = text
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|frame| serde_json::from_str(frame.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect();
+
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0]["error"]["code"], RpcError::INVALID_REQUEST);
+        assert_eq!(bodies[1], json!({"jsonrpc": "2.0", "result": 3, "id": 1}));
+    }
+
+    #[test]
+    fn test_send_after_close() {
+        let transport = StreamTransport::ndjson(Cursor::new(""), SharedBuffer::default());
+        transport.close().unwrap();
+
+        let message = RpcMessage::from(crate::RpcRequest::new());
+        assert!(matches!(
+            transport.send(message),
+            Err(TransportError::Closed)
+        ));
+    }
+}
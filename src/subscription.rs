@@ -0,0 +1,660 @@
+use crate::{
+    ClientError, IdGenerator, PendingCall, RpcClient, RpcContext, RpcError, RpcId, RpcRequest,
+    UuidIdGenerator,
+};
+use futures::StreamExt;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::stream::Stream;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value as JsonValue, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Identifies a subscription stream by its notification method and ID.
+type StreamKey = (String, RpcId);
+
+/// The params of a subscription notification.
+#[derive(Serialize)]
+struct SubscriptionItem<'a, T> {
+    subscription: &'a RpcId,
+    result: &'a T,
+}
+
+// ===========================================================================
+// STRUCT: SubscriptionManager
+// ===
+
+/// Tracks the subscriptions clients have opened on a server and pushes
+/// notifications to them.
+///
+/// Subscriptions follow the Ethereum convention: a subscribe method returns
+/// a fresh subscription ID, and every item is then pushed as a notification
+/// whose params are `{"subscription": id, "result": item}`. A manager covers
+/// one notification method; a server offering several kinds of
+/// subscriptions can share one manager or keep one per kind.
+///
+/// Subscribing needs a connection that can carry notifications back, which
+/// is any connection whose `RpcContext::client()` is set, such as on a
+/// `WebSocketServer` or an `RpcPeer`. Subscriptions whose connection has
+/// closed are dropped the next time the manager looks at them.
+///
+/// `SubscriptionManager` is cheap to clone; clones share the same
+/// subscriptions.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcContext, RpcError, RpcId, RpcRouter, SubscriptionManager};
+///
+/// let heads = SubscriptionManager::new("eth_subscription");
+/// let mut router = RpcRouter::new();
+///
+/// let manager = heads.clone();
+/// router.register_with_context("eth_subscribe", move |_: (String,), context: &RpcContext| {
+///     Ok::<_, RpcError>(manager.subscribe(context)?.id().clone())
+/// });
+/// let manager = heads.clone();
+/// router.register_with_context("eth_unsubscribe", move |(id,): (RpcId,), context: &RpcContext| {
+///     Ok::<_, RpcError>(manager.unsubscribe(context, &id))
+/// });
+///
+/// // Whenever a new block arrives
+/// heads.broadcast(&serde_json::json!({ "number": "0x1b4" }));
+/// ```
+#[derive(Clone)]
+pub struct SubscriptionManager {
+    state: Arc<ManagerState>,
+}
+
+/// The state shared by all clones of a manager.
+struct ManagerState {
+    method: String,
+    generator: Box<dyn IdGenerator>,
+    sinks: Mutex<HashMap<RpcId, SubscriptionSink>>,
+}
+
+impl SubscriptionManager {
+    /// Creates a manager whose subscription IDs are random UUIDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `notification_method` - The method of the notifications carrying
+    ///   items, such as `eth_subscription`
+    pub fn new(notification_method: impl Into<String>) -> Self {
+        Self::with_generator(notification_method, UuidIdGenerator)
+    }
+
+    /// Creates a manager that draws subscription IDs from the given
+    /// generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `notification_method` - The method of the notifications carrying
+    ///   items
+    /// * `generator` - The generator providing subscription IDs
+    pub fn with_generator(
+        notification_method: impl Into<String>,
+        generator: impl IdGenerator + 'static,
+    ) -> Self {
+        Self {
+            state: Arc::new(ManagerState {
+                method: notification_method.into(),
+                generator: Box::new(generator),
+                sinks: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the method of the notifications carrying items.
+    pub fn notification_method(&self) -> &str {
+        &self.state.method
+    }
+
+    /// Opens a subscription for the connection a request arrived on.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context of the subscribe request
+    ///
+    /// # Returns
+    ///
+    /// The sink pushing items to the new subscription, whose ID the
+    /// subscribe method returns. Connections that cannot carry notifications
+    /// back fail with "Method not found", so the handler can return the
+    /// error as-is.
+    pub fn subscribe(&self, context: &RpcContext) -> Result<SubscriptionSink, RpcError> {
+        let Some(client) = context.client() else {
+            return Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                "notifications are not supported on this connection",
+            ));
+        };
+
+        let sink = SubscriptionSink {
+            id: self.state.generator.next_id(),
+            method: self.state.method.clone(),
+            client: client.clone(),
+            active: Arc::new(AtomicBool::new(true)),
+        };
+
+        let mut sinks = self.state.sinks.lock().unwrap();
+        sinks.retain(|_, sink| !sink.is_closed());
+        sinks.insert(sink.id.clone(), sink.clone());
+        Ok(sink)
+    }
+
+    /// Cancels a subscription on behalf of the connection that opened it.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context of the unsubscribe request
+    /// * `id` - The ID of the subscription
+    ///
+    /// # Returns
+    ///
+    /// `true` if the subscription was cancelled, `false` if there is none
+    /// with this ID on the connection.
+    pub fn unsubscribe(&self, context: &RpcContext, id: &RpcId) -> bool {
+        let mut sinks = self.state.sinks.lock().unwrap();
+        let owned = match (sinks.get(id), context.client()) {
+            (Some(sink), Some(client)) => sink.client.same_connection(client),
+            _ => false,
+        };
+
+        match owned.then(|| sinks.remove(id)).flatten() {
+            Some(sink) => {
+                sink.active.store(false, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the sink of a subscription that is still open.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the subscription
+    pub fn get(&self, id: &RpcId) -> Option<SubscriptionSink> {
+        let sinks = self.state.sinks.lock().unwrap();
+        sinks.get(id).filter(|sink| !sink.is_closed()).cloned()
+    }
+
+    /// Pushes an item to every open subscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item to push
+    ///
+    /// # Returns
+    ///
+    /// The number of subscriptions the item was sent to. Subscriptions whose
+    /// connection has failed are dropped.
+    pub fn broadcast<T: Serialize>(&self, item: &T) -> usize {
+        let sinks: Vec<_> = self.state.sinks.lock().unwrap().values().cloned().collect();
+
+        let mut sent = 0;
+        for sink in sinks {
+            match sink.notify(item) {
+                Ok(()) => sent += 1,
+                Err(ClientError::Conversion(_)) => {}
+                Err(_) => {
+                    self.state.sinks.lock().unwrap().remove(sink.id());
+                }
+            }
+        }
+        sent
+    }
+
+    /// Returns the number of open subscriptions.
+    pub fn len(&self) -> usize {
+        let mut sinks = self.state.sinks.lock().unwrap();
+        sinks.retain(|_, sink| !sink.is_closed());
+        sinks.len()
+    }
+
+    /// Checks if there are no open subscriptions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for SubscriptionManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscriptionManager")
+            .field("notification_method", &self.state.method)
+            .field("subscriptions", &self.state.sinks.lock().unwrap().len())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: SubscriptionSink
+// ===
+
+/// Pushes items to one subscription, from any thread.
+///
+/// A sink is closed once the subscription has been cancelled or its
+/// connection has closed; pushing to it then fails with
+/// `ClientError::Disconnected`, which tells a producer to stop.
+#[derive(Clone)]
+pub struct SubscriptionSink {
+    id: RpcId,
+    method: String,
+    client: RpcClient,
+    active: Arc<AtomicBool>,
+}
+
+impl SubscriptionSink {
+    /// Returns the ID of the subscription.
+    pub fn id(&self) -> &RpcId {
+        &self.id
+    }
+
+    /// Pushes an item as a notification.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item, sent as the `result` of the notification
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the notification has been written to the connection.
+    /// Items that cannot be serialized fail with `ClientError::Conversion`.
+    pub fn notify<T: Serialize>(&self, item: &T) -> Result<(), ClientError> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(ClientError::Disconnected);
+        }
+
+        let params = serde_json::to_value(SubscriptionItem {
+            subscription: &self.id,
+            result: item,
+        })
+        .map_err(|error| invalid_item(RpcError::INVALID_PARAMS, &self.id, error))?;
+        self.client.notify(&self.method, params)
+    }
+
+    /// Checks if the subscription has been cancelled or its connection has
+    /// closed.
+    pub fn is_closed(&self) -> bool {
+        !self.active.load(Ordering::SeqCst) || self.client.is_closed()
+    }
+}
+
+impl fmt::Debug for SubscriptionSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscriptionSink")
+            .field("id", &self.id)
+            .field("method", &self.method)
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: Subscription
+// ===
+
+/// The client side of a subscription: a `Stream` of the items the server
+/// pushes.
+///
+/// Created by `RpcClient::subscribe()`. Items that do not match `T` are
+/// yielded as `ClientError::Conversion` without ending the stream. The
+/// stream ends when the connection closes. Outside of an async context, use
+/// `futures::executor::block_on_stream()` to iterate over it.
+///
+/// Dropping the subscription stops listening without telling the server;
+/// `unsubscribe()` also cancels it on the server.
+pub struct Subscription<T> {
+    id: RpcId,
+    method: String,
+    client: RpcClient,
+    receiver: UnboundedReceiver<JsonValue>,
+    items: PhantomData<fn() -> T>,
+}
+
+impl<T> Subscription<T> {
+    /// Starts listening for the notifications of a subscription.
+    pub(crate) fn open(client: RpcClient, method: String, id: RpcId) -> Self {
+        let receiver = client.subscriptions().open(&method, &id);
+        Self {
+            id,
+            method,
+            client,
+            receiver,
+            items: PhantomData,
+        }
+    }
+
+    /// Returns the ID the server assigned to the subscription.
+    pub fn id(&self) -> &RpcId {
+        &self.id
+    }
+
+    /// Stops listening and cancels the subscription on the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The unsubscribe method, such as `eth_unsubscribe`, called
+    ///   with the subscription ID as its only param
+    ///
+    /// # Returns
+    ///
+    /// A `PendingCall` resolving to the server's answer.
+    pub fn unsubscribe(self, method: &str) -> PendingCall {
+        self.client.call(method, json!([self.id]))
+    }
+}
+
+impl<T: DeserializeOwned> Stream for Subscription<T> {
+    type Item = Result<T, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let id = self.id.clone();
+        self.receiver.poll_next_unpin(cx).map(|item| {
+            item.map(|value| {
+                serde_json::from_value(value)
+                    .map_err(|error| invalid_item(RpcError::INTERNAL_ERROR, &id, error))
+            })
+        })
+    }
+}
+
+/// Builds the error of an item that did not convert to or from JSON.
+fn invalid_item(code: i64, id: &RpcId, error: serde_json::Error) -> ClientError {
+    ClientError::Conversion(RpcError::new(
+        code,
+        format!("Invalid item for subscription {}: {}", id, error),
+    ))
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.client.subscriptions().remove(&self.method, &self.id);
+    }
+}
+
+impl<T> fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .field("method", &self.method)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: SubscriptionStreams
+// ===
+
+/// Routes the subscription notifications a client receives to the streams
+/// listening for them.
+///
+/// A notification can arrive before the response to its subscribe call has
+/// been handled. While a subscribe call is in flight, notifications for
+/// unknown subscriptions on its notification method are kept, and handed to
+/// the stream it opens.
+#[derive(Default)]
+pub(crate) struct SubscriptionStreams {
+    state: Mutex<StreamsState>,
+}
+
+#[derive(Default)]
+struct StreamsState {
+    streams: HashMap<StreamKey, UnboundedSender<JsonValue>>,
+    subscribing: HashMap<String, usize>,
+    unclaimed: HashMap<StreamKey, Vec<JsonValue>>,
+    closed: bool,
+}
+
+impl SubscriptionStreams {
+    /// Routes a notification to the stream of its subscription.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the notification belongs to a subscription, or the
+    /// notification itself if it is meant for someone else.
+    pub(crate) fn dispatch(&self, notification: RpcRequest) -> Option<RpcRequest> {
+        let JsonValue::Object(params) = notification.params() else {
+            return Some(notification);
+        };
+        let Some(Ok(id)) = params
+            .get("subscription")
+            .map(|id| serde_json::from_value::<RpcId>(id.clone()))
+        else {
+            return Some(notification);
+        };
+        let item = params.get("result").cloned().unwrap_or(JsonValue::Null);
+        let key = (notification.method().to_string(), id);
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(sender) = state.streams.get(&key) {
+            if sender.unbounded_send(item).is_err() {
+                state.streams.remove(&key);
+            }
+            return None;
+        }
+        if state.subscribing.contains_key(&key.0) {
+            state.unclaimed.entry(key).or_default().push(item);
+            return None;
+        }
+        Some(notification)
+    }
+
+    /// Notes that a subscribe call for `method` is in flight.
+    pub(crate) fn begin(&self, method: &str) {
+        let mut state = self.state.lock().unwrap();
+        *state.subscribing.entry(method.to_string()).or_default() += 1;
+    }
+
+    /// Notes that a subscribe call for `method` has finished, dropping the
+    /// notifications no stream claimed once none is in flight any more.
+    pub(crate) fn end(&self, method: &str) {
+        let mut state = self.state.lock().unwrap();
+        let Some(count) = state.subscribing.get_mut(method) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            state.subscribing.remove(method);
+            state
+                .unclaimed
+                .retain(|(unclaimed, _), _| unclaimed != method);
+        }
+    }
+
+    /// Opens the stream of a subscription, starting with the notifications
+    /// that arrived before it.
+    pub(crate) fn open(&self, method: &str, id: &RpcId) -> UnboundedReceiver<JsonValue> {
+        let (sender, receiver) = mpsc::unbounded();
+        let key = (method.to_string(), id.clone());
+
+        let mut state = self.state.lock().unwrap();
+        for item in state.unclaimed.remove(&key).unwrap_or_default() {
+            let _ = sender.unbounded_send(item);
+        }
+        if !state.closed {
+            state.streams.insert(key, sender);
+        }
+        receiver
+    }
+
+    /// Stops routing notifications to a subscription's stream.
+    pub(crate) fn remove(&self, method: &str, id: &RpcId) {
+        let key = (method.to_string(), id.clone());
+        self.state.lock().unwrap().streams.remove(&key);
+    }
+
+    /// Ends every stream, because the connection has closed.
+    pub(crate) fn close_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.streams.clear();
+        state.unclaimed.clear();
+    }
+}
+
+/// Keeps notifications for a subscription that is being opened until its
+/// subscribe call finishes, successfully or not.
+pub(crate) struct Subscribing {
+    client: RpcClient,
+    method: String,
+}
+
+impl Subscribing {
+    /// Starts keeping notifications for `method`.
+    pub(crate) fn new(client: &RpcClient, method: &str) -> Self {
+        client.subscriptions().begin(method);
+        Self {
+            client: client.clone(),
+            method: method.to_string(),
+        }
+    }
+}
+
+impl Drop for Subscribing {
+    fn drop(&mut self) {
+        self.client.subscriptions().end(&self.method);
+    }
+}
+
+// ===========================================================================
+// TESTS: Subscriptions
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MemoryTransport, RpcMessage, RpcPeer, RpcResponse, RpcRouter, SequentialIdGenerator,
+        Transport,
+    };
+    use futures::executor::{self, block_on_stream};
+    use serde::Deserialize;
+    use std::sync::mpsc as std_mpsc;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Head {
+        number: u64,
+    }
+
+    /// A server offering `subscribe`/`unsubscribe` for new heads, which
+    /// reports every new subscription's sink.
+    fn server(heads: &SubscriptionManager) -> (RpcRouter, std_mpsc::Receiver<SubscriptionSink>) {
+        let (opened, sinks) = std_mpsc::channel();
+        let opened = Mutex::new(opened);
+        let mut router = RpcRouter::new();
+
+        let manager = heads.clone();
+        router.register_with_context("subscribe", move |_: (String,), context: &RpcContext| {
+            let sink = manager.subscribe(context)?;
+            opened.lock().unwrap().send(sink.clone()).unwrap();
+            Ok::<_, RpcError>(sink.id().clone())
+        });
+        let manager = heads.clone();
+        router.register_with_context(
+            "unsubscribe",
+            move |(id,): (RpcId,), context: &RpcContext| {
+                Ok::<_, RpcError>(manager.unsubscribe(context, &id))
+            },
+        );
+        (router, sinks)
+    }
+
+    #[test]
+    fn test_items_stream_until_unsubscribed() {
+        let heads = SubscriptionManager::with_generator("heads", SequentialIdGenerator::new());
+        let (router, sinks) = server(&heads);
+        let (left, right) = MemoryTransport::pair();
+        let _server = RpcPeer::new(right, router);
+        let client = RpcClient::connect(left);
+
+        let subscription =
+            executor::block_on(client.subscribe::<_, Head>("subscribe", &("newHeads",), "heads"))
+                .unwrap();
+        assert_eq!(subscription.id(), &RpcId::Number(1));
+        let sink = sinks.recv().unwrap();
+
+        assert_eq!(heads.broadcast(&Head { number: 1 }), 1);
+        sink.notify(&Head { number: 2 }).unwrap();
+        sink.notify(&json!({ "number": "three" })).unwrap();
+        sink.notify(&Head { number: 4 }).unwrap();
+
+        let mut items = block_on_stream(subscription);
+        assert_eq!(items.next().unwrap().unwrap(), Head { number: 1 });
+        assert_eq!(items.next().unwrap().unwrap(), Head { number: 2 });
+        assert!(matches!(
+            items.next().unwrap(),
+            Err(ClientError::Conversion(_))
+        ));
+        assert_eq!(items.next().unwrap().unwrap(), Head { number: 4 });
+
+        // Cancelling closes the sink, so the producer knows to stop
+        let subscription = items.into_inner();
+        assert_eq!(
+            subscription.unsubscribe("unsubscribe").wait().unwrap(),
+            json!(true)
+        );
+        assert!(sink.is_closed());
+        assert!(matches!(
+            sink.notify(&Head { number: 5 }),
+            Err(ClientError::Disconnected)
+        ));
+        assert!(heads.is_empty());
+        assert_eq!(heads.broadcast(&Head { number: 6 }), 0);
+    }
+
+    #[test]
+    fn test_notifications_sent_before_the_response_are_kept() {
+        let (left, right) = MemoryTransport::pair();
+        let client = RpcClient::connect(left);
+        let subscribing = client.subscribe::<_, u64>("subscribe", &json!([]), "tick");
+
+        // The server pushes an item before answering the subscribe call
+        let Some(RpcMessage::Request(request)) = right.receive().unwrap() else {
+            panic!("expected a request");
+        };
+        let mut notification = RpcRequest::notification();
+        notification
+            .set_method("tick".to_string())
+            .set_params(json!({ "subscription": "a", "result": 1 }));
+        right.send(RpcMessage::from(notification)).unwrap();
+        let response = RpcResponse::success(request.id().unwrap().clone(), json!("a"));
+        right.send(RpcMessage::from(response)).unwrap();
+
+        let subscription = executor::block_on(subscribing).unwrap();
+        let mut items = block_on_stream(subscription);
+        assert_eq!(items.next().unwrap().unwrap(), 1);
+
+        // The stream ends with the connection
+        drop(right);
+        assert!(items.next().is_none());
+    }
+
+    #[test]
+    fn test_subscribing_needs_a_client() {
+        let heads = SubscriptionManager::new("heads");
+        let error = heads.subscribe(&RpcContext::new()).unwrap_err();
+        assert_eq!(error.code(), RpcError::METHOD_NOT_FOUND);
+
+        // Another connection cannot cancel a subscription it does not own
+        let (left, _right) = MemoryTransport::pair();
+        let mut owner = RpcContext::new();
+        owner.set_client(RpcClient::connect(left));
+        let sink = heads.subscribe(&owner).unwrap();
+        assert_eq!(sink.id().as_str().unwrap().len(), 36);
+
+        let (other, _right) = MemoryTransport::pair();
+        let mut stranger = RpcContext::new();
+        stranger.set_client(RpcClient::connect(other));
+        assert!(!heads.unsubscribe(&stranger, sink.id()));
+        assert!(heads.unsubscribe(&owner, sink.id()));
+        assert!(heads.get(sink.id()).is_none());
+    }
+}
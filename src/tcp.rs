@@ -0,0 +1,475 @@
+use crate::codec::CodecFactory;
+use crate::connections::{Connections, SharedSocket, accept_next};
+use crate::{
+    FrameCodec, NdjsonCodec, RpcContext, RpcMessage, RpcRouter, StreamTransport, Transport,
+    TransportError, serve_with_context,
+};
+use std::fmt;
+use std::io;
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs,
+};
+use std::sync::Arc;
+use std::thread;
+
+// ===========================================================================
+// STRUCT: TcpTransport
+// ===
+
+/// A transport over a TCP connection, framed by a `FrameCodec`.
+///
+/// Use `connect()` on the client side. `TcpServer` creates one for every
+/// connection it accepts. Closing the transport shuts down the sending half
+/// of the socket, so the peer sees the end of the stream while responses
+/// still in flight can be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::{RpcClient, TcpTransport};
+/// use serde_json::json;
+///
+/// let client = RpcClient::connect(TcpTransport::connect("127.0.0.1:4000")?);
+/// let sum = client.call("add", json!([2, 3])).wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TcpTransport {
+    stream: StreamTransport,
+    socket: Arc<TcpStream>,
+    peer_addr: SocketAddr,
+}
+
+impl TcpTransport {
+    /// Connects to a server framed as newline-delimited JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the server
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented connecting.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::connect_with_codec(addr, NdjsonCodec::new())
+    }
+
+    /// Connects to a server framed by `codec`.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the server
+    /// * `codec` - The framing format used in both directions
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented connecting.
+    pub fn connect_with_codec(
+        addr: impl ToSocketAddrs,
+        codec: impl FrameCodec + 'static,
+    ) -> io::Result<Self> {
+        Self::new(TcpStream::connect(addr)?, codec)
+    }
+
+    /// Creates a transport over an established connection.
+    ///
+    /// Nagle's algorithm is disabled on the socket, since each message is
+    /// written in one piece and should be sent right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The connected socket
+    /// * `codec` - The framing format used in both directions
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error raised while setting up the socket.
+    pub fn new(socket: TcpStream, codec: impl FrameCodec + 'static) -> io::Result<Self> {
+        let peer_addr = socket.peer_addr()?;
+        socket.set_nodelay(true)?;
+        let socket = Arc::new(socket);
+
+        Ok(Self {
+            stream: StreamTransport::new(
+                SharedSocket(socket.clone()),
+                SharedSocket(socket.clone()),
+                codec,
+            ),
+            socket,
+            peer_addr,
+        })
+    }
+
+    /// Returns the address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Returns the local address of the connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        self.stream.send(message)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        self.stream.receive()
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        self.stream.close()?;
+        match self.socket.shutdown(Shutdown::Write) {
+            // The peer may already have closed the connection
+            Err(error) if error.kind() == io::ErrorKind::NotConnected => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
+
+impl fmt::Debug for TcpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpTransport")
+            .field("peer_addr", &self.peer_addr)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: TcpServer
+// ===
+
+/// Accepts TCP connections and serves requests on each of them.
+///
+/// Every connection runs as its own session on its own thread: requests are
+/// dispatched through the shared router with an `RpcContext` holding the
+/// peer's address, and responses go back on the same connection. Sessions are
+/// independent, so request IDs only need to be unique per connection.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcClient, RpcContext, RpcError, RpcRouter, TcpServer, TcpTransport};
+/// use serde_json::json;
+/// use std::thread;
+///
+/// let mut router = RpcRouter::new();
+/// router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+/// router.register_with_context("whoami", |_: (), context: &RpcContext| {
+///     Ok::<_, RpcError>(context.peer_addr().unwrap().to_string())
+/// });
+///
+/// let server = TcpServer::bind("127.0.0.1:0").unwrap();
+/// let addr = server.local_addr().unwrap();
+/// let handle = server.handle().unwrap();
+/// let running = thread::spawn(move || server.serve(&router));
+///
+/// let transport = TcpTransport::connect(addr).unwrap();
+/// let local_addr = transport.local_addr().unwrap();
+/// let client = RpcClient::connect(transport);
+///
+/// assert_eq!(client.call("add", json!([2, 3])).wait().unwrap(), json!(5));
+/// assert_eq!(client.call("whoami", json!(null)).wait().unwrap(), json!(local_addr.to_string()));
+///
+/// handle.shutdown();
+/// running.join().unwrap().unwrap();
+/// ```
+pub struct TcpServer {
+    listener: TcpListener,
+    codec: Box<CodecFactory>,
+    connections: Arc<Connections<TcpStream>>,
+}
+
+impl TcpServer {
+    /// Binds a server framed as newline-delimited JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on; port 0 picks a free port
+    ///
+    /// # Returns
+    ///
+    /// The server, or the error that prevented binding the address.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::from_listener(TcpListener::bind(addr)?))
+    }
+
+    /// Creates a server accepting connections from an existing listener.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The bound listener
+    pub fn from_listener(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            codec: Box::new(|| Box::new(NdjsonCodec::new())),
+            connections: Arc::new(Connections::new()),
+        }
+    }
+
+    /// Sets the framing format used on every connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec; each connection gets its own copy
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_codec<C>(&mut self, codec: C) -> &mut Self
+    where
+        C: FrameCodec + Clone + 'static,
+    {
+        self.codec = Box::new(move || Box::new(codec.clone()));
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a handle that can shut the server down from another thread.
+    pub fn handle(&self) -> io::Result<TcpServerHandle> {
+        Ok(TcpServerHandle::new(
+            self.local_addr()?,
+            self.connections.clone(),
+        ))
+    }
+
+    /// Accepts connections and serves them until the server is shut down.
+    ///
+    /// Each connection is served on its own thread. Errors on a single
+    /// connection only end that session.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router dispatching requests on every connection
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The server was shut down and every session has ended
+    /// * `Err(io::Error)` - Accepting connections failed
+    pub fn serve(&self, router: &RpcRouter) -> io::Result<()> {
+        thread::scope(|scope| {
+            let result = loop {
+                let socket = match accept_next(|| self.listener.accept()) {
+                    Ok((socket, _)) => socket,
+                    Err(error) => break Err(error),
+                };
+
+                // The peer may already be gone, which only affects this connection
+                let Ok(transport) = TcpTransport::new(socket, (self.codec)()) else {
+                    continue;
+                };
+                let Some(id) = self.connections.track(&transport.socket) else {
+                    break Ok(());
+                };
+
+                let connections = self.connections.clone();
+                scope.spawn(move || {
+                    let mut context = RpcContext::new();
+                    context.set_peer_addr(transport.peer_addr());
+
+                    let _ = serve_with_context(&transport, router, &context);
+                    let _ = transport.close();
+                    connections.untrack(id);
+                });
+            };
+
+            self.connections.close_all();
+            result
+        })
+    }
+}
+
+impl fmt::Debug for TcpServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpServer")
+            .field("local_addr", &self.listener.local_addr().ok())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: TcpServerHandle
+// ===
+
+/// Shuts down a running `TcpServer` or `HttpServer`.
+#[derive(Clone, Debug)]
+pub struct TcpServerHandle {
+    addr: SocketAddr,
+    connections: Arc<Connections<TcpStream>>,
+}
+
+impl TcpServerHandle {
+    /// Creates a handle for a server listening on `addr`.
+    pub(crate) fn new(addr: SocketAddr, connections: Arc<Connections<TcpStream>>) -> Self {
+        Self { addr, connections }
+    }
+
+    /// Stops accepting connections and closes every open connection.
+    ///
+    /// The server's `serve()` returns once every session has ended.
+    pub fn shutdown(&self) {
+        self.connections.close_all();
+
+        // Wake the accept loop with a connection it will immediately drop
+        let mut addr = self.addr;
+        match addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+            IpAddr::V6(ip) if ip.is_unspecified() => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+            _ => {}
+        }
+        let _ = TcpStream::connect(addr);
+    }
+
+    /// Returns the number of connections currently being served.
+    pub fn connection_count(&self) -> usize {
+        self.connections.count()
+    }
+}
+
+// ===========================================================================
+// TESTS: TcpServer
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientError, LengthPrefixedCodec, RpcClient, RpcError};
+    use serde_json::json;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("echo", |params: serde_json::Value| {
+            Ok::<_, RpcError>(params)
+        });
+        router.register_with_context("whoami", |_: (), context: &RpcContext| {
+            Ok::<_, RpcError>(context.peer_addr().map(|addr| addr.to_string()))
+        });
+        router
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_sessions_per_connection() {
+        let server = TcpServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            let first = TcpTransport::connect(addr).unwrap();
+            let second = TcpTransport::connect(addr).unwrap();
+            let (first_addr, second_addr) =
+                (first.local_addr().unwrap(), second.local_addr().unwrap());
+            let first = RpcClient::connect(first);
+            let second = RpcClient::connect(second);
+
+            // Both clients number their requests from 1, so the IDs collide
+            // across connections but each session answers its own
+            let a = first.call("echo", json!(["first"]));
+            let b = second.call("echo", json!(["second"]));
+            assert_eq!(a.id(), b.id());
+            assert_eq!(b.wait().unwrap(), json!(["second"]));
+            assert_eq!(a.wait().unwrap(), json!(["first"]));
+
+            // Handlers see the address of their own peer
+            let whoami = |client: &RpcClient| client.call("whoami", json!(null)).wait().unwrap();
+            assert_eq!(whoami(&first), json!(first_addr.to_string()));
+            assert_eq!(whoami(&second), json!(second_addr.to_string()));
+            assert_eq!(handle.connection_count(), 2);
+
+            // Closing a client ends its session only
+            first.close();
+            wait_for(|| handle.connection_count() == 1);
+            assert_eq!(whoami(&second), json!(second_addr.to_string()));
+
+            // Shutting down closes the remaining connections
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+            wait_for(|| second.is_closed());
+            assert!(matches!(
+                second.call("echo", json!([])).wait(),
+                Err(ClientError::Disconnected)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_slow_requests_can_be_cancelled() {
+        let (started, starts) = mpsc::channel();
+        let (stopped, stops) = mpsc::channel();
+        let mut router = router();
+        router.register_with_context("index", move |_: (), context: &RpcContext| {
+            started.send(()).unwrap();
+            while !context.cancellation().is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            stopped.send(()).unwrap();
+            context.cancellation().check()?;
+            Ok::<_, RpcError>(())
+        });
+
+        let server = TcpServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+            let client = RpcClient::connect(TcpTransport::connect(addr).unwrap());
+
+            // Other requests are answered while the slow one runs
+            let call = client.call("index", json!(null));
+            starts.recv().unwrap();
+            assert_eq!(client.call("echo", json!([1])).wait().unwrap(), json!([1]));
+
+            // Dropping the call sends `$/cancelRequest`, which stops the handler
+            drop(call);
+            stops.recv_timeout(Duration::from_secs(5)).unwrap();
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_length_prefixed_framing() {
+        let mut server = TcpServer::bind("127.0.0.1:0").unwrap();
+        server.set_codec(LengthPrefixedCodec::new());
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            let transport = TcpTransport::connect_with_codec(addr, LengthPrefixedCodec::new());
+            let client = RpcClient::connect(transport.unwrap());
+            let text = "spans\nlines";
+            assert_eq!(
+                client.call("echo", json!([text])).wait().unwrap(),
+                json!([text])
+            );
+
+            client.close();
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{RpcMessage, ValidationError};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::process::ExitStatus;
+use std::sync::Arc;
+
+// ===========================================================================
+// TRAIT: Transport
+// ===
+
+/// A connection that carries JSON-RPC messages in both directions.
+///
+/// Clients and servers are written against this trait, so the same code runs
+/// over stdio, sockets, HTTP or in-memory channels. A transport is shared
+/// between the thread reading from it and the threads writing to it, so both
+/// methods take `&self` and may be called concurrently.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{MemoryTransport, RpcMessage, RpcRequest, Transport};
+///
+/// let (client, server) = MemoryTransport::pair();
+/// client.send(RpcMessage::from(RpcRequest::new())).unwrap();
+///
+/// let received = server.receive().unwrap();
+/// assert!(matches!(received, Some(RpcMessage::Request(_))));
+/// ```
+pub trait Transport: Send + Sync {
+    /// Sends a message to the peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to send
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once the message has been handed to the connection.
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError>;
+
+    /// Waits for the next message from the peer.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(RpcMessage))` - The next message
+    /// * `Ok(None)` - The peer closed the connection
+    /// * `Err(TransportError)` - The message could not be read; see
+    ///   `TransportError::is_recoverable()` for whether reading may continue
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError>;
+
+    /// Closes the sending side of the connection.
+    ///
+    /// The peer sees the connection as closed once it has received every
+    /// message sent before. The default implementation does nothing.
+    fn close(&self) -> Result<(), TransportError> {
+        Ok(())
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        (**self).send(message)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        (**self).receive()
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        (**self).close()
+    }
+}
+
+// ===========================================================================
+// ENUM: TransportError
+// ===
+
+/// Describes why a transport failed to send or receive a message.
+#[derive(Debug)]
+pub enum TransportError {
+    /// The underlying connection failed.
+    Io(io::Error),
+
+    /// A message was received, but it is not valid JSON-RPC 2.0. The
+    /// connection is still usable and the peer should be sent
+    /// `error.to_response()`.
+    Invalid(ValidationError),
+
+    /// A frame was larger than the codec's limit and has been skipped. The
+    /// connection is still usable.
+    FrameTooLarge {
+        /// The maximum frame size in bytes.
+        limit: usize,
+    },
+
+    /// A frame's framing was invalid, such as a bad header, and the frame has
+    /// been skipped. The connection is still usable.
+    MalformedFrame(String),
+
+    /// The connection has been closed.
+    Closed,
+
+    /// The process at the other end of the connection has exited.
+    ProcessExited(ExitStatus),
+}
+
+impl TransportError {
+    /// Checks if the transport can still be used after this error.
+    ///
+    /// # Returns
+    ///
+    /// `true` if only the current message was lost, `false` if the
+    /// connection itself is broken.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            TransportError::Invalid(_)
+                | TransportError::FrameTooLarge { .. }
+                | TransportError::MalformedFrame(_)
+        )
+    }
+}
+
+impl From<io::Error> for TransportError {
+    fn from(error: io::Error) -> Self {
+        TransportError::Io(error)
+    }
+}
+
+impl From<ValidationError> for TransportError {
+    fn from(error: ValidationError) -> Self {
+        TransportError::Invalid(error)
+    }
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Io(error) => write!(f, "transport I/O error: {}", error),
+            TransportError::Invalid(error) => write!(f, "invalid message: {}", error),
+            TransportError::FrameTooLarge { limit } => {
+                write!(f, "frame exceeds the limit of {} bytes", limit)
+            }
+            TransportError::MalformedFrame(reason) => write!(f, "malformed frame: {}", reason),
+            TransportError::Closed => write!(f, "transport is closed"),
+            TransportError::ProcessExited(status) => write!(f, "process exited ({})", status),
+        }
+    }
+}
+
+impl Error for TransportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TransportError::Io(error) => Some(error),
+            TransportError::Invalid(error) => Some(error),
+            TransportError::FrameTooLarge { .. }
+            | TransportError::MalformedFrame(_)
+            | TransportError::Closed
+            | TransportError::ProcessExited(_) => None,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+// ===========================================================================
+// STRUCT: TypeMismatch
+// ===
+
+/// Describes where and why a JSON value did not match the expected type.
+///
+/// This is an internal helper for the typed accessors of `RpcRequest` and
+/// `RpcResponse`, which turn it into an `RpcError` carrying the context they know.
+pub(crate) struct TypeMismatch {
+    /// The path of the offending field, such as `transaction.amount`, or `.` for the value itself.
+    pub(crate) field: String,
+
+    /// The reason reported by serde, such as `invalid type: string "x", expected u64`.
+    pub(crate) reason: String,
+}
+
+/// Deserializes a JSON value into a typed value, tracking the offending field.
+///
+/// # Arguments
+///
+/// * `value` - The JSON value to convert
+///
+/// # Returns
+///
+/// The typed value, or a `TypeMismatch` naming the field that failed.
+pub(crate) fn from_value<T: DeserializeOwned>(value: &JsonValue) -> Result<T, TypeMismatch> {
+    serde_path_to_error::deserialize(value).map_err(|error| TypeMismatch {
+        field: error.path().to_string(),
+        reason: error.into_inner().to_string(),
+    })
+}
+
+/// Serializes a typed value into a JSON value.
+///
+/// # Arguments
+///
+/// * `value` - The value to convert
+///
+/// # Returns
+///
+/// The JSON value, or a `TypeMismatch` naming the field that failed.
+pub(crate) fn to_value<T: Serialize>(value: &T) -> Result<JsonValue, TypeMismatch> {
+    serde_path_to_error::serialize(value, serde_json::value::Serializer).map_err(|error| {
+        TypeMismatch {
+            field: error.path().to_string(),
+            reason: error.into_inner().to_string(),
+        }
+    })
+}
@@ -0,0 +1,803 @@
+use crate::codec::CodecFactory;
+use crate::connections::{Connections, SharedSocket, accept_next};
+use crate::{
+    FrameCodec, NdjsonCodec, PeerCredentials, RpcContext, RpcMessage, RpcRouter, StreamTransport,
+    Transport, TransportError, serve_with_context,
+};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{SocketAddr, UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+// ===========================================================================
+// STRUCT: UnixTransport
+// ===
+
+/// A transport over a Unix domain socket, framed by a `FrameCodec`.
+///
+/// Use `connect()` on the client side. `UnixServer` creates one for every
+/// connection it accepts. The credentials of the process on the other end are
+/// looked up when the transport is created and can be read with
+/// `peer_credentials()`.
+///
+/// Closing the transport shuts down the sending half of the socket, so the
+/// peer sees the end of the stream while responses still in flight can be
+/// read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::{RpcClient, UnixTransport};
+/// use serde_json::json;
+///
+/// let client = RpcClient::connect(UnixTransport::connect("/run/my-daemon.sock")?);
+/// let sum = client.call("add", json!([2, 3])).wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct UnixTransport {
+    stream: StreamTransport,
+    socket: Arc<UnixStream>,
+    peer_credentials: Option<PeerCredentials>,
+}
+
+impl UnixTransport {
+    /// Connects to a server framed as newline-delimited JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the server's socket
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented connecting.
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::connect_with_codec(path, NdjsonCodec::new())
+    }
+
+    /// Connects to a server framed by `codec`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the server's socket
+    /// * `codec` - The framing format used in both directions
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented connecting.
+    pub fn connect_with_codec(
+        path: impl AsRef<Path>,
+        codec: impl FrameCodec + 'static,
+    ) -> io::Result<Self> {
+        Self::new(UnixStream::connect(path)?, codec)
+    }
+
+    /// Creates a transport over an established connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The connected socket
+    /// * `codec` - The framing format used in both directions
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error raised while setting up the socket.
+    pub fn new(socket: UnixStream, codec: impl FrameCodec + 'static) -> io::Result<Self> {
+        // Credentials are optional: some platforms cannot report them
+        let peer_credentials = peer_credentials(&socket).ok();
+
+        let socket = Arc::new(socket);
+
+        Ok(Self {
+            stream: StreamTransport::new(
+                SharedSocket(socket.clone()),
+                SharedSocket(socket.clone()),
+                codec,
+            ),
+            socket,
+            peer_credentials,
+        })
+    }
+
+    /// Returns the credentials of the peer process, if the operating system
+    /// reports them.
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.peer_credentials
+    }
+}
+
+impl Transport for UnixTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        self.stream.send(message)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        self.stream.receive()
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        self.stream.close()?;
+        match self.socket.shutdown(Shutdown::Write) {
+            // The peer may already have closed the connection
+            Err(error) if error.kind() == io::ErrorKind::NotConnected => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
+
+impl fmt::Debug for UnixTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnixTransport")
+            .field("peer_credentials", &self.peer_credentials)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+/// Asks the kernel for the credentials of the process on the other end.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_credentials(socket: &UnixStream) -> io::Result<PeerCredentials> {
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: the descriptor is open for the lifetime of `socket`, and the
+    // kernel writes at most `length` bytes into `credentials`
+    let result = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut credentials as *mut libc::ucred).cast(),
+            &mut length,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // A pid of 0 means the peer is in another pid namespace
+    let pid = u32::try_from(credentials.pid).ok().filter(|&pid| pid != 0);
+    Ok(PeerCredentials::new(credentials.uid, credentials.gid, pid))
+}
+
+/// Asks the kernel for the credentials of the process on the other end.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+fn peer_credentials(socket: &UnixStream) -> io::Result<PeerCredentials> {
+    let mut uid = 0;
+    let mut gid = 0;
+
+    // SAFETY: the descriptor is open for the lifetime of `socket`, and both
+    // pointers are to live locals
+    let result = unsafe { libc::getpeereid(socket.as_raw_fd(), &mut uid, &mut gid) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(PeerCredentials::new(uid, gid, None))
+}
+
+/// Reports that this platform cannot look up peer credentials.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+)))]
+fn peer_credentials(_socket: &UnixStream) -> io::Result<PeerCredentials> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// ===========================================================================
+// STRUCT: UnixSocketOptions
+// ===
+
+/// Controls how `UnixServer::bind_with_options()` creates its socket file.
+///
+/// By default the file gets the permissions allowed by the process umask, is
+/// owned by the process, and a stale file left behind by a crashed server is
+/// removed before binding.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::UnixSocketOptions;
+///
+/// let mut options = UnixSocketOptions::new();
+/// options.set_mode(0o660).set_group(100);
+///
+/// assert_eq!(options.mode(), Some(0o660));
+/// assert_eq!(options.owner(), None);
+/// assert!(options.remove_stale());
+/// ```
+#[derive(Clone, Debug)]
+pub struct UnixSocketOptions {
+    mode: Option<u32>,
+    owner: Option<u32>,
+    group: Option<u32>,
+    remove_stale: bool,
+}
+
+impl UnixSocketOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self {
+            mode: None,
+            owner: None,
+            group: None,
+            remove_stale: true,
+        }
+    }
+
+    /// Returns the permission bits set on the socket file, if any.
+    pub fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// Sets the permission bits of the socket file.
+    ///
+    /// Connecting to a socket requires write permission on it, so `0o600`
+    /// restricts the server to processes of the same user and `0o660` to
+    /// members of its group.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The permission bits, as for `chmod`
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Returns the user ID the socket file is given to, if any.
+    pub fn owner(&self) -> Option<u32> {
+        self.owner
+    }
+
+    /// Sets the user owning the socket file.
+    ///
+    /// Changing the owner usually requires root privileges.
+    ///
+    /// # Arguments
+    ///
+    /// * `uid` - The user ID, as for `chown`
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_owner(&mut self, uid: u32) -> &mut Self {
+        self.owner = Some(uid);
+        self
+    }
+
+    /// Returns the group ID the socket file is given to, if any.
+    pub fn group(&self) -> Option<u32> {
+        self.group
+    }
+
+    /// Sets the group owning the socket file.
+    ///
+    /// # Arguments
+    ///
+    /// * `gid` - The group ID, as for `chgrp`
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_group(&mut self, gid: u32) -> &mut Self {
+        self.group = Some(gid);
+        self
+    }
+
+    /// Returns whether a stale socket file is removed before binding.
+    pub fn remove_stale(&self) -> bool {
+        self.remove_stale
+    }
+
+    /// Sets whether a stale socket file is removed before binding.
+    ///
+    /// A socket file is stale when no server accepts connections on it. Only
+    /// sockets are ever removed: if the path holds any other kind of file,
+    /// binding fails and the file is left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `remove_stale` - Whether to remove a stale socket file
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_remove_stale(&mut self, remove_stale: bool) -> &mut Self {
+        self.remove_stale = remove_stale;
+        self
+    }
+}
+
+impl Default for UnixSocketOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the socket file at `path` if no server is listening on it.
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        ));
+    }
+
+    match UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("a server is already listening on {}", path.display()),
+        )),
+        Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+            match fs::remove_file(path) {
+                // Another process may have removed it first
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Applies the ownership and permission options to a new socket file.
+fn apply_options(path: &Path, options: &UnixSocketOptions) -> io::Result<()> {
+    if options.owner.is_some() || options.group.is_some() {
+        std::os::unix::fs::chown(path, options.owner, options.group)?;
+    }
+    if let Some(mode) = options.mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+/// Binds a listener whose socket file already has the ownership and
+/// permissions of `options` when it appears at `path`.
+///
+/// The socket is bound in a private directory next to `path`, which only
+/// this user can enter, and linked into place once the options are applied.
+/// Linking never replaces a file created at `path` in the meantime.
+fn bind_private(path: &Path, options: &UnixSocketOptions) -> io::Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // Kept short, since socket paths are limited to about a hundred bytes
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let dir = parent.join(format!(".ink-rpc-{}", &suffix[..12]));
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+    let staged = dir.join("s");
+    let result = UnixListener::bind(&staged).and_then(|listener| {
+        apply_options(&staged, options)?;
+        match fs::hard_link(&staged, path) {
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} already exists", path.display()),
+            )),
+            result => result.map(|_| listener),
+        }
+    });
+
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&dir);
+    result
+}
+
+// ===========================================================================
+// STRUCT: UnixServer
+// ===
+
+/// Accepts Unix domain socket connections and serves requests on each of them.
+///
+/// Every connection runs as its own session on its own thread, like
+/// `TcpServer`. The `RpcContext` of each session holds the credentials of
+/// the connecting process, which handlers can check with
+/// `RpcContext::peer_credentials()` before doing anything privileged.
+///
+/// A server created with `bind()` owns its socket file and removes it when
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcClient, RpcContext, RpcError, RpcRouter, UnixServer, UnixTransport};
+/// use serde_json::json;
+/// use std::thread;
+///
+/// let mut router = RpcRouter::new();
+/// router.register_with_context("whoami", |_: (), context: &RpcContext| {
+///     Ok::<_, RpcError>(context.peer_credentials().map(|credentials| credentials.uid()))
+/// });
+///
+/// let path = std::env::temp_dir().join(format!("ink-rpc-doc-{}.sock", std::process::id()));
+/// let server = UnixServer::bind(&path).unwrap();
+/// let handle = server.handle().unwrap();
+/// let running = thread::spawn(move || server.serve(&router));
+///
+/// let client = RpcClient::connect(UnixTransport::connect(&path).unwrap());
+/// assert!(client.call("whoami", json!(null)).wait().unwrap().is_u64());
+///
+/// handle.shutdown();
+/// running.join().unwrap().unwrap();
+/// assert!(!path.exists());
+/// ```
+pub struct UnixServer {
+    listener: UnixListener,
+    codec: Box<CodecFactory>,
+    connections: Arc<Connections<UnixStream>>,
+    socket_file: Option<SocketFile>,
+}
+
+/// The socket file created by `UnixServer::bind()`, identified by its inode
+/// so a file since replaced by another server is never removed.
+#[derive(Debug)]
+struct SocketFile {
+    path: PathBuf,
+    dev: u64,
+    ino: u64,
+}
+
+impl UnixServer {
+    /// Binds a server framed as newline-delimited JSON, with the default
+    /// socket options.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the socket file to create
+    ///
+    /// # Returns
+    ///
+    /// The server, or the error that prevented binding the socket.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::bind_with_options(path, &UnixSocketOptions::new())
+    }
+
+    /// Binds a server framed as newline-delimited JSON.
+    ///
+    /// The socket is bound in a private directory next to `path` and only
+    /// moved into place once its owner and permissions are set, so no other
+    /// user can connect before they apply. The directory holding `path` must
+    /// be writable.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the socket file to create
+    /// * `options` - How the socket file is created
+    ///
+    /// # Returns
+    ///
+    /// The server, or the error that prevented binding the socket. Fails with
+    /// `io::ErrorKind::AddrInUse` if another server is listening on `path`.
+    pub fn bind_with_options(
+        path: impl AsRef<Path>,
+        options: &UnixSocketOptions,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        if options.remove_stale {
+            remove_stale_socket(path)?;
+        }
+
+        let listener = bind_private(path, options)?;
+        let metadata = fs::metadata(path)?;
+        let mut server = Self::from_listener(listener);
+        server.socket_file = Some(SocketFile {
+            path: path.to_path_buf(),
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        });
+        Ok(server)
+    }
+
+    /// Creates a server accepting connections from an existing listener.
+    ///
+    /// The server does not own the listener's socket file, so it is left in
+    /// place when the server is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The bound listener
+    pub fn from_listener(listener: UnixListener) -> Self {
+        Self {
+            listener,
+            codec: Box::new(|| Box::new(NdjsonCodec::new())),
+            connections: Arc::new(Connections::new()),
+            socket_file: None,
+        }
+    }
+
+    /// Sets the framing format used on every connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec; each connection gets its own copy
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_codec<C>(&mut self, codec: C) -> &mut Self
+    where
+        C: FrameCodec + Clone + 'static,
+    {
+        self.codec = Box::new(move || Box::new(codec.clone()));
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    ///
+    /// For a server created with `bind()` this is the path it was asked to
+    /// bind, not the private path the socket was first bound at.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.socket_file {
+            Some(socket_file) => SocketAddr::from_pathname(&socket_file.path),
+            None => self.listener.local_addr(),
+        }
+    }
+
+    /// Returns a handle that can shut the server down from another thread.
+    pub fn handle(&self) -> io::Result<UnixServerHandle> {
+        Ok(UnixServerHandle {
+            addr: self.local_addr()?,
+            connections: self.connections.clone(),
+        })
+    }
+
+    /// Accepts connections and serves them until the server is shut down.
+    ///
+    /// Each connection is served on its own thread. Errors on a single
+    /// connection only end that session.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router dispatching requests on every connection
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The server was shut down and every session has ended
+    /// * `Err(io::Error)` - Accepting connections failed
+    pub fn serve(&self, router: &RpcRouter) -> io::Result<()> {
+        thread::scope(|scope| {
+            let result = loop {
+                let socket = match accept_next(|| self.listener.accept()) {
+                    Ok((socket, _)) => socket,
+                    Err(error) => break Err(error),
+                };
+
+                // The peer may already be gone, which only affects this connection
+                let Ok(transport) = UnixTransport::new(socket, (self.codec)()) else {
+                    continue;
+                };
+                let Some(id) = self.connections.track(&transport.socket) else {
+                    break Ok(());
+                };
+
+                let connections = self.connections.clone();
+                scope.spawn(move || {
+                    let mut context = RpcContext::new();
+                    if let Some(credentials) = transport.peer_credentials() {
+                        context.set_peer_credentials(credentials);
+                    }
+
+                    let _ = serve_with_context(&transport, router, &context);
+                    let _ = transport.close();
+                    connections.untrack(id);
+                });
+            };
+
+            self.connections.close_all();
+            result
+        })
+    }
+}
+
+impl Drop for UnixServer {
+    fn drop(&mut self) {
+        let Some(file) = &self.socket_file else {
+            return;
+        };
+        if let Ok(metadata) = fs::symlink_metadata(&file.path)
+            && metadata.dev() == file.dev
+            && metadata.ino() == file.ino
+        {
+            let _ = fs::remove_file(&file.path);
+        }
+    }
+}
+
+impl fmt::Debug for UnixServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnixServer")
+            .field("local_addr", &self.local_addr().ok())
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: UnixServerHandle
+// ===
+
+/// Shuts down a running `UnixServer`.
+#[derive(Clone, Debug)]
+pub struct UnixServerHandle {
+    addr: SocketAddr,
+    connections: Arc<Connections<UnixStream>>,
+}
+
+impl UnixServerHandle {
+    /// Stops accepting connections and closes every open connection.
+    ///
+    /// `UnixServer::serve()` returns once every session has ended.
+    pub fn shutdown(&self) {
+        self.connections.close_all();
+
+        // Wake the accept loop with a connection it will immediately drop
+        let _ = UnixStream::connect_addr(&self.addr);
+    }
+
+    /// Returns the number of connections currently being served.
+    pub fn connection_count(&self) -> usize {
+        self.connections.count()
+    }
+}
+
+// ===========================================================================
+// TESTS: UnixServer
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientError, RpcClient, RpcError};
+    use serde_json::json;
+    use std::process;
+
+    /// Returns a socket path unique to this process and test.
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ink-rpc-{}-{}.sock", process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register_with_context("whoami", |_: (), context: &RpcContext| {
+            let credentials = context.peer_credentials().unwrap();
+            Ok::<_, RpcError>((credentials.uid(), credentials.gid(), credentials.pid()))
+        });
+        router
+    }
+
+    #[test]
+    fn test_peer_credentials() {
+        let path = socket_path("credentials");
+        let server = UnixServer::bind(&path).unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            // Both ends are this process, so both see the same credentials
+            let expected = unsafe { (libc::getuid(), libc::getgid()) };
+            let transport = UnixTransport::connect(&path).unwrap();
+            let credentials = transport.peer_credentials().unwrap();
+            assert_eq!((credentials.uid(), credentials.gid()), expected);
+
+            let client = RpcClient::connect(transport);
+            let (uid, gid, pid): (u32, u32, Option<u32>) =
+                serde_json::from_value(client.call("whoami", json!(null)).wait().unwrap()).unwrap();
+            assert_eq!((uid, gid), expected);
+            if cfg!(target_os = "linux") {
+                assert_eq!(pid, Some(process::id()));
+            }
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+            assert!(matches!(
+                client.call("whoami", json!(null)).wait(),
+                Err(ClientError::Disconnected)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_stale_socket_cleanup() {
+        let path = socket_path("stale");
+
+        // A listener dropped without removing its file leaves a stale socket
+        drop(UnixListener::bind(&path).unwrap());
+        let mut options = UnixSocketOptions::new();
+        options.set_remove_stale(false);
+        let error = UnixServer::bind_with_options(&path, &options).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+
+        // By default the stale file is replaced
+        let server = UnixServer::bind(&path).unwrap();
+
+        // A live socket is never replaced
+        let error = UnixServer::bind(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+        assert!(path.exists());
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_never_removes_other_files() {
+        let path = socket_path("regular");
+        fs::write(&path, "keep me").unwrap();
+
+        let error = UnixServer::bind(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep me");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_socket_permissions() {
+        let dir = std::env::temp_dir().join(format!("ink-rpc-{}-mode", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("server.sock");
+        let mut options = UnixSocketOptions::new();
+        options.set_mode(0o600);
+
+        let server = UnixServer::bind_with_options(&path, &options).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+        // The socket is reachable at its final path and nothing else is left behind
+        UnixStream::connect(&path).unwrap();
+        assert_eq!(
+            server.local_addr().unwrap().as_pathname(),
+            Some(path.as_path())
+        );
+        let entries: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["server.sock"]);
+
+        // A server over someone else's listener leaves its file alone
+        let listener = server.listener.try_clone().unwrap();
+        drop(UnixServer::from_listener(listener));
+        assert!(path.exists());
+
+        drop(server);
+        assert!(!path.exists());
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,545 @@
+use crate::{RpcError, RpcId, RpcResponse};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use std::fmt;
+
+// ===========================================================================
+// ENUM: ValidationErrorKind
+// ===
+
+/// The rule of the JSON-RPC 2.0 specification that an inbound message broke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The payload is not valid JSON.
+    Parse,
+
+    /// The payload is not a JSON object (or, for a batch, not a JSON array).
+    InvalidStructure,
+
+    /// The payload is a batch without any members.
+    EmptyBatch,
+
+    /// The `jsonrpc` member is missing or is not exactly `"2.0"`.
+    InvalidVersion,
+
+    /// The `method` member is missing or is not a string.
+    InvalidMethod,
+
+    /// The `params` member is neither an array nor an object.
+    InvalidParams,
+
+    /// The `id` member is not a number, a string or null.
+    InvalidId,
+
+    /// A response does not contain exactly one of `result` or `error`.
+    InvalidOutcome,
+
+    /// The `error` member of a response is not a valid error object.
+    InvalidError,
+}
+
+// ===========================================================================
+// STRUCT: ValidationError
+// ===
+
+/// Describes why an inbound JSON-RPC 2.0 message was rejected.
+///
+/// A validation error knows which JSON-RPC error code the reply should carry
+/// and, when the message had a usable `id`, which ID the reply must echo back.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcError, RpcId, RpcRequest, ValidationErrorKind};
+/// use serde_json::json;
+///
+/// let error = RpcRequest::from_json(json!({
+///     "jsonrpc": "1.0",
+///     "method": "sum",
+///     "id": 7
+/// }))
+/// .unwrap_err();
+///
+/// assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+/// assert_eq!(error.code(), RpcError::INVALID_REQUEST);
+/// assert_eq!(error.id(), &RpcId::Number(7));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    kind: ValidationErrorKind,
+    id: RpcId,
+    message: String,
+}
+
+impl ValidationError {
+    /// Creates a new validation error.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The rule that was broken
+    /// * `id` - The ID of the offending message, or `RpcId::Null` if unknown
+    /// * `message` - A human-readable description of the problem
+    pub fn new(kind: ValidationErrorKind, id: RpcId, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a validation error for a payload that is not valid JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error reported by the JSON parser
+    pub fn parse(error: serde_json::Error) -> Self {
+        Self::new(ValidationErrorKind::Parse, RpcId::Null, error.to_string())
+    }
+
+    /// Returns the rule that was broken.
+    pub fn kind(&self) -> ValidationErrorKind {
+        self.kind
+    }
+
+    /// Returns the ID of the offending message.
+    ///
+    /// # Returns
+    ///
+    /// The message ID if it could be determined, or `RpcId::Null` otherwise.
+    pub fn id(&self) -> &RpcId {
+        &self.id
+    }
+
+    /// Returns a human-readable description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the JSON-RPC error code the reply should carry.
+    ///
+    /// # Returns
+    ///
+    /// -32700 (Parse error) for invalid JSON, -32600 (Invalid Request) otherwise.
+    pub fn code(&self) -> i64 {
+        match self.kind {
+            ValidationErrorKind::Parse => RpcError::PARSE_ERROR,
+            _ => RpcError::INVALID_REQUEST,
+        }
+    }
+
+    /// Converts the validation error into the `RpcError` to send back.
+    ///
+    /// The description of the problem is attached as the error's `data`.
+    ///
+    /// # Returns
+    ///
+    /// A "Parse error" or "Invalid Request" error.
+    pub fn to_rpc_error(&self) -> RpcError {
+        let error = match self.kind {
+            ValidationErrorKind::Parse => RpcError::parse_error(),
+            _ => RpcError::invalid_request(),
+        };
+        error.with_data(json!(self.message))
+    }
+
+    /// Builds the error response to send back for the rejected message.
+    ///
+    /// # Returns
+    ///
+    /// An error response carrying the message ID (or null) and the matching error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ink_rpc::{RpcId, RpcRequest};
+    ///
+    /// let error = "{not json".parse::<RpcRequest>().unwrap_err();
+    /// let response = error.to_response();
+    /// assert_eq!(response.id(), &RpcId::Null);
+    /// assert_eq!(response.error().unwrap().code(), -32700);
+    /// ```
+    pub fn to_response(&self) -> RpcResponse {
+        RpcResponse::failure(self.id.clone(), self.to_rpc_error())
+    }
+}
+
+impl From<ValidationError> for RpcError {
+    fn from(error: ValidationError) -> Self {
+        error.to_rpc_error()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    /// Formats the validation error as its description.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+// ===========================================================================
+// FUNCTIONS: Validation
+// ===
+
+/// Parses a JSON payload, reporting invalid JSON as a validation error.
+///
+/// # Arguments
+///
+/// * `text` - The raw JSON text
+///
+/// # Returns
+///
+/// The parsed JSON value, or a `ValidationErrorKind::Parse` error.
+pub(crate) fn parse_json(text: &str) -> Result<JsonValue, ValidationError> {
+    serde_json::from_str(text).map_err(ValidationError::parse)
+}
+
+/// Checks that a JSON value is a valid request or notification object.
+///
+/// # Arguments
+///
+/// * `json` - The JSON value to check
+///
+/// # Returns
+///
+/// `Ok(())` if the value is a valid request, or the first broken rule.
+pub(crate) fn validate_request(json: &JsonValue) -> Result<(), ValidationError> {
+    let object = match json.as_object() {
+        Some(object) => object,
+        None => return Err(invalid_structure("a request must be a JSON object")),
+    };
+
+    let id = validate_id(object.get("id"))?;
+    validate_version(object.get("jsonrpc"), &id)?;
+
+    if !matches!(object.get("method"), Some(JsonValue::String(_))) {
+        return Err(ValidationError::new(
+            ValidationErrorKind::InvalidMethod,
+            id,
+            "`method` must be a string",
+        ));
+    }
+
+    // An explicit null is tolerated and treated the same as omitted params
+    match object.get("params") {
+        None | Some(JsonValue::Null) | Some(JsonValue::Array(_)) | Some(JsonValue::Object(_)) => {
+            Ok(())
+        }
+        Some(_) => Err(ValidationError::new(
+            ValidationErrorKind::InvalidParams,
+            id,
+            "`params` must be an array or an object",
+        )),
+    }
+}
+
+/// Checks that a JSON value is a valid response object.
+///
+/// # Arguments
+///
+/// * `json` - The JSON value to check
+///
+/// # Returns
+///
+/// `Ok(())` if the value is a valid response, or the first broken rule.
+pub(crate) fn validate_response(json: &JsonValue) -> Result<(), ValidationError> {
+    let object = match json.as_object() {
+        Some(object) => object,
+        None => return Err(invalid_structure("a response must be a JSON object")),
+    };
+
+    let id = match object.get("id") {
+        Some(id) => validate_id(Some(id))?,
+        None => {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidId,
+                RpcId::Null,
+                "a response must contain an `id`",
+            ));
+        }
+    };
+    validate_version(object.get("jsonrpc"), &id)?;
+
+    match (object.get("result"), object.get("error")) {
+        (Some(_), None) => Ok(()),
+        (None, Some(error)) => match RpcError::deserialize(error) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(ValidationError::new(
+                ValidationErrorKind::InvalidError,
+                id,
+                "`error` must be an object with an integer `code` and a string `message`",
+            )),
+        },
+        (Some(_), Some(_)) => Err(ValidationError::new(
+            ValidationErrorKind::InvalidOutcome,
+            id,
+            "a response must not contain both `result` and `error`",
+        )),
+        (None, None) => Err(ValidationError::new(
+            ValidationErrorKind::InvalidOutcome,
+            id,
+            "a response must contain either `result` or `error`",
+        )),
+    }
+}
+
+/// Checks that a JSON value is a non-empty array and returns its members.
+///
+/// # Arguments
+///
+/// * `json` - The JSON value to check
+///
+/// # Returns
+///
+/// The batch members, or an error if the value is not a non-empty array.
+pub(crate) fn validate_batch(json: JsonValue) -> Result<Vec<JsonValue>, ValidationError> {
+    match json {
+        JsonValue::Array(members) if members.is_empty() => Err(ValidationError::new(
+            ValidationErrorKind::EmptyBatch,
+            RpcId::Null,
+            "a batch must contain at least one member",
+        )),
+        JsonValue::Array(members) => Ok(members),
+        _ => Err(invalid_structure("a batch must be a JSON array")),
+    }
+}
+
+/// Checks the `jsonrpc` member of a message.
+fn validate_version(version: Option<&JsonValue>, id: &RpcId) -> Result<(), ValidationError> {
+    match version {
+        Some(JsonValue::String(version)) if version == "2.0" => Ok(()),
+        _ => Err(ValidationError::new(
+            ValidationErrorKind::InvalidVersion,
+            id.clone(),
+            "`jsonrpc` must be exactly \"2.0\"",
+        )),
+    }
+}
+
+/// Checks the `id` member of a message and returns it.
+///
+/// A missing `id` is reported as `RpcId::Null`; whether that is acceptable is
+/// up to the caller.
+fn validate_id(id: Option<&JsonValue>) -> Result<RpcId, ValidationError> {
+    match id {
+        None => Ok(RpcId::Null),
+        Some(id) => RpcId::deserialize(id).map_err(|_| {
+            ValidationError::new(
+                ValidationErrorKind::InvalidId,
+                RpcId::Null,
+                "`id` must be an integer, a string or null",
+            )
+        }),
+    }
+}
+
+/// Converts a deserialization failure of an already validated message.
+///
+/// Validation runs before deserialization, so this is only reached for
+/// messages that break a rule validation does not check.
+///
+/// # Arguments
+///
+/// * `error` - The error reported by serde
+pub(crate) fn invalid_message(error: serde_json::Error) -> ValidationError {
+    invalid_structure(&error.to_string())
+}
+
+/// Builds an error for a payload of the wrong JSON type.
+fn invalid_structure(message: &str) -> ValidationError {
+    ValidationError::new(ValidationErrorKind::InvalidStructure, RpcId::Null, message)
+}
+
+// ===========================================================================
+// TESTS: Validation
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_error(json: JsonValue) -> ValidationError {
+        validate_request(&json).unwrap_err()
+    }
+
+    fn response_error(json: JsonValue) -> ValidationError {
+        validate_response(&json).unwrap_err()
+    }
+
+    // ---------------------------------------------------------------------------
+    // Request Validation Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_valid_requests() {
+        let valid = [
+            json!({"jsonrpc": "2.0", "method": "a", "id": 1}),
+            json!({"jsonrpc": "2.0", "method": "a", "params": [1], "id": "x"}),
+            json!({"jsonrpc": "2.0", "method": "a", "params": {"k": 1}, "id": null}),
+            json!({"jsonrpc": "2.0", "method": "a", "params": null}),
+            json!({"jsonrpc": "2.0", "method": "a"}),
+        ];
+
+        for json in valid {
+            assert_eq!(validate_request(&json), Ok(()), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_request_structure() {
+        let error = request_error(json!([1]));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+        assert_eq!(error.id(), &RpcId::Null);
+
+        let error = request_error(json!("request"));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+    }
+
+    #[test]
+    fn test_request_version() {
+        let error = request_error(json!({"jsonrpc": "1.0", "method": "a", "id": 3}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+        assert_eq!(error.id(), &RpcId::Number(3));
+
+        let error = request_error(json!({"method": "a", "id": 3}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+
+        let error = request_error(json!({"jsonrpc": 2.0, "method": "a", "id": 3}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+    }
+
+    #[test]
+    fn test_request_method() {
+        let error = request_error(json!({"jsonrpc": "2.0", "id": "m"}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidMethod);
+        assert_eq!(error.id(), &RpcId::from("m"));
+
+        let error = request_error(json!({"jsonrpc": "2.0", "method": 1, "id": "m"}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidMethod);
+    }
+
+    #[test]
+    fn test_request_params() {
+        for params in [json!("text"), json!(1), json!(true)] {
+            let error =
+                request_error(json!({"jsonrpc": "2.0", "method": "a", "params": params, "id": 9}));
+            assert_eq!(error.kind(), ValidationErrorKind::InvalidParams);
+            assert_eq!(error.id(), &RpcId::Number(9));
+            assert_eq!(error.code(), RpcError::INVALID_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_request_id() {
+        for id in [json!({"a": 1}), json!([1]), json!(true), json!(1.5)] {
+            let error = request_error(json!({"jsonrpc": "2.0", "method": "a", "id": id}));
+            assert_eq!(error.kind(), ValidationErrorKind::InvalidId);
+            assert_eq!(error.id(), &RpcId::Null);
+        }
+
+        // Negative integers are valid IDs
+        let json = json!({"jsonrpc": "2.0", "method": "a", "id": -1});
+        assert_eq!(validate_request(&json), Ok(()));
+        let error = request_error(json!({"jsonrpc": "1.0", "method": "a", "id": -1}));
+        assert_eq!(error.id(), &RpcId::Number(-1));
+    }
+
+    // ---------------------------------------------------------------------------
+    // Response Validation Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_valid_responses() {
+        let valid = [
+            json!({"jsonrpc": "2.0", "result": 1, "id": 1}),
+            json!({"jsonrpc": "2.0", "result": null, "id": "x"}),
+            json!({"jsonrpc": "2.0", "error": {"code": -1, "message": "m"}, "id": null}),
+            json!({"jsonrpc": "2.0", "error": {"code": 5, "message": "m", "data": [1]}, "id": 2}),
+        ];
+
+        for json in valid {
+            assert_eq!(validate_response(&json), Ok(()), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_response_outcome() {
+        let error = response_error(json!({"jsonrpc": "2.0", "id": 4}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidOutcome);
+        assert_eq!(error.id(), &RpcId::Number(4));
+
+        let error = response_error(json!({
+            "jsonrpc": "2.0",
+            "result": 1,
+            "error": {"code": -1, "message": "m"},
+            "id": 4
+        }));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidOutcome);
+    }
+
+    #[test]
+    fn test_response_error_object() {
+        for error in [
+            json!("failed"),
+            json!({"message": "no code"}),
+            json!({"code": "x", "message": "m"}),
+            json!({"code": -1}),
+        ] {
+            let error = response_error(json!({"jsonrpc": "2.0", "error": error, "id": 1}));
+            assert_eq!(error.kind(), ValidationErrorKind::InvalidError);
+        }
+    }
+
+    #[test]
+    fn test_response_id_and_version() {
+        let error = response_error(json!({"jsonrpc": "2.0", "result": 1}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidId);
+
+        let error = response_error(json!({"result": 1, "id": 1}));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidVersion);
+
+        let error = response_error(json!(null));
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+    }
+
+    // ---------------------------------------------------------------------------
+    // Batch and Parse Tests
+    // ---------------------------------------------------------------------------
+
+    #[test]
+    fn test_batch() {
+        assert_eq!(validate_batch(json!([1, 2])).unwrap().len(), 2);
+
+        let error = validate_batch(json!([])).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::EmptyBatch);
+        assert_eq!(error.code(), RpcError::INVALID_REQUEST);
+
+        let error = validate_batch(json!({})).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStructure);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_json("{\"jsonrpc\": \"2.0\", \"method\"").unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::Parse);
+        assert_eq!(error.code(), RpcError::PARSE_ERROR);
+
+        assert_eq!(parse_json("[1]").unwrap(), json!([1]));
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let error = request_error(json!({"jsonrpc": "2.0", "method": "a", "params": 1, "id": 5}));
+
+        let rpc_error = RpcError::from(error.clone());
+        assert_eq!(rpc_error.code(), RpcError::INVALID_REQUEST);
+        assert_eq!(rpc_error.message(), "Invalid Request");
+        assert_eq!(rpc_error.data(), Some(&json!(error.message())));
+
+        let response = error.to_response();
+        assert_eq!(response.id(), &RpcId::Number(5));
+        assert_eq!(response.error(), Some(&rpc_error));
+    }
+}
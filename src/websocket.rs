@@ -0,0 +1,1395 @@
+use crate::codec;
+use crate::connections::{Connections, SharedSocket, accept_next};
+use crate::http::{self, HttpResponse, MessageHead};
+use crate::http_client::{host_header, parse_url};
+use crate::peer::{run_session, session_client};
+use crate::{
+    RpcClient, RpcContext, RpcMessage, RpcRouter, SequentialIdGenerator, TcpServerHandle,
+    Transport, TransportError,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The GUID appended to the handshake key, from RFC 6455.
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How long to wait for the peer to answer a close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest payload of a control frame.
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// The hook run when a `WebSocketServer` accepts a connection.
+type ConnectHook = dyn Fn(&RpcContext) + Send + Sync;
+
+// ===========================================================================
+// STRUCT: CloseFrame
+// ===
+
+/// The status code and reason of a WebSocket close frame.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::CloseFrame;
+///
+/// let frame = CloseFrame::new(CloseFrame::GOING_AWAY, "server restarting");
+/// assert_eq!(frame.code(), 1001);
+/// assert_eq!(frame.reason(), "server restarting");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CloseFrame {
+    code: u16,
+    reason: String,
+}
+
+impl CloseFrame {
+    /// The connection has served its purpose.
+    pub const NORMAL: u16 = 1000;
+
+    /// The endpoint is going away, such as a server shutting down.
+    pub const GOING_AWAY: u16 = 1001;
+
+    /// The peer broke the WebSocket protocol.
+    pub const PROTOCOL_ERROR: u16 = 1002;
+
+    /// The peer sent a kind of message that is not accepted, such as binary.
+    pub const UNSUPPORTED_DATA: u16 = 1003;
+
+    /// The close frame carried no status code.
+    pub const NO_STATUS: u16 = 1005;
+
+    /// A text message was not valid UTF-8.
+    pub const INVALID_PAYLOAD: u16 = 1007;
+
+    /// A message broke the endpoint's policy.
+    pub const POLICY_VIOLATION: u16 = 1008;
+
+    /// A message exceeded the maximum message size.
+    pub const MESSAGE_TOO_BIG: u16 = 1009;
+
+    /// The endpoint hit an unexpected condition.
+    pub const INTERNAL_ERROR: u16 = 1011;
+
+    /// Creates a close frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The status code, such as `CloseFrame::NORMAL`
+    /// * `reason` - A short human-readable reason
+    pub fn new(code: u16, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns the status code.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns the reason, which may be empty.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Checks if a status code may be sent in a close frame.
+fn is_sendable(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999)
+}
+
+// ===========================================================================
+// STRUCT: WebSocketTransport
+// ===
+
+/// A transport over a WebSocket connection, carrying each message as a text
+/// frame.
+///
+/// Use `connect()` on the client side; `WebSocketServer` creates one for
+/// every connection it accepts, and `accept()` performs the server side of
+/// the handshake on a socket accepted elsewhere.
+///
+/// Pings from the peer are answered automatically. With a ping interval set,
+/// the transport also pings a peer that has been silent for that long while
+/// it is being read from, and drops the connection if the peer stays silent
+/// for another interval.
+///
+/// Closing the transport sends a close frame with `CloseFrame::NORMAL`; use
+/// `close_with()` for another status code. The close frame sent by the peer
+/// is available from `peer_close()` once `receive()` has returned `None`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ink_rpc::{RpcClient, WebSocketTransport};
+/// use serde_json::json;
+///
+/// let client = RpcClient::connect(WebSocketTransport::connect("ws://127.0.0.1:9000/rpc")?);
+/// let sum = client.call("add", json!([2, 3])).wait()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct WebSocketTransport {
+    reader: Mutex<ReaderState>,
+    writer: Mutex<WriterState>,
+    socket: Arc<TcpStream>,
+    role: Role,
+    peer_addr: SocketAddr,
+    max_message_size: usize,
+    ping_interval: Option<Duration>,
+    peer_close: Mutex<Option<CloseFrame>>,
+}
+
+/// The reading half of a WebSocket connection.
+struct ReaderState {
+    reader: BufReader<SharedSocket<TcpStream>>,
+    awaiting_pong: bool,
+    done: bool,
+}
+
+/// The writing half of a WebSocket connection.
+struct WriterState {
+    socket: SharedSocket<TcpStream>,
+    close_sent: bool,
+}
+
+/// Which end of the connection a transport is; clients mask their frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Client,
+    Server,
+}
+
+impl WebSocketTransport {
+    /// The default maximum message size: 16 MiB.
+    pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Connects to a WebSocket server and performs the opening handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The endpoint, such as `ws://127.0.0.1:9000/rpc`; `wss://` is
+    ///   not supported
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that prevented connecting. A server
+    /// refusing the upgrade is reported as `io::ErrorKind::InvalidData`.
+    pub fn connect(url: &str) -> io::Result<Self> {
+        let (host, port, path) = parse_url(url, "ws")
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?;
+        let socket = Arc::new(TcpStream::connect((host.as_str(), port))?);
+        let mut reader = BufReader::new(SharedSocket(socket.clone()));
+
+        let mut nonce = [0; 16];
+        getrandom::fill(&mut nonce)?;
+        let key = BASE64.encode(nonce);
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path,
+            host_header(&host, port),
+            key
+        );
+        (&*socket).write_all(request.as_bytes())?;
+
+        let head = read_head(&mut reader, MessageHead::read_response)?;
+        if let Some(status) = head.status().filter(|&status| status != 101) {
+            return Err(invalid_data(format!(
+                "server refused the WebSocket upgrade with status {}",
+                status
+            )));
+        }
+        if head.header("Sec-WebSocket-Accept") != Some(accept_key(&key).as_str()) {
+            return Err(invalid_data("server sent a wrong Sec-WebSocket-Accept"));
+        }
+
+        Self::new(socket, reader, Role::Client)
+    }
+
+    /// Performs the server side of the opening handshake on an accepted
+    /// connection.
+    ///
+    /// Requests that are not a WebSocket upgrade are answered with an HTTP
+    /// error, such as `426 Upgrade Required`, and fail with
+    /// `io::ErrorKind::InvalidData`.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The accepted connection
+    ///
+    /// # Returns
+    ///
+    /// The transport, or the error that ended the handshake.
+    pub fn accept(socket: TcpStream) -> io::Result<Self> {
+        Self::accept_shared(Arc::new(socket))
+    }
+
+    /// Performs the server side of the handshake on a connection whose
+    /// socket is also tracked by a server.
+    fn accept_shared(socket: Arc<TcpStream>) -> io::Result<Self> {
+        let mut reader = BufReader::new(SharedSocket(socket.clone()));
+        let head = read_head(&mut reader, MessageHead::read_request)?;
+
+        match check_upgrade(&head) {
+            Ok(key) => {
+                let response = format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                     Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                    accept_key(key)
+                );
+                (&*socket).write_all(response.as_bytes())?;
+                Self::new(socket, reader, Role::Server)
+            }
+            Err(status) => {
+                let headers: &[_] = match status {
+                    405 => &[("Allow", "GET")],
+                    426 => &[("Upgrade", "websocket"), ("Sec-WebSocket-Version", "13")],
+                    _ => &[],
+                };
+                http::write_response(&mut &*socket, &HttpResponse::empty(status), headers, true)?;
+                Err(invalid_data("not a WebSocket handshake"))
+            }
+        }
+    }
+
+    /// Creates a transport once the handshake is complete.
+    fn new(
+        socket: Arc<TcpStream>,
+        reader: BufReader<SharedSocket<TcpStream>>,
+        role: Role,
+    ) -> io::Result<Self> {
+        socket.set_nodelay(true)?;
+
+        Ok(Self {
+            reader: Mutex::new(ReaderState {
+                reader,
+                awaiting_pong: false,
+                done: false,
+            }),
+            writer: Mutex::new(WriterState {
+                socket: SharedSocket(socket.clone()),
+                close_sent: false,
+            }),
+            peer_addr: socket.peer_addr()?,
+            socket,
+            role,
+            max_message_size: Self::DEFAULT_MAX_MESSAGE_SIZE,
+            ping_interval: None,
+            peer_close: Mutex::new(None),
+        })
+    }
+
+    /// Returns the maximum message size in bytes.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Sets the maximum size of an inbound message.
+    ///
+    /// A larger message closes the connection with
+    /// `CloseFrame::MESSAGE_TOO_BIG`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_message_size` - The maximum size in bytes
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) -> &mut Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Returns the keepalive ping interval, if any.
+    pub fn ping_interval(&self) -> Option<Duration> {
+        self.ping_interval
+    }
+
+    /// Sets how long the peer may stay silent before it is pinged.
+    ///
+    /// # Arguments
+    ///
+    /// * `ping_interval` - The interval; `None` never pings
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_ping_interval(&mut self, ping_interval: Option<Duration>) -> &mut Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Returns the address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Returns the close frame sent by the peer, once it has been received.
+    pub fn peer_close(&self) -> Option<CloseFrame> {
+        self.peer_close.lock().unwrap().clone()
+    }
+
+    /// Sends a ping; the peer answers with a pong carrying the same payload.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - Up to 125 bytes of application data
+    pub fn ping(&self, payload: &[u8]) -> Result<(), TransportError> {
+        if payload.len() > MAX_CONTROL_PAYLOAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a ping payload is limited to 125 bytes",
+            )
+            .into());
+        }
+        Ok(self.write_control(OPCODE_PING, payload)?)
+    }
+
+    /// Starts the closing handshake with a status code.
+    ///
+    /// Nothing can be sent afterwards; messages already on their way are
+    /// still received until the peer answers with its own close frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The status code, such as `CloseFrame::GOING_AWAY` or an
+    ///   application code between 4000 and 4999
+    /// * `reason` - A short reason, truncated to fit in a control frame
+    pub fn close_with(&self, code: u16, reason: &str) -> Result<(), TransportError> {
+        if !is_sendable(code) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} cannot be sent as a close code", code),
+            )
+            .into());
+        }
+        Ok(self.send_close(code, reason)?)
+    }
+
+    /// Sends a close frame unless one has been sent already.
+    fn send_close(&self, code: u16, reason: &str) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        if writer.close_sent {
+            return Ok(());
+        }
+        writer.close_sent = true;
+
+        let mut payload = Vec::new();
+        if code != CloseFrame::NO_STATUS {
+            let mut end = reason.len().min(MAX_CONTROL_PAYLOAD - 2);
+            while !reason.is_char_boundary(end) {
+                end -= 1;
+            }
+            payload.extend_from_slice(&code.to_be_bytes());
+            payload.extend_from_slice(&reason.as_bytes()[..end]);
+        }
+
+        // A peer that never answers must not hold the connection open
+        let _ = self.socket.set_read_timeout(Some(CLOSE_TIMEOUT));
+        let mask = self.mask()?;
+        match write_frame(&mut writer.socket, OPCODE_CLOSE, &payload, mask) {
+            Err(error) if is_disconnect(&error) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Sends a control frame, unless the connection is closing.
+    fn write_control(&self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        if writer.close_sent {
+            return Ok(());
+        }
+        write_frame(&mut writer.socket, opcode, payload, self.mask()?)
+    }
+
+    /// Returns a fresh masking key for a client frame.
+    ///
+    /// The keys come from the operating system's random number generator,
+    /// so that intermediaries cannot predict them, as RFC 6455 requires.
+    fn mask(&self) -> io::Result<Option<[u8; 4]>> {
+        if self.role == Role::Server {
+            return Ok(None);
+        }
+        let mut mask = [0; 4];
+        getrandom::fill(&mut mask)?;
+        Ok(Some(mask))
+    }
+
+    /// Waits until the next frame starts arriving, pinging a silent peer.
+    ///
+    /// # Returns
+    ///
+    /// `true` once a frame is available, or `false` at the end of the stream.
+    fn wait_for_frame(&self, state: &mut ReaderState) -> Result<bool, TransportError> {
+        loop {
+            let close_sent = self.writer.lock().unwrap().close_sent;
+            let timeout = if close_sent {
+                Some(CLOSE_TIMEOUT)
+            } else {
+                self.ping_interval
+            };
+
+            if timeout.is_some() {
+                self.socket.set_read_timeout(timeout)?;
+            }
+            let result = state.reader.fill_buf().map(|buffer| !buffer.is_empty());
+            if timeout.is_some() {
+                self.socket.set_read_timeout(None)?;
+            }
+
+            match result {
+                Ok(available) => return Ok(available),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if close_sent {
+                        return Ok(false);
+                    }
+                    if state.awaiting_pong {
+                        let _ = self.socket.shutdown(Shutdown::Both);
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "peer did not answer a ping",
+                        )
+                        .into());
+                    }
+                    self.write_control(OPCODE_PING, b"")?;
+                    state.awaiting_pong = true;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Closes the connection because the peer broke the protocol.
+    ///
+    /// # Returns
+    ///
+    /// The error `receive()` reports; the next call returns `None`.
+    fn fail(&self, state: &mut ReaderState, code: u16, message: String) -> TransportError {
+        state.done = true;
+        let _ = self.send_close(code, &message);
+
+        match code {
+            CloseFrame::MESSAGE_TOO_BIG => TransportError::FrameTooLarge {
+                limit: self.max_message_size,
+            },
+            _ => TransportError::MalformedFrame(message),
+        }
+    }
+
+    /// Handles a close frame from the peer, answering it if needed.
+    fn closed_by_peer(
+        &self,
+        state: &mut ReaderState,
+        payload: &[u8],
+    ) -> Result<(), TransportError> {
+        let frame = match payload {
+            [] => CloseFrame::new(CloseFrame::NO_STATUS, ""),
+            [_] => {
+                let message = "close frame with a truncated status code".to_string();
+                return Err(self.fail(state, CloseFrame::PROTOCOL_ERROR, message));
+            }
+            [high, low, reason @ ..] => match std::str::from_utf8(reason) {
+                Ok(reason) => CloseFrame::new(u16::from_be_bytes([*high, *low]), reason),
+                Err(_) => {
+                    let message = "close reason is not valid UTF-8".to_string();
+                    return Err(self.fail(state, CloseFrame::INVALID_PAYLOAD, message));
+                }
+            },
+        };
+
+        state.done = true;
+        let _ = self.send_close(frame.code(), frame.reason());
+        *self.peer_close.lock().unwrap() = Some(frame);
+        Ok(())
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn send(&self, message: RpcMessage) -> Result<(), TransportError> {
+        let mut writer = self.writer.lock().unwrap();
+        if writer.close_sent {
+            return Err(TransportError::Closed);
+        }
+        let payload = codec::encode_message(&message);
+        Ok(write_frame(
+            &mut writer.socket,
+            OPCODE_TEXT,
+            &payload,
+            self.mask()?,
+        )?)
+    }
+
+    fn receive(&self) -> Result<Option<RpcMessage>, TransportError> {
+        let mut state = self.reader.lock().unwrap();
+        let mut message: Option<Vec<u8>> = None;
+
+        loop {
+            if state.done {
+                return Ok(None);
+            }
+            if !self.wait_for_frame(&mut state)? {
+                state.done = true;
+                return Ok(None);
+            }
+
+            let received = message.as_ref().map_or(0, Vec::len);
+            let limit = self.max_message_size - received;
+            let frame = match read_frame(&mut state.reader, limit, self.role == Role::Server) {
+                Ok(frame) => frame,
+                Err(FrameError::Io(error)) => return Err(error.into()),
+                Err(FrameError::Violation { code, message }) => {
+                    return Err(self.fail(&mut state, code, message));
+                }
+            };
+            state.awaiting_pong = false;
+
+            match (frame.opcode, message.as_mut()) {
+                (OPCODE_PING, _) => self.write_control(OPCODE_PONG, &frame.payload)?,
+                (OPCODE_PONG, _) => {}
+                (OPCODE_CLOSE, _) => self.closed_by_peer(&mut state, &frame.payload)?,
+                (OPCODE_TEXT, None) => message = Some(frame.payload),
+                (OPCODE_CONTINUATION, Some(message)) => message.extend_from_slice(&frame.payload),
+                (OPCODE_BINARY, _) => {
+                    let text = "binary messages are not supported".to_string();
+                    return Err(self.fail(&mut state, CloseFrame::UNSUPPORTED_DATA, text));
+                }
+                _ => {
+                    let text = "frame out of sequence".to_string();
+                    return Err(self.fail(&mut state, CloseFrame::PROTOCOL_ERROR, text));
+                }
+            }
+
+            if frame.fin && matches!(frame.opcode, OPCODE_TEXT | OPCODE_CONTINUATION) {
+                let text = message.take().unwrap_or_default();
+                if std::str::from_utf8(&text).is_err() {
+                    let text = "text message is not valid UTF-8".to_string();
+                    return Err(self.fail(&mut state, CloseFrame::INVALID_PAYLOAD, text));
+                }
+                return Ok(Some(codec::parse_frame(&text)?));
+            }
+        }
+    }
+
+    fn close(&self) -> Result<(), TransportError> {
+        Ok(self.send_close(CloseFrame::NORMAL, "")?)
+    }
+}
+
+impl fmt::Debug for WebSocketTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("peer_addr", &self.peer_addr)
+            .field("role", &self.role)
+            .field("peer_close", &self.peer_close())
+            .finish()
+    }
+}
+
+/// Reads the head of a handshake request or response with `read`.
+fn read_head(
+    reader: &mut dyn BufRead,
+    read: fn(&mut dyn BufRead) -> Result<Option<MessageHead>, TransportError>,
+) -> io::Result<MessageHead> {
+    match read(reader) {
+        Ok(Some(head)) => Ok(head),
+        Ok(None) => Err(io::ErrorKind::UnexpectedEof.into()),
+        Err(TransportError::Io(error)) => Err(error),
+        Err(error) => Err(invalid_data(error.to_string())),
+    }
+}
+
+/// Checks an opening handshake request.
+///
+/// # Returns
+///
+/// The client's `Sec-WebSocket-Key`, or the HTTP status to refuse it with.
+fn check_upgrade(head: &MessageHead) -> Result<&str, u16> {
+    if !head.is_http_11() {
+        return Err(400);
+    }
+    if head.method() != Some("GET") {
+        return Err(405);
+    }
+    if !head.has_token("Upgrade", "websocket")
+        || !head.has_token("Connection", "upgrade")
+        || head.header("Sec-WebSocket-Version") != Some("13")
+    {
+        return Err(426);
+    }
+    match head.header("Sec-WebSocket-Key") {
+        Some(key) if key.len() == 24 => Ok(key),
+        _ => Err(400),
+    }
+}
+
+/// Computes the `Sec-WebSocket-Accept` answer to a handshake key.
+fn accept_key(key: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(key)
+        .chain_update(HANDSHAKE_GUID)
+        .finalize();
+    BASE64.encode(digest)
+}
+
+/// Creates an `io::ErrorKind::InvalidData` error.
+fn invalid_data(error: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Checks if a write failed because the peer is already gone.
+fn is_disconnect(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset | io::ErrorKind::NotConnected
+    )
+}
+
+// ===========================================================================
+// FUNCTIONS: Framing
+// ===
+
+/// One frame of a WebSocket message.
+#[derive(Debug)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Describes why a frame could not be read.
+#[derive(Debug)]
+enum FrameError {
+    /// The connection failed.
+    Io(io::Error),
+
+    /// The peer broke the protocol; the connection is closed with `code`.
+    Violation { code: u16, message: String },
+}
+
+impl From<io::Error> for FrameError {
+    fn from(error: io::Error) -> Self {
+        FrameError::Io(error)
+    }
+}
+
+/// Creates a protocol error closing the connection with `code`.
+fn violation(code: u16, message: impl Into<String>) -> FrameError {
+    FrameError::Violation {
+        code,
+        message: message.into(),
+    }
+}
+
+/// Reads one frame.
+///
+/// # Arguments
+///
+/// * `reader` - The stream to read from
+/// * `limit` - The largest payload accepted
+/// * `masked` - Whether the frame must be masked, which is the case for
+///   frames sent by clients
+fn read_frame(reader: &mut dyn BufRead, limit: usize, masked: bool) -> Result<Frame, FrameError> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    if header[0] & 0x70 != 0 {
+        return Err(violation(
+            CloseFrame::PROTOCOL_ERROR,
+            "reserved bits are set",
+        ));
+    }
+    if !matches!(opcode, 0x0..=0x2 | 0x8..=0xA) {
+        return Err(violation(
+            CloseFrame::PROTOCOL_ERROR,
+            format!("unknown opcode {:#x}", opcode),
+        ));
+    }
+    if (header[1] & 0x80 != 0) != masked {
+        let message = if masked {
+            "client frames must be masked"
+        } else {
+            "server frames must not be masked"
+        };
+        return Err(violation(CloseFrame::PROTOCOL_ERROR, message));
+    }
+
+    let length = match header[1] & 0x7F {
+        126 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as u64
+        }
+        127 => {
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            u64::from_be_bytes(length)
+        }
+        length => length as u64,
+    };
+    if opcode >= OPCODE_CLOSE && (!fin || length > MAX_CONTROL_PAYLOAD as u64) {
+        return Err(violation(
+            CloseFrame::PROTOCOL_ERROR,
+            "control frames must be final and at most 125 bytes",
+        ));
+    }
+    if length > limit as u64 {
+        return Err(violation(
+            CloseFrame::MESSAGE_TOO_BIG,
+            "message exceeds the maximum message size",
+        ));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+    if masked {
+        apply_mask(&mut payload, mask);
+    }
+
+    Ok(Frame {
+        fin,
+        opcode,
+        payload,
+    })
+}
+
+/// Writes one final frame.
+///
+/// # Arguments
+///
+/// * `writer` - The stream to write to
+/// * `opcode` - The kind of frame
+/// * `payload` - The payload of the frame
+/// * `mask` - The masking key, for frames sent by clients
+fn write_frame(
+    writer: &mut dyn Write,
+    opcode: u8,
+    payload: &[u8],
+    mask: Option<[u8; 4]>,
+) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    match payload.len() {
+        length if length < 126 => frame.push(mask_bit | length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(mask_bit | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(mask_bit | 127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+
+    let start = frame.len();
+    frame.extend_from_slice(payload);
+    if let Some(mask) = mask {
+        frame.splice(start..start, mask);
+        apply_mask(&mut frame[start + 4..], mask);
+    }
+
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// Masks or unmasks a payload in place.
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+}
+
+// ===========================================================================
+// STRUCT: WebSocketServer
+// ===
+
+/// Accepts WebSocket connections and serves JSON-RPC on each of them, in
+/// both directions.
+///
+/// Every connection runs as its own session, like on a `TcpServer`, but the
+/// server can also call the client: the `RpcContext` of every request holds
+/// an `RpcClient` for the connection, available from `RpcContext::client()`.
+/// The hook set with `set_on_connect()` receives the same context when a
+/// connection opens, which is where a server pushing updates keeps the
+/// clients it pushes to. Handlers calling the client back follow the same
+/// rules as on an `RpcPeer`.
+///
+/// Requests that are not a WebSocket upgrade are refused with an HTTP error.
+/// The request path is not checked.
+///
+/// # Examples
+///
+/// ```
+/// use ink_rpc::{RpcContext, RpcError, RpcRouter, WebSocketClient, WebSocketServer};
+/// use serde_json::json;
+/// use std::thread;
+///
+/// // The server greets callers by asking them for their name
+/// let mut router = RpcRouter::new();
+/// router.register_async_with_context("greet", |_: (), context: RpcContext| async move {
+///     let client = context.client().unwrap();
+///     let name = client.call("name", json!(null)).await.map_err(|error| {
+///         RpcError::new(-32000, error.to_string())
+///     })?;
+///     Ok::<_, RpcError>(format!("hello {}", name.as_str().unwrap_or("?")))
+/// });
+///
+/// let server = WebSocketServer::bind("127.0.0.1:0").unwrap();
+/// let addr = server.local_addr().unwrap();
+/// let handle = server.handle().unwrap();
+/// let running = thread::spawn(move || server.serve(&router));
+///
+/// let mut callbacks = RpcRouter::new();
+/// callbacks.register("name", |_: ()| Ok::<_, RpcError>("ink"));
+/// let connection = WebSocketClient::connect(&format!("ws://{}/rpc", addr), callbacks).unwrap();
+///
+/// let greeting = connection.client().call("greet", json!(null)).wait().unwrap();
+/// assert_eq!(greeting, json!("hello ink"));
+///
+/// connection.close();
+/// handle.shutdown();
+/// running.join().unwrap().unwrap();
+/// ```
+pub struct WebSocketServer {
+    listener: TcpListener,
+    max_message_size: usize,
+    ping_interval: Option<Duration>,
+    on_connect: Option<Box<ConnectHook>>,
+    connections: Arc<Connections<TcpStream>>,
+}
+
+impl WebSocketServer {
+    /// Binds a server.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on; port 0 picks a free port
+    ///
+    /// # Returns
+    ///
+    /// The server, or the error that prevented binding the address.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::from_listener(TcpListener::bind(addr)?))
+    }
+
+    /// Creates a server accepting connections from an existing listener.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The bound listener
+    pub fn from_listener(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            max_message_size: WebSocketTransport::DEFAULT_MAX_MESSAGE_SIZE,
+            ping_interval: None,
+            on_connect: None,
+            connections: Arc::new(Connections::new()),
+        }
+    }
+
+    /// Sets the maximum size of an inbound message on every connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_message_size` - The maximum size in bytes
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) -> &mut Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Sets how long a client may stay silent before it is pinged; a client
+    /// that does not answer within another interval is disconnected.
+    ///
+    /// # Arguments
+    ///
+    /// * `ping_interval` - The interval; `None` never pings
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_ping_interval(&mut self, ping_interval: Option<Duration>) -> &mut Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// Sets a hook run whenever a connection opens, before any of its
+    /// messages are read.
+    ///
+    /// The context holds the peer's address and a client for calling it, via
+    /// `RpcContext::client()`. The client fails its calls once the connection
+    /// closes, which `RpcClient::is_closed()` reports.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_connect` - The hook
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self, allowing for method chaining.
+    pub fn set_on_connect<F>(&mut self, on_connect: F) -> &mut Self
+    where
+        F: Fn(&RpcContext) + Send + Sync + 'static,
+    {
+        self.on_connect = Some(Box::new(on_connect));
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a handle that can shut the server down from another thread.
+    pub fn handle(&self) -> io::Result<TcpServerHandle> {
+        Ok(TcpServerHandle::new(
+            self.local_addr()?,
+            self.connections.clone(),
+        ))
+    }
+
+    /// Accepts connections and serves them until the server is shut down.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router dispatching requests on every connection
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The server was shut down and every session has ended
+    /// * `Err(io::Error)` - Accepting connections failed
+    pub fn serve(&self, router: &RpcRouter) -> io::Result<()> {
+        thread::scope(|scope| {
+            let result = loop {
+                let (socket, peer_addr) = match accept_next(|| self.listener.accept()) {
+                    Ok((socket, peer_addr)) => (Arc::new(socket), peer_addr),
+                    Err(error) => break Err(error),
+                };
+                let Some(id) = self.connections.track(&socket) else {
+                    break Ok(());
+                };
+
+                let connections = self.connections.clone();
+                scope.spawn(move || {
+                    self.serve_connection(socket.clone(), peer_addr, router);
+                    let _ = socket.shutdown(Shutdown::Both);
+                    connections.untrack(id);
+                });
+            };
+
+            self.connections.close_all();
+            result
+        })
+    }
+
+    /// Performs the handshake on one connection and serves it until it
+    /// closes.
+    fn serve_connection(&self, socket: Arc<TcpStream>, peer_addr: SocketAddr, router: &RpcRouter) {
+        let Ok(mut transport) = WebSocketTransport::accept_shared(socket) else {
+            return;
+        };
+        transport
+            .set_max_message_size(self.max_message_size)
+            .set_ping_interval(self.ping_interval);
+
+        let transport = Arc::new(transport);
+        let client = session_client(&transport, SequentialIdGenerator::new());
+        let mut context = RpcContext::new();
+        context.set_peer_addr(peer_addr).set_client(client.clone());
+
+        if let Some(on_connect) = &self.on_connect {
+            on_connect(&context);
+        }
+        run_session(&transport, router, &client, &context);
+        let _ = transport.close();
+    }
+}
+
+impl fmt::Debug for WebSocketServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketServer")
+            .field("local_addr", &self.listener.local_addr().ok())
+            .field("max_message_size", &self.max_message_size)
+            .field("ping_interval", &self.ping_interval)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// STRUCT: WebSocketClient
+// ===
+
+/// The client end of a WebSocket connection that also serves the server's
+/// calls.
+///
+/// Calls to the server go through `client()`. Requests and notifications
+/// from the server are dispatched through the router given when connecting,
+/// on a background thread. A client that only makes calls can instead run an
+/// `RpcClient` directly over a `WebSocketTransport`.
+///
+/// Dropping the connection starts the closing handshake without waiting for
+/// it; `close()` waits until the connection has closed.
+pub struct WebSocketClient {
+    client: RpcClient,
+    transport: Arc<WebSocketTransport>,
+    session: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl WebSocketClient {
+    /// Connects to a WebSocket server.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The endpoint, such as `ws://127.0.0.1:9000/rpc`
+    /// * `router` - The router dispatching the server's calls
+    ///
+    /// # Returns
+    ///
+    /// The connection, or the error that prevented connecting.
+    pub fn connect(url: &str, router: RpcRouter) -> io::Result<Self> {
+        Ok(Self::new(WebSocketTransport::connect(url)?, router))
+    }
+
+    /// Runs a connection over a transport that has completed its handshake,
+    /// such as one with a ping interval set.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The connected transport
+    /// * `router` - The router dispatching the server's calls
+    pub fn new(transport: WebSocketTransport, router: RpcRouter) -> Self {
+        let transport = Arc::new(transport);
+        let client = session_client(&transport, SequentialIdGenerator::new());
+
+        let session = {
+            let (transport, client) = (transport.clone(), client.clone());
+            thread::spawn(move || {
+                let mut context = RpcContext::new();
+                context
+                    .set_peer_addr(transport.peer_addr())
+                    .set_client(client.clone());
+                run_session(&transport, &router, &client, &context);
+            })
+        };
+
+        Self {
+            client,
+            transport,
+            session: Mutex::new(Some(session)),
+        }
+    }
+
+    /// Returns the client calling the server.
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// Returns the underlying transport.
+    pub fn transport(&self) -> &WebSocketTransport {
+        &self.transport
+    }
+
+    /// Closes the connection with `CloseFrame::NORMAL` and waits until the
+    /// server has answered, or the close timed out.
+    ///
+    /// Calls still pending fail with `ClientError::Disconnected`.
+    pub fn close(&self) {
+        let _ = self.transport.close();
+        if let Some(session) = self.session.lock().unwrap().take() {
+            let _ = session.join();
+        }
+    }
+}
+
+impl Drop for WebSocketClient {
+    fn drop(&mut self) {
+        let _ = self.transport.close();
+    }
+}
+
+impl fmt::Debug for WebSocketClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketClient")
+            .field("transport", &self.transport)
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+// ===========================================================================
+// TESTS: WebSocketTransport
+// ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientError, RpcError};
+    use serde_json::json;
+    use std::io::{Cursor, Read};
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    fn router() -> RpcRouter {
+        let mut router = RpcRouter::new();
+        router.register("add", |(a, b): (i64, i64)| Ok::<_, RpcError>(a + b));
+        router.register("echo", |params: serde_json::Value| {
+            Ok::<_, RpcError>(params)
+        });
+        router.register_async_with_context("greet", |_: (), context: RpcContext| async move {
+            let name = context
+                .client()
+                .unwrap()
+                .call("name", json!(null))
+                .await
+                .map_err(|error| RpcError::new(-32000, error.to_string()))?;
+            Ok::<_, RpcError>(format!("hello {}", name.as_str().unwrap()))
+        });
+        router
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Performs a handshake by hand, for tests that break the protocol.
+    fn raw_client(addr: SocketAddr) -> (TcpStream, BufReader<TcpStream>) {
+        let socket = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(socket.try_clone().unwrap());
+        write!(
+            &socket,
+            "GET / HTTP/1.1\r\nHost: x\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+
+        let head = read_head(&mut reader, MessageHead::read_response).unwrap();
+        assert_eq!(head.status(), Some(101));
+        (socket, reader)
+    }
+
+    // -----------------------------------------------------------------------
+    // Framing Tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_handshake_key() {
+        // The example from RFC 6455, section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        for length in [0, 125, 126, 65535, 65536] {
+            for mask in [None, Some([1, 2, 3, 4])] {
+                let payload: Vec<u8> = (0..length).map(|index| index as u8).collect();
+                let mut bytes = Vec::new();
+                write_frame(&mut bytes, OPCODE_TEXT, &payload, mask).unwrap();
+
+                let frame = read_frame(&mut Cursor::new(bytes), length, mask.is_some()).unwrap();
+                assert!(frame.fin);
+                assert_eq!(frame.opcode, OPCODE_TEXT);
+                assert_eq!(frame.payload, payload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_violations() {
+        let code = |bytes: &[u8], limit, masked| match read_frame(
+            &mut Cursor::new(bytes.to_vec()),
+            limit,
+            masked,
+        ) {
+            Err(FrameError::Violation { code, .. }) => code,
+            other => panic!("expected a violation, got {:?}", other),
+        };
+
+        // Unmasked frame from a client, and masked frame from a server
+        assert_eq!(code(&[0x81, 0x00], 10, true), CloseFrame::PROTOCOL_ERROR);
+        assert_eq!(code(&[0x81, 0x80, 0, 0, 0, 0], 10, false), 1002);
+        // Reserved bits, unknown opcode, fragmented control frame
+        assert_eq!(code(&[0xC1, 0x00], 10, false), 1002);
+        assert_eq!(code(&[0x83, 0x00], 10, false), 1002);
+        assert_eq!(code(&[0x09, 0x00], 10, false), 1002);
+        // Payload over the limit
+        assert_eq!(code(&[0x81, 0x0B], 10, false), CloseFrame::MESSAGE_TOO_BIG);
+    }
+
+    // -----------------------------------------------------------------------
+    // Connection Tests
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_calls_in_both_directions() {
+        let mut server = WebSocketServer::bind("127.0.0.1:0").unwrap();
+        let (pushed, welcomes) = mpsc::channel();
+        server.set_on_connect(|context| {
+            let client = context.client().unwrap();
+            client.notify("welcome", json!(["hi"])).unwrap();
+        });
+        let url = format!("ws://{}", server.local_addr().unwrap());
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            let mut callbacks = RpcRouter::new();
+            callbacks.register("name", |_: ()| Ok::<_, RpcError>("ink"));
+            callbacks.register("welcome", move |(text,): (String,)| {
+                pushed.send(text).unwrap();
+                Ok::<_, RpcError>(())
+            });
+            let connection = WebSocketClient::connect(&url, callbacks).unwrap();
+            let client = connection.client();
+
+            assert_eq!(welcomes.recv().unwrap(), "hi");
+            assert_eq!(client.call("add", json!([2, 3])).wait().unwrap(), json!(5));
+            assert_eq!(
+                client.call("greet", json!(null)).wait().unwrap(),
+                json!("hello ink")
+            );
+
+            // Messages that need a 64-bit frame length still arrive whole
+            let text = "x".repeat(70_000);
+            assert_eq!(
+                client.call("echo", json!([text])).wait().unwrap(),
+                json!([text])
+            );
+            assert!(matches!(
+                client.call("missing", json!(null)).wait(),
+                Err(ClientError::Rpc(error)) if error.code() == -32601
+            ));
+
+            // The server echoes the close code of the client
+            connection.transport().close_with(4000, "bye").unwrap();
+            connection.close();
+            assert_eq!(
+                connection.transport().peer_close(),
+                Some(CloseFrame::new(4000, "bye"))
+            );
+            assert!(client.is_closed());
+            wait_for(|| handle.connection_count() == 0);
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_ping_keepalive() {
+        let mut server = WebSocketServer::bind("127.0.0.1:0").unwrap();
+        server.set_ping_interval(Some(Duration::from_millis(50)));
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            // A client that answers pings stays connected
+            let client =
+                RpcClient::connect(WebSocketTransport::connect(&format!("ws://{}", addr)).unwrap());
+            thread::sleep(Duration::from_millis(200));
+            assert_eq!(client.call("add", json!([1, 1])).wait().unwrap(), json!(2));
+
+            // A silent client is pinged, then dropped
+            let (_socket, mut reader) = raw_client(addr);
+            let ping = read_frame(&mut reader, 125, false).unwrap();
+            assert_eq!(ping.opcode, OPCODE_PING);
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).unwrap();
+            assert!(rest.is_empty());
+
+            client.close();
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_protocol_errors_close_the_connection() {
+        let mut server = WebSocketServer::bind("127.0.0.1:0").unwrap();
+        server.set_max_message_size(64);
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle().unwrap();
+        let router = router();
+
+        let close_code = |frame: Frame| {
+            assert_eq!(frame.opcode, OPCODE_CLOSE);
+            u16::from_be_bytes([frame.payload[0], frame.payload[1]])
+        };
+
+        thread::scope(|scope| {
+            let running = scope.spawn(|| server.serve(&router));
+
+            // An unmasked frame from a client
+            let (mut socket, mut reader) = raw_client(addr);
+            write_frame(&mut socket, OPCODE_TEXT, b"{}", None).unwrap();
+            let frame = read_frame(&mut reader, 125, false).unwrap();
+            assert_eq!(close_code(frame), CloseFrame::PROTOCOL_ERROR);
+
+            // A message over the size limit
+            let (mut socket, mut reader) = raw_client(addr);
+            write_frame(&mut socket, OPCODE_TEXT, &[b' '; 65], Some([9; 4])).unwrap();
+            let frame = read_frame(&mut reader, 125, false).unwrap();
+            assert_eq!(close_code(frame), CloseFrame::MESSAGE_TOO_BIG);
+
+            // A binary message
+            let (mut socket, mut reader) = raw_client(addr);
+            write_frame(&mut socket, OPCODE_BINARY, b"{}", Some([9; 4])).unwrap();
+            let frame = read_frame(&mut reader, 125, false).unwrap();
+            assert_eq!(close_code(frame), CloseFrame::UNSUPPORTED_DATA);
+
+            // A plain HTTP request is refused
+            let socket = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(socket.try_clone().unwrap());
+            write!(&socket, "GET / HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+            let head = read_head(&mut reader, MessageHead::read_response).unwrap();
+            assert_eq!(head.status(), Some(426));
+
+            handle.shutdown();
+            running.join().unwrap().unwrap();
+        });
+    }
+}